@@ -33,6 +33,10 @@ fn main() {
 
     args.process(|a| {
         let settings = Settings::from_args(a);
-        flow::core::runner::execute(settings);
+        if settings.print {
+            flow::core::headless::execute(settings);
+        } else {
+            flow::core::runner::execute(settings);
+        }
     });
 }
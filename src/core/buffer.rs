@@ -17,29 +17,541 @@
  */
 
 use std::cmp::{min, max};
-use std::cell::Cell;
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::{VecDeque, HashSet, HashMap, BTreeMap};
 
-use core::line::{Line, LineCollection, Parser as LineParser};
+use time;
+
+use core::line::{self, Line, LineCollection, Parser as LineParser};
 use core::filter::Filter;
+use core::collapse::{Collapse, Repetition, Run};
+use core::rate::RateMeter;
 
 static DEFAULT_REVERSE_INDEX: usize = 0;
-static MAX_LINES_RENDERED: usize = 2_000;
 
 pub struct Buffer {
     pub filter: Filter,
     pub reverse_index: Cell<usize>,
+    pub match_count: Cell<usize>,
+    pub unread_count: Cell<usize>,
+    first_unread_id: Cell<usize>,
+    index: RefCell<VecDeque<usize>>,
+    next_indexed_id: Cell<usize>,
+    unterminated: RefCell<HashSet<usize>>,
+    /// Start id and time of the blocks missing their end that will be shown
+    /// as unterminated once past a limit, oldest first.
+    open_blocks: RefCell<Vec<(usize, i64)>>,
+    groups: RefCell<Groups>,
+    collapse: Cell<Option<Collapse>>,
+    /// Runs of repeated lines making up the index while collapsed.
+    runs: RefCell<VecDeque<Run>>,
+    rows: RefCell<Rows>,
+    pub rate: RefCell<RateMeter>,
 }
 
 impl Buffer {
     pub fn new(filter: Filter) -> Buffer {
+        let collapse = filter.collapse;
+
         Buffer {
             filter: filter,
             reverse_index: Cell::new(DEFAULT_REVERSE_INDEX),
+            match_count: Cell::new(0),
+            unread_count: Cell::new(0),
+            first_unread_id: Cell::new(0),
+            index: RefCell::new(VecDeque::new()),
+            next_indexed_id: Cell::new(0),
+            unterminated: RefCell::new(HashSet::new()),
+            open_blocks: RefCell::new(vec![]),
+            groups: RefCell::new(Groups::default()),
+            collapse: Cell::new(collapse),
+            runs: RefCell::new(VecDeque::new()),
+            rows: RefCell::new(Rows::default()),
+            rate: RefCell::new(RateMeter::default()),
+        }
+    }
+
+    /// Brings the matching line ids up to date with the collection. Only the
+    /// lines received since the last call are looked at, and ids of evicted
+    /// lines are dropped. Returns the position from which the index changed,
+    /// before the evicted ids were dropped.
+    pub fn update_index(&self, lines: &LineCollection) -> usize {
+        let indexed_count = self.index.borrow().len();
+        let changed_from = if self.filter.has_boundaries() && self.filter.group_by.is_some() {
+            self.index_groups(lines)
+        } else if self.filter.has_boundaries() {
+            self.index_blocks(lines)
+        } else {
+            self.index_new_lines(lines)
+        };
+        let shown_changed_from = match self.collapse.get() {
+            Some(_) => self.update_runs(lines, indexed_count, changed_from),
+            None => changed_from,
+        };
+        self.rows.borrow_mut().truncate(shown_changed_from);
+        self.drop_evicted_lines(lines);
+
+        self.next_indexed_id.set(lines.next_id());
+        self.match_count.set(self.index.borrow().len());
+
+        changed_from
+    }
+
+    /// Counts the matches among the lines received starting with the given id.
+    pub fn record_rate(&self, first_new_id: usize, now: i64) {
+        let count = self.index.borrow().iter().rev().take_while(|id| **id >= first_new_id).count();
+        self.rate.borrow_mut().record(count, now);
+    }
+
+    /// Each of the ways to index lines returns the position from which the
+    /// index changed.
+    fn index_new_lines(&self, lines: &LineCollection) -> usize {
+        let offset = self.next_indexed_id.get().saturating_sub(lines.first_id());
+        let mut matches = lines.entries
+            .iter()
+            .skip(offset)
+            .parse(&self.filter)
+            .map(|line| line.id)
+            .collect::<Vec<_>>();
+        matches.reverse();
+
+        let mut index = self.index.borrow_mut();
+        let indexed_count = index.len();
+        self.splice_index(&mut index, indexed_count, matches);
+
+        indexed_count
+    }
+
+    /// New lines can complete, extend or invalidate the most recent blocks.
+    /// Blocks are parsed again from the newest line until one turns up that
+    /// predates the new lines and is already indexed; everything older than
+    /// it stays as it is. Blocks left open that have since run past a limit
+    /// are parsed again as well.
+    fn index_blocks(&self, lines: &LineCollection) -> usize {
+        let next_indexed_id = self.next_indexed_id.get();
+        let converge_before = match self.oldest_overdue_start(lines) {
+            Some(id) => min(id, next_indexed_id),
+            None => next_indexed_id,
+        };
+        let mut index = self.index.borrow_mut();
+        let mut parser = lines.entries.iter().parse(&self.filter);
+        let mut blocks = vec![];
+        let mut unterminated_ids = vec![];
+        let mut retained = 0;
+
+        while let Some(block) = parser.next_block() {
+            let mut ids = block.iter().map(|line| line.id).collect::<Vec<_>>();
+            ids.reverse();
+
+            if ids[ids.len() - 1] < converge_before {
+                if let Some(position) = find_block(&index, &ids) {
+                    retained = position + ids.len();
+                    break;
+                }
+            }
+            if parser.is_unterminated() {
+                unterminated_ids.push(ids[ids.len() - 1]);
+            }
+            blocks.push(ids);
+        }
+
+        // Whatever is newer than the retained blocks was just parsed again
+        let last_retained_id = if retained > 0 { Some(index[retained - 1]) } else { None };
+        let is_retained = |id: usize| last_retained_id.map_or(false, |last_id| id <= last_id);
+
+        let mut unterminated = self.unterminated.borrow_mut();
+        unterminated.retain(|id| is_retained(*id));
+        unterminated.extend(unterminated_ids);
+
+        let first_id = lines.first_id();
+        let mut open_blocks = self.open_blocks.borrow_mut();
+        open_blocks.retain(|&(id, _)| id >= first_id && is_retained(id));
+        open_blocks.extend(parser.open_blocks()
+            .iter()
+            .rev()
+            .map(|line| (line.id, line.received_at)));
+
+        self.splice_index(&mut index, retained, blocks.into_iter().rev().flat_map(|ids| ids));
+
+        retained
+    }
+
+    /// Grouped blocks interleave, so each group's lines are kept apart and
+    /// only the groups that received new lines, lost evicted ones, or have a
+    /// block left open that has since run past a limit, are parsed again. The
+    /// index is rebuilt from the oldest block that came out different.
+    fn index_groups(&self, lines: &LineCollection) -> usize {
+        let first_id = lines.first_id();
+        let newest_id = lines.next_id().checked_sub(1);
+        let offset = self.next_indexed_id.get().saturating_sub(first_id);
+        let mut groups = self.groups.borrow_mut();
+        let Groups { ref mut items, ref mut blocks, ref mut open_blocks, ref mut first_ids } =
+            *groups;
+        let mut unterminated = self.unterminated.borrow_mut();
+        let mut changed_keys = HashSet::new();
+
+        for line in lines.entries.iter().skip(offset) {
+            if let Some(key) = self.filter.group_key(&line.content_without_ansi) {
+                let group = items.entry(key.to_string()).or_insert_with(Group::default);
+                if group.ids.is_empty() {
+                    first_ids.insert(line.id, key.to_string());
+                }
+                group.ids.push(line.id);
+                changed_keys.insert(key.to_string());
+            }
+        }
+        for (&id, &(received_at, ref key)) in open_blocks.iter() {
+            if !self.is_overdue_start(id, received_at, lines) {
+                break;
+            }
+            changed_keys.insert(key.clone());
+        }
+
+        // Groups that lost lines are parsed again, or dropped once none are left
+        let evicted_keys = first_ids.range(..first_id)
+            .map(|(&id, key)| (id, key.clone()))
+            .collect::<Vec<_>>();
+        for (id, key) in evicted_keys {
+            first_ids.remove(&id);
+            let group = items.get_mut(&key).unwrap();
+            group.ids.retain(|id| *id >= first_id);
+            if let Some(&id) = group.ids.first() {
+                first_ids.insert(id, key.clone());
+            }
+            changed_keys.insert(key);
         }
+
+        let mut index = self.index.borrow_mut();
+        let mut oldest_changed_key = None;
+        {
+            let mut mark_changed = |block_key: usize| {
+                // Evicted blocks are at the front of the index, they're dropped
+                // along with the rest of the evicted lines
+                if block_key >= first_id {
+                    oldest_changed_key = Some(oldest_changed_key.map_or(block_key, |key| {
+                        min(key, block_key)
+                    }));
+                }
+            };
+
+            for key in changed_keys {
+                let is_empty = {
+                    let group = items.get_mut(&key).unwrap();
+                    let mut previous_blocks = HashMap::new();
+                    for block_key in group.block_keys.drain(..) {
+                        let ids = blocks.remove(&block_key).unwrap();
+                        let was_unterminated = unterminated.remove(&block_key);
+                        previous_blocks.insert(block_key, (ids, was_unterminated));
+                    }
+                    for id in group.open_ids.drain(..) {
+                        open_blocks.remove(&id);
+                    }
+
+                    let stream = group.ids
+                        .iter()
+                        .rev()
+                        .filter_map(|id| lines.get_by_id(*id))
+                        .collect::<Vec<_>>();
+                    let (group_blocks, group_open_blocks) =
+                        line::parse_group(stream, &self.filter, newest_id);
+
+                    for (block, is_unterminated) in group_blocks {
+                        let ids = block.iter().map(|line| line.id).collect::<Vec<_>>();
+                        let block_key = ids[ids.len() - 1];
+                        // Blocks keeping their lines can still gain or lose their marker
+                        let is_unchanged = previous_blocks.remove(&block_key)
+                            .map_or(false, |(previous_ids, was_unterminated)| {
+                                previous_ids == ids && was_unterminated == is_unterminated
+                            });
+                        if !is_unchanged {
+                            mark_changed(block_key);
+                        }
+                        if is_unterminated {
+                            unterminated.insert(block_key);
+                        }
+                        group.block_keys.push(block_key);
+                        blocks.insert(block_key, ids);
+                    }
+                    for block_key in previous_blocks.keys() {
+                        mark_changed(*block_key);
+                    }
+                    for start in group_open_blocks {
+                        group.open_ids.push(start.id);
+                        open_blocks.insert(start.id, (start.received_at, key.clone()));
+                    }
+
+                    group.ids.is_empty()
+                };
+
+                if is_empty {
+                    items.remove(&key);
+                }
+            }
+        }
+
+        let oldest_changed_key = match oldest_changed_key {
+            Some(value) => value,
+            None => return index.len(),
+        };
+        // Blocks are indexed in the order of their keys, each ending with its key
+        let changed_from = match blocks.range(..oldest_changed_key).next_back() {
+            Some((&block_key, _)) => index.iter().rposition(|id| *id == block_key).unwrap() + 1,
+            None => 0,
+        };
+        let ids = blocks.range(oldest_changed_key..)
+            .flat_map(|(_, ids)| ids.iter().cloned())
+            .collect::<Vec<_>>();
+        self.splice_index(&mut index, changed_from, ids);
+
+        changed_from
+    }
+
+    /// Replaces the ids from the given position on, keeping the unread count
+    /// in step with the ids taken out and added.
+    fn splice_index<I>(&self, index: &mut VecDeque<usize>, position: usize, ids: I)
+        where I: IntoIterator<Item = usize>
+    {
+        let first_unread_id = self.first_unread_id.get();
+        let removed = index.iter().skip(position).filter(|id| **id >= first_unread_id).count();
+        index.truncate(position);
+
+        let mut added = 0;
+        for id in ids {
+            if id >= first_unread_id {
+                added += 1;
+            }
+            index.push_back(id);
+        }
+
+        self.unread_count.set(self.unread_count.get() + added - removed);
+    }
+
+    /// Grouped blocks losing lines are parsed again, so evicted lines are
+    /// always at the front of the index.
+    fn drop_evicted_lines(&self, lines: &LineCollection) {
+        let first_id = lines.first_id();
+        let first_unread_id = self.first_unread_id.get();
+        let mut evicted = 0;
+        {
+            let mut index = self.index.borrow_mut();
+            while index.front().map_or(false, |id| *id < first_id) {
+                if index.pop_front().unwrap() >= first_unread_id {
+                    self.unread_count.set(self.unread_count.get() - 1);
+                }
+                evicted += 1;
+            }
+        }
+        if evicted == 0 {
+            return;
+        }
+
+        if self.collapse.get().is_none() {
+            self.rows.borrow_mut().drop_front(evicted);
+            return;
+        }
+
+        let mut dropped_runs = 0;
+        let mut is_first_run_cut = false;
+        {
+            let index = self.index.borrow();
+            let mut runs = self.runs.borrow_mut();
+            while evicted > 0 && !runs.is_empty() {
+                if runs[0].count <= evicted {
+                    evicted -= runs[0].count;
+                    runs.pop_front();
+                    dropped_runs += 1;
+                } else {
+                    runs[0].count -= evicted;
+                    runs[0].first_id = index[0];
+                    is_first_run_cut = true;
+                    evicted = 0;
+                }
+            }
+        }
+
+        let mut rows = self.rows.borrow_mut();
+        rows.drop_front(dropped_runs);
+        if is_first_run_cut && rows.len() > 0 {
+            let height = self.measure(0, lines, rows.width);
+            rows.resize_first(height);
+        }
+    }
+
+    /// Brings the runs of repeated lines in line with the index, which held
+    /// the given amount of ids and changed starting with the given position.
+    /// Returns the position of the first run that may have changed.
+    fn update_runs(&self,
+                   lines: &LineCollection,
+                   indexed_count: usize,
+                   changed_from: usize)
+                   -> usize {
+        let collapse = self.collapse.get().unwrap();
+        let index = self.index.borrow();
+        let mut runs = self.runs.borrow_mut();
+
+        let mut covered = indexed_count;
+        while covered > changed_from {
+            let excess = covered - changed_from;
+            let run = runs.back_mut().unwrap();
+            if run.count > excess {
+                run.count -= excess;
+                run.last_id = index[changed_from - 1];
+                break;
+            }
+            covered -= run.count;
+            runs.pop_back();
+        }
+
+        // The last run left may take in the new lines
+        let first_changed = runs.len().saturating_sub(1);
+        let new_lines = index.iter().skip(changed_from).filter_map(|id| lines.get_by_id(*id));
+        collapse.extend_runs(&mut runs, new_lines);
+
+        first_changed
+    }
+
+    /// Lines as they're shown, one for each run of repeated lines while
+    /// collapsed.
+    fn shown_count(&self) -> usize {
+        match self.collapse.get() {
+            Some(_) => self.runs.borrow().len(),
+            None => self.index.borrow().len(),
+        }
+    }
+
+    fn repetition_at<'a>(&self,
+                         position: usize,
+                         lines: &'a LineCollection)
+                         -> Option<Repetition<'a>> {
+        match self.collapse.get() {
+            Some(_) => {
+                self.runs.borrow().get(position).and_then(|run| {
+                    match (lines.get_by_id(run.first_id), lines.get_by_id(run.last_id)) {
+                        (Some(first), Some(last)) => {
+                            Some(Repetition {
+                                first: first,
+                                last: last,
+                                count: run.count,
+                            })
+                        }
+                        _ => None,
+                    }
+                })
+            }
+            None => {
+                self.index
+                    .borrow()
+                    .get(position)
+                    .and_then(|id| lines.get_by_id(*id))
+                    .map(Repetition::new)
+            }
+        }
+    }
+
+    /// Rows the shown line takes up, along with the marker following the
+    /// last line of a block that never got its end.
+    fn measure(&self, position: usize, lines: &LineCollection, width: usize) -> i32 {
+        self.repetition_at(position, lines).map_or(0, |repetition| {
+            let height = repetition.guess_height(width) as i32;
+            if self.ends_unterminated_block(repetition.last.id) {
+                height + 1
+            } else {
+                height
+            }
+        })
+    }
+
+    /// Measures the shown lines that weren't yet at the given width.
+    fn lay_out(&self, lines: &LineCollection, width: usize) {
+        let mut rows = self.rows.borrow_mut();
+        if rows.width != width {
+            *rows = Rows::new(width);
+        }
+
+        for position in rows.len()..self.shown_count() {
+            let height = self.measure(position, lines, width);
+            rows.push(height);
+        }
+    }
+
+    fn is_overdue_start(&self, id: usize, received_at: i64, lines: &LineCollection) -> bool {
+        let newest_id = lines.next_id().saturating_sub(1);
+
+        self.filter.is_past_limits(newest_id - id, time::get_time().sec - received_at)
+    }
+
+    /// Blocks left open run past their limits oldest first, so only the
+    /// oldest one needs to be looked at.
+    fn oldest_overdue_start(&self, lines: &LineCollection) -> Option<usize> {
+        self.open_blocks
+            .borrow()
+            .first()
+            .and_then(|&(id, received_at)| {
+                if self.is_overdue_start(id, received_at, lines) {
+                    Some(id)
+                } else {
+                    None
+                }
+            })
+    }
+
+    /// Whether a block left open has run past its limit since the index was
+    /// last updated.
+    pub fn has_overdue_blocks(&self, lines: &LineCollection) -> bool {
+        if self.filter.group_by.is_none() {
+            return self.oldest_overdue_start(lines).is_some();
+        }
+
+        match self.groups.borrow().open_blocks.iter().next() {
+            Some((&id, &(received_at, _))) => self.is_overdue_start(id, received_at, lines),
+            None => false,
+        }
+    }
+
+    /// Whether an unterminated block ends with the given line.
+    pub fn ends_unterminated_block(&self, id: usize) -> bool {
+        self.unterminated.borrow().contains(&id)
+    }
+
+    pub fn unterminated_count(&self) -> usize {
+        self.unterminated.borrow().len()
+    }
+
+    pub fn collapse(&self) -> Option<Collapse> {
+        self.collapse.get()
+    }
+
+    /// Filters without a collapse mode of their own collapse similar lines.
+    pub fn toggle_collapse(&self, lines: &LineCollection) {
+        let collapse = match self.collapse.get() {
+            Some(_) => None,
+            None => Some(self.filter.collapse.unwrap_or(Collapse::Similar)),
+        };
+        self.collapse.set(collapse);
+
+        self.runs.borrow_mut().clear();
+        if collapse.is_some() {
+            self.update_runs(lines, 0, 0);
+        }
+        self.rows.borrow_mut().truncate(0);
+    }
+
+    pub fn mark_as_read(&self, lines: &LineCollection) {
+        self.first_unread_id.set(lines.next_id());
+        self.unread_count.set(0);
     }
 
     pub fn with_lines<'a>(&'a self, lines: &'a LineCollection) -> BufferLines<'a> {
-        BufferLines::new(self, lines)
+        BufferLines::new(self, lines, None)
+    }
+
+    /// Leaves out the lines received starting with the given id, such as the
+    /// ones that arrived while the view is paused.
+    pub fn with_lines_before<'a>(&'a self,
+                                 lines: &'a LineCollection,
+                                 next_id: Option<usize>)
+                                 -> BufferLines<'a> {
+        BufferLines::new(self, lines, next_id)
     }
 
     pub fn increment_reverse_index(&self, value: i32, max_value: usize) {
@@ -62,39 +574,155 @@ impl Buffer {
 pub struct BufferLines<'a> {
     lines: &'a LineCollection,
     pub buffer: &'a Buffer,
-    pub width: usize,
+    next_id: Option<usize>,
 }
 
 impl<'a> BufferLines<'a> {
-    fn new(buffer: &'a Buffer, lines: &'a LineCollection) -> BufferLines<'a> {
+    fn new(buffer: &'a Buffer,
+           lines: &'a LineCollection,
+           next_id: Option<usize>)
+           -> BufferLines<'a> {
         BufferLines {
             buffer: buffer,
             lines: lines,
-            width: 0,
+            next_id: next_id,
+        }
+    }
+
+    /// Lines as they're shown, one for each run of repeated lines while the
+    /// buffer is collapsed. Runs may take in lines left out of the view, in
+    /// which case they're worked out again from the lines shown.
+    pub fn repetitions(&'a self) -> Vec<Repetition<'a>> {
+        match self.buffer.collapse() {
+            Some(collapse) if self.leaves_out_lines() => collapse.repetitions(self),
+            Some(_) => {
+                (0..self.buffer.shown_count())
+                    .filter_map(|position| self.buffer.repetition_at(position, self.lines))
+                    .collect()
+            }
+            None => self.into_iter().map(Repetition::new).collect(),
+        }
+    }
+
+    /// Whether some of the indexed lines were received past the given id.
+    /// The newest indexed line is always the last one, as blocks are indexed
+    /// in the order of the line that completes them.
+    fn leaves_out_lines(&self) -> bool {
+        let index = self.buffer.index.borrow();
+        match (self.next_id, index.back()) {
+            (Some(next_id), Some(&last_id)) => last_id >= next_id,
+            _ => false,
+        }
+    }
+
+    /// Measures the shown lines at the given width, so the ones around any
+    /// row can be looked up. Lines left out of the view aren't measured,
+    /// which is told by returning false.
+    pub fn lay_out(&self, width: usize) -> bool {
+        if self.leaves_out_lines() {
+            return false;
+        }
+
+        self.buffer.lay_out(self.lines, width);
+        true
+    }
+
+    /// How many lines are shown, once laid out.
+    pub fn len(&self) -> usize {
+        self.buffer.rows.borrow().len()
+    }
+
+    pub fn repetition(&self, position: usize) -> Option<Repetition<'a>> {
+        self.buffer.repetition_at(position, self.lines)
+    }
+
+    /// Rows taken up by the lines once laid out.
+    pub fn height(&self) -> i32 {
+        let rows = self.buffer.rows.borrow();
+        rows.row_of(rows.len())
+    }
+
+    /// Row the line at the given position starts on, once laid out.
+    pub fn row_of(&self, position: usize) -> i32 {
+        self.buffer.rows.borrow().row_of(position)
+    }
+
+    /// Position of the line reaching past the given row, once laid out.
+    pub fn position_at(&self, row: i32) -> usize {
+        self.buffer.rows.borrow().position_at(row)
+    }
+
+    /// Height taken by the lines preceding the first one received starting
+    /// with the given id, once laid out.
+    pub fn height_before(&self, id: usize) -> i32 {
+        let position = (0..self.len())
+            .position(|position| self.first_id_at(position) >= id)
+            .unwrap_or(self.len());
+        self.row_of(position)
+    }
+
+    /// Height taken by the lines received starting with the given id, once
+    /// laid out.
+    pub fn height_since(&self, id: usize) -> i32 {
+        let count = (0..self.len())
+            .rev()
+            .take_while(|position| self.first_id_at(*position) >= id)
+            .count();
+        self.height() - self.row_of(self.len() - count)
+    }
+
+    /// Position of the line, or of the run, starting with the given id, once
+    /// laid out.
+    pub fn position_of(&self, id: usize) -> Option<usize> {
+        (0..self.len()).position(|position| self.first_id_at(position) == id)
+    }
+
+    fn first_id_at(&self, position: usize) -> usize {
+        match self.buffer.collapse() {
+            Some(_) => self.buffer.runs.borrow()[position].first_id,
+            None => self.buffer.index.borrow()[position],
         }
     }
 }
 
 impl<'a> IntoIterator for &'a BufferLines<'a> {
     type Item = &'a Line;
-    type IntoIter = ::std::vec::IntoIter<&'a Line>;
+    type IntoIter = Iter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        let mut estimated_height = 0;
+        Iter {
+            ids: self.buffer.index.borrow(),
+            position: 0,
+            lines: self.lines,
+            next_id: self.next_id,
+        }
+    }
+}
 
-        let height_within_boundary = |line: &&Line| -> bool {
-            estimated_height += line.guess_height(self.width);
-            estimated_height <= MAX_LINES_RENDERED
-        };
+/// Indexed lines, oldest first, looked up as they're iterated over.
+pub struct Iter<'a> {
+    ids: Ref<'a, VecDeque<usize>>,
+    position: usize,
+    lines: &'a LineCollection,
+    next_id: Option<usize>,
+}
 
-        let mut lines = self.lines
-            .entries
-            .iter()
-            .parse(self.buffer.filter.clone())
-            .take_while(height_within_boundary)
-            .collect::<Vec<_>>();
-        lines.reverse();
-        lines.into_iter()
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Line;
+
+    fn next(&mut self) -> Option<&'a Line> {
+        while let Some(&id) = self.ids.get(self.position) {
+            self.position += 1;
+
+            if self.next_id.map_or(false, |next_id| id >= next_id) {
+                continue;
+            }
+            if let Some(line) = self.lines.get_by_id(id) {
+                return Some(line);
+            }
+        }
+
+        None
     }
 }
 
@@ -117,6 +745,82 @@ impl BufferCollection {
         self.items.get(self.index).unwrap()
     }
 
+    pub fn iter<'a>(&'a self) -> ::std::slice::Iter<'a, Buffer> {
+        self.items.iter()
+    }
+
+    /// Lines arriving in the selected buffer are seen right away, so they
+    /// never count as unread.
+    pub fn update_indexes(&self, lines: &LineCollection) {
+        self.selected_item().mark_as_read(lines);
+
+        for item in &self.items {
+            item.update_index(lines);
+        }
+    }
+
+    /// Blocks can run past their timeout while no lines are being received.
+    /// Returns whether the selected buffer has changed.
+    pub fn refresh_overdue_blocks(&self, lines: &LineCollection) -> bool {
+        let mut is_selected_changed = false;
+
+        for (index, item) in self.items.iter().enumerate() {
+            if item.filter.block_timeout.is_some() && item.filter.tracks_unterminated() &&
+               item.has_overdue_blocks(lines) {
+                let previous_count = item.unterminated_count();
+                item.update_index(lines);
+                if index == self.index && item.unterminated_count() != previous_count {
+                    is_selected_changed = true;
+                }
+            }
+        }
+
+        is_selected_changed
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn position_of(&self, name: &str) -> Option<usize> {
+        self.items.iter().position(|item| item.filter.name == name)
+    }
+
+    /// Tabs opened at runtime rather than read from the config.
+    pub fn position_of_virtual(&self, name: &str) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.filter.is_virtual() && item.filter.name == name)
+    }
+
+    pub fn add(&mut self, buffer: Buffer) {
+        self.items.push(buffer);
+    }
+
+    /// Only virtual tabs can be removed, the ones from the config stay.
+    pub fn remove_selected(&mut self) -> bool {
+        if !self.selected_item().filter.is_virtual() {
+            return false;
+        }
+
+        self.items.remove(self.index);
+        if self.index == self.items.len() {
+            self.index -= 1;
+        }
+
+        true
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.index = index;
+        }
+    }
+
     pub fn select_previous(&mut self) {
         if self.index > 0 {
             self.index -= 1;
@@ -129,3 +833,112 @@ impl BufferCollection {
         }
     }
 }
+
+/// Rows the shown lines take up once wrapped, accumulated, so the ones
+/// around any row can be found without measuring every line.
+#[derive(Default)]
+struct Rows {
+    width: usize,
+    /// Row following each line, counted from `base` so that dropping the
+    /// oldest lines leaves the rest untouched.
+    ends: VecDeque<i32>,
+    base: i32,
+}
+
+impl Rows {
+    fn new(width: usize) -> Rows {
+        Rows {
+            width: width,
+            ends: VecDeque::new(),
+            base: 0,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    fn push(&mut self, height: i32) {
+        let end = self.ends.back().cloned().unwrap_or(self.base) + height;
+        self.ends.push_back(end);
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.ends.truncate(len);
+    }
+
+    fn drop_front(&mut self, count: usize) {
+        for _ in 0..count {
+            match self.ends.pop_front() {
+                Some(end) => self.base = end,
+                None => break,
+            }
+        }
+    }
+
+    /// The rows of every line shift along with the first one.
+    fn resize_first(&mut self, height: i32) {
+        let previous_height = self.row_of(1);
+        self.base -= height - previous_height;
+    }
+
+    fn row_of(&self, position: usize) -> i32 {
+        if position == 0 {
+            0
+        } else {
+            self.ends[position - 1] - self.base
+        }
+    }
+
+    fn position_at(&self, row: i32) -> usize {
+        let (mut low, mut high) = (0, self.ends.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            if self.ends[middle] - self.base > row {
+                high = middle;
+            } else {
+                low = middle + 1;
+            }
+        }
+
+        low
+    }
+}
+
+/// What's kept of interleaved blocks between updates, so that only the
+/// groups receiving new lines are parsed again.
+#[derive(Default)]
+struct Groups {
+    items: HashMap<String, Group>,
+    /// Ids of every block, oldest first, keyed by the id of the newest one.
+    blocks: BTreeMap<usize, Vec<usize>>,
+    /// Keys of the groups, by the id of their oldest line, so that the ones
+    /// losing evicted lines are found without going through every group.
+    first_ids: BTreeMap<usize, String>,
+    /// Start time and group key of the blocks missing their end that will be
+    /// shown as unterminated once past a limit, keyed by their start id.
+    open_blocks: BTreeMap<usize, (i64, String)>,
+}
+
+#[derive(Default)]
+struct Group {
+    /// Ids of the lines having the group's key, oldest first.
+    ids: Vec<usize>,
+    /// Keys of the group's blocks in `Groups::blocks`.
+    block_keys: Vec<usize>,
+    /// Keys of the group's blocks in `Groups::open_blocks`.
+    open_ids: Vec<usize>,
+}
+
+fn find_block(index: &VecDeque<usize>, ids: &[usize]) -> Option<usize> {
+    let position = match index.iter().rposition(|id| *id == ids[0]) {
+        Some(value) => value,
+        None => return None,
+    };
+
+    if index.iter().skip(position).take(ids.len()).eq(ids.iter()) {
+        Some(position)
+    } else {
+        None
+    }
+}
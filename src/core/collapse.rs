@@ -0,0 +1,180 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+use regex::Regex;
+use time::{self, Timespec};
+
+use core::line::Line;
+
+lazy_static! {
+    // Timestamps, uuids, hex ids and numbers, in order of precedence
+    static ref VARIABLE_TOKENS: Regex = Regex::new(concat!(
+        r"(?i)\d{4}-\d{2}-\d{2}[t ]\d{2}:\d{2}:\d{2}(?:[.,]\d+)?(?:z|[+-]\d{2}:?\d{2})?",
+        r"|\b[0-9a-f]{8}(?:-[0-9a-f]{4}){3}-[0-9a-f]{12}\b",
+        r"|\b0x[0-9a-f]+\b|\b[0-9a-f]{8,}\b|\d+"
+    )).unwrap();
+}
+
+/// Tells which consecutive lines are shown as a single one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Collapse {
+    /// Lines having the exact same content.
+    Identical,
+    /// Lines that only differ by their timestamps, ids or numbers.
+    Similar,
+}
+
+impl Collapse {
+    pub fn from_name(name: &str) -> Option<Collapse> {
+        match name {
+            "identical" => Some(Collapse::Identical),
+            "similar" => Some(Collapse::Similar),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Collapse::Identical => "identical",
+            Collapse::Similar => "similar",
+        }
+    }
+
+    pub fn key<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        match *self {
+            Collapse::Identical => Cow::Borrowed(text),
+            Collapse::Similar => mask(text),
+        }
+    }
+
+    /// Groups runs of lines sharing the same key, keeping their order.
+    pub fn repetitions<'a, I>(&self, lines: I) -> Vec<Repetition<'a>>
+        where I: IntoIterator<Item = &'a Line>
+    {
+        let mut repetitions: Vec<Repetition<'a>> = vec![];
+        let mut previous_key = None;
+
+        for line in lines {
+            let key = self.key(&line.content_without_ansi);
+
+            if previous_key.as_ref() == Some(&key) {
+                let repetition = repetitions.last_mut().unwrap();
+                repetition.last = line;
+                repetition.count += 1;
+            } else {
+                repetitions.push(Repetition::new(line));
+                previous_key = Some(key);
+            }
+        }
+
+        repetitions
+    }
+
+    /// Adds the lines following the ones the runs were made of, working out
+    /// the key of each line only once.
+    pub fn extend_runs<'a, I>(&self, runs: &mut VecDeque<Run>, lines: I)
+        where I: IntoIterator<Item = &'a Line>
+    {
+        for line in lines {
+            let key = self.key(&line.content_without_ansi);
+
+            if runs.back().map_or(false, |run| run.key == key) {
+                let run = runs.back_mut().unwrap();
+                run.last_id = line.id;
+                run.count += 1;
+            } else {
+                runs.push_back(Run::new(line.id, key.into_owned()));
+            }
+        }
+    }
+}
+
+/// Replaces the parts of a line that usually change between otherwise
+/// identical messages with a `#`.
+pub fn mask<'t>(text: &'t str) -> Cow<'t, str> {
+    VARIABLE_TOKENS.replace_all(text, "#")
+}
+
+/// Ids of consecutive lines considered the same, kept by collapsed buffers
+/// as their lines are indexed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Run {
+    pub first_id: usize,
+    pub last_id: usize,
+    pub count: usize,
+    key: String,
+}
+
+impl Run {
+    fn new(id: usize, key: String) -> Run {
+        Run {
+            first_id: id,
+            last_id: id,
+            count: 1,
+            key: key,
+        }
+    }
+}
+
+/// Consecutive lines considered the same, shown as the first one of them.
+pub struct Repetition<'a> {
+    pub first: &'a Line,
+    pub last: &'a Line,
+    pub count: usize,
+}
+
+impl<'a> Repetition<'a> {
+    pub fn new(line: &'a Line) -> Repetition<'a> {
+        Repetition {
+            first: line,
+            last: line,
+            count: 1,
+        }
+    }
+
+    /// Rows the line is shown on once wrapped to the given width.
+    pub fn guess_height(&self, container_width: usize) -> usize {
+        if self.count > 1 {
+            self.to_line().guess_height(container_width)
+        } else {
+            self.first.guess_height(container_width)
+        }
+    }
+
+    /// The first line, followed by how many times it was seen and when it was
+    /// first and last seen.
+    pub fn to_line(&self) -> Line {
+        let mut line = self.first.clone();
+
+        if self.count > 1 {
+            line.append(&format!(" ×{} ({} – {})",
+                                 self.count,
+                                 clock_time(self.first.received_at),
+                                 clock_time(self.last.received_at)));
+        }
+
+        line
+    }
+}
+
+fn clock_time(seconds: i64) -> String {
+    time::strftime("%H:%M:%S", &time::at(Timespec::new(seconds, 0))).unwrap()
+}
@@ -0,0 +1,103 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use rustc_serialize::{Decodable, Decoder};
+
+/// Tells how to extract an id shared by related lines, such as a request id,
+/// a trace id or a thread name.
+#[derive(Clone)]
+pub struct CorrelationRule {
+    pub name: String,
+    pub pattern: Regex,
+}
+
+impl CorrelationRule {
+    pub fn new(name: &str, pattern: Regex) -> CorrelationRule {
+        CorrelationRule {
+            name: name.to_string(),
+            pattern: pattern,
+        }
+    }
+
+    pub fn extract<'t>(&self, text: &'t str) -> Option<&'t str> {
+        capture_key(&self.pattern, text)
+    }
+}
+
+impl Decodable for CorrelationRule {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<CorrelationRule, D::Error> {
+        decoder.read_struct("CorrelationRule", 2, |d| {
+            let name = try!(d.read_struct_field("name", 0, |d| d.read_str()));
+            let pattern = try!(d.read_struct_field("pattern", 1, |d| d.read_str()));
+            let regex = match Regex::new(&pattern) {
+                Ok(value) => value,
+                Err(_) => {
+                    return Err(d.error(&format!("invalid correlation pattern `{}`", pattern)))
+                }
+            };
+
+            Ok(CorrelationRule::new(&name, regex))
+        })
+    }
+}
+
+/// Every line having the same id as the one it was picked from.
+#[derive(Clone)]
+pub struct Correlation {
+    pub rule: CorrelationRule,
+    pub id: String,
+}
+
+impl Correlation {
+    /// Uses the first rule able to extract an id from the given text.
+    pub fn find(rules: &[CorrelationRule], text: &str) -> Option<Correlation> {
+        rules.iter()
+            .filter_map(|rule| {
+                rule.extract(text).map(|id| {
+                    Correlation {
+                        rule: rule.clone(),
+                        id: id.to_string(),
+                    }
+                })
+            })
+            .next()
+    }
+
+    pub fn name(&self) -> String {
+        format!("{} {}", self.rule.name, self.id)
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.rule.extract(text) == Some(&self.id)
+    }
+}
+
+/// Takes the `id` named group, or else the first group, or else the whole
+/// match as the key.
+pub fn capture_key<'t>(regex: &Regex, text: &'t str) -> Option<&'t str> {
+    let captures = match regex.captures(text) {
+        Some(value) => value,
+        None => return None,
+    };
+
+    captures.name("id")
+        .or_else(|| captures.get(1))
+        .or_else(|| captures.get(0))
+        .map(|value| value.as_str())
+}
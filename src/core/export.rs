@@ -0,0 +1,153 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use rustc_serialize::json::Json;
+
+use core::line::Line;
+use core::filter::Context;
+use core::matcher::Matcher;
+use core::selection::{self, Kind, SEPARATOR, UNTERMINATED_MARKER};
+use core::fields;
+
+/// How exported lines are written.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Text,
+    /// Text with the colors and styles the lines were received with.
+    Ansi,
+    /// One JSON object per line, holding its parsed fields.
+    Json,
+}
+
+impl Format {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Format::Text => "text",
+            Format::Ansi => "text with colors",
+            Format::Json => "JSON lines",
+        }
+    }
+
+    pub fn next(&self) -> Format {
+        match *self {
+            Format::Text => Format::Ansi,
+            Format::Ansi => Format::Json,
+            Format::Json => Format::Text,
+        }
+    }
+
+    pub fn format_line(&self, line: &Line) -> String {
+        match *self {
+            Format::Text => line.content_without_ansi.clone(),
+            Format::Ansi => {
+                line.components
+                    .as_ref()
+                    .map_or_else(|| line.content_without_ansi.clone(),
+                                 |components| components.to_ansi())
+            }
+            Format::Json => {
+                let fields = fields::parse(&line.content_without_ansi)
+                    .into_iter()
+                    .map(|(key, value)| (key, Json::String(value)))
+                    .collect::<BTreeMap<_, _>>();
+
+                let mut object = BTreeMap::new();
+                object.insert("line".to_string(), Json::String(line.content_without_ansi.clone()));
+                object.insert("received_at".to_string(), Json::I64(line.received_at));
+                object.insert("fields".to_string(), Json::Object(fields));
+
+                Json::Object(object).to_string()
+            }
+        }
+    }
+}
+
+/// Paths starting with `~/` are taken from the user home.
+pub fn expand_path(text: &str) -> PathBuf {
+    match (text.starts_with("~/"), env::home_dir()) {
+        (true, Some(mut path)) => {
+            path.push(&text[2..]);
+            path
+        }
+        _ => PathBuf::from(text),
+    }
+}
+
+/// The lines as they're shown, each followed by a marker when it ends a
+/// block that never got its end.
+pub fn entries<'a, F>(lines: Vec<&'a Line>, ends_unterminated_block: F) -> Vec<(Kind, &'a Line)>
+    where F: Fn(&Line) -> bool
+{
+    let mut result = vec![];
+
+    for line in lines {
+        result.push((Kind::Normal, line));
+        if ends_unterminated_block(line) {
+            result.push((Kind::Marker, line));
+        }
+    }
+
+    result
+}
+
+/// Entries a search in filter mode leaves, picked the same way as the shown
+/// ones.
+pub fn filtered<'a>(entries: Vec<(Kind, &'a Line)>,
+                    matcher: &Matcher,
+                    context: Context,
+                    inverse: bool)
+                    -> Vec<(Kind, &'a Line)> {
+    let selected = if inverse {
+        selection::excluding(&entries, matcher)
+    } else {
+        selection::matching(&entries, matcher, context)
+    };
+
+    selected.into_iter().map(|(index, kind)| (kind, entries[index].1)).collect()
+}
+
+/// Replaces the file's contents with the entries, one per row. JSON lines
+/// only hold lines, so separators and markers are left out of them. Returns
+/// the number of lines written.
+pub fn save<'a, I>(path: &str, entries: I, format: Format) -> io::Result<usize>
+    where I: IntoIterator<Item = (Kind, &'a Line)>
+{
+    let mut file = try!(File::create(expand_path(path)));
+    let mut count = 0;
+
+    for (kind, line) in entries {
+        match (kind, format) {
+            (Kind::Separator, Format::Json) |
+            (Kind::Marker, Format::Json) => {}
+            (Kind::Separator, _) => try!(writeln!(file, "{}", SEPARATOR)),
+            (Kind::Marker, _) => try!(writeln!(file, "{}", UNTERMINATED_MARKER)),
+            _ => {
+                try!(writeln!(file, "{}", format.format_line(line)));
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,62 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use rustc_serialize::json::Json;
+
+lazy_static! {
+    static ref KEY_VALUE_MATCHER: Regex = Regex::new(
+        r#"(?:^|[\s,;(\[{])([A-Za-z_][\w.-]*)=("[^"]*"|[^\s,;)\]}]+)"#
+    ).unwrap();
+}
+
+/// Fields found in a line, taken from the JSON object it holds or else from
+/// its `key=value` pairs.
+pub fn parse(text: &str) -> Vec<(String, String)> {
+    let fields = json_fields(text);
+    if !fields.is_empty() {
+        return fields;
+    }
+
+    KEY_VALUE_MATCHER.captures_iter(text)
+        .map(|captures| (captures[1].to_string(), captures[2].trim_matches('"').to_string()))
+        .collect()
+}
+
+/// Members of the outermost object, sorted by their names. Nested values are
+/// kept as JSON.
+fn json_fields(text: &str) -> Vec<(String, String)> {
+    let (start, end) = match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return vec![],
+    };
+
+    match Json::from_str(&text[start..end + 1]) {
+        Ok(Json::Object(object)) => {
+            object.into_iter()
+                .map(|(key, value)| {
+                    match value {
+                        Json::String(value) => (key, value),
+                        value => (key, value.to_string()),
+                    }
+                })
+                .collect()
+        }
+        _ => vec![],
+    }
+}
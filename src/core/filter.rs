@@ -19,6 +19,15 @@
 use regex::Regex;
 use rustc_serialize::{Decodable, Decoder};
 
+use core::correlation::{Correlation, capture_key};
+use core::collapse::Collapse;
+use core::template::Template;
+
+/// Colors are kept by name, the interface turns them into terminal colors.
+pub static COLOR_NAMES: [&'static str; 9] = ["black", "red", "green", "yellow", "blue",
+                                             "magenta", "cyan", "white", "default"];
+static TEMPLATE_NAME_LENGTH: usize = 24;
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum Constraint {
     Start,
@@ -50,12 +59,54 @@ impl BoundaryFilter {
     }
 }
 
+/// Lines shown before and after each search match while in filter mode.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Context {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Context {
+    pub fn new(before: usize, after: usize) -> Context {
+        Context {
+            before: before,
+            after: after,
+        }
+    }
+
+    pub fn none() -> Context {
+        Context::new(0, 0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.before == 0 && self.after == 0
+    }
+}
+
 #[derive(Clone)]
 pub struct Filter {
     pub name: String,
     pub content: Option<Regex>,
     pub start: Option<BoundaryFilter>,
     pub end: Option<BoundaryFilter>,
+    pub context: Context,
+    /// Takes the place of the theme's unread color.
+    pub unread_color: Option<String>,
+    pub group_by: Option<Regex>,
+    pub correlation: Option<Correlation>,
+    /// Pattern of the message template a tab was opened for, matched on
+    /// top of the content of the filter it was opened from.
+    pub template: Option<Regex>,
+    /// Lines received after a start, without its end, before the block is
+    /// shown as unterminated.
+    pub max_block_length: Option<usize>,
+    /// Seconds a block can stay open before it's shown as unterminated.
+    pub block_timeout: Option<u64>,
+    /// Shows repeated lines once, along with how many times they were seen.
+    pub collapse: Option<Collapse>,
+    /// Leaves out the most recent block until its end arrives, instead of
+    /// showing it while it's in progress.
+    pub requires_end: bool,
 }
 
 pub enum ParserResult {
@@ -63,22 +114,27 @@ pub enum ParserResult {
     NoMatch,
     LastMatch(bool),
     Invalid(bool),
+    /// A start line whose block never got an end, along with whether the
+    /// start line itself is valid.
+    Unterminated(bool),
 }
 
-pub struct Parser {
-    pub filter: Filter,
+pub struct Parser<'f> {
+    pub filter: &'f Filter,
     pub constraints: Vec<Constraint>,
     active_constraint: Constraint,
     first_match: bool,
+    skipping_block: bool,
 }
 
-impl Parser {
-    pub fn new(filter: Filter) -> Parser {
+impl<'f> Parser<'f> {
+    pub fn new(filter: &'f Filter) -> Parser<'f> {
         Parser {
             active_constraint: Constraint::Content,
             constraints: filter.determine_constraints(),
             filter: filter,
             first_match: true,
+            skipping_block: false,
         }
     }
 
@@ -92,7 +148,7 @@ impl Parser {
         }
     }
 
-    fn end_constraint_parser<'a>(&'a mut self, text: &'a str) -> EndConstraintParser<'a> {
+    fn end_constraint_parser<'a>(&'a mut self, text: &'a str) -> EndConstraintParser<'a, 'f> {
         EndConstraintParser {
             text: text,
             parser: self,
@@ -101,20 +157,29 @@ impl Parser {
 
     fn content_end_constraints_parser<'a>(&'a mut self,
                                           text: &'a str)
-                                          -> ContentEndConstraintsParser<'a> {
+                                          -> ContentEndConstraintsParser<'a, 'f> {
         ContentEndConstraintsParser {
             text: text,
             parser: self,
         }
     }
 
-    fn constraints_parser<'a>(&'a mut self, text: &'a str) -> ConstraintsParser<'a> {
+    fn constraints_parser<'a>(&'a mut self, text: &'a str) -> ConstraintsParser<'a, 'f> {
         ConstraintsParser {
             text: text,
             parser: self,
         }
     }
 
+    /// The most recent block is normally taken to be in progress and shown
+    /// before its end arrives. This makes the parser wait for the end instead.
+    pub fn require_end(&mut self) {
+        if self.constraints.contains(&Constraint::End) &&
+           self.constraints.contains(&Constraint::Start) {
+            self.active_constraint = Constraint::Start;
+        }
+    }
+
     pub fn assume_found_matches(&self) -> bool {
         self.constraints == [Constraint::End] ||
         self.constraints == [Constraint::End, Constraint::Content] &&
@@ -127,12 +192,12 @@ impl Parser {
     }
 }
 
-struct ConstraintsParser<'a> {
+struct ConstraintsParser<'a, 'f: 'a> {
     text: &'a str,
-    parser: &'a mut Parser,
+    parser: &'a mut Parser<'f>,
 }
 
-impl<'a> ConstraintsParser<'a> {
+impl<'a, 'f> ConstraintsParser<'a, 'f> {
     fn parse(&mut self) -> ParserResult {
         match *self.parser.next_constraint() {
             Constraint::Start => self.handle_start(),
@@ -165,7 +230,7 @@ impl<'a> ConstraintsParser<'a> {
             self.parser.active_constraint = self.parser.constraints.iter().last().unwrap().clone();
             ParserResult::Invalid(false)
         } else {
-            if self.parser.filter.content.as_ref().unwrap().is_match(self.text) {
+            if self.parser.filter.is_content_match(self.text) {
                 self.parser.active_constraint = Constraint::Content;
             }
 
@@ -174,22 +239,43 @@ impl<'a> ConstraintsParser<'a> {
     }
 
     fn handle_end(&mut self) -> ParserResult {
-        if self.parser.filter.end.as_ref().unwrap().is_match(self.text) {
+        let end = self.parser.filter.end.as_ref().unwrap();
+
+        if end.is_match(self.text) {
             self.parser.active_constraint = Constraint::End;
+            self.parser.skipping_block = false;
 
             ParserResult::Match
+        } else if !self.parser.filter.tracks_unterminated() {
+            ParserResult::NoMatch
+        } else if end.regex.is_match(self.text) {
+            // Ended, just not the way we're interested in
+            self.parser.skipping_block = true;
+
+            ParserResult::NoMatch
+        } else if self.parser.filter.is_partial_match(Constraint::Start, self.text) {
+            let start = self.parser.filter.start.as_ref().unwrap();
+            let was_skipping = self.parser.skipping_block;
+            self.parser.skipping_block = false;
+
+            if was_skipping {
+                ParserResult::NoMatch
+            } else {
+                ParserResult::Unterminated(!start.has_named_match ||
+                                           start.is_named_match(self.text))
+            }
         } else {
             ParserResult::NoMatch
         }
     }
 }
 
-struct EndConstraintParser<'a> {
+struct EndConstraintParser<'a, 'f: 'a> {
     text: &'a str,
-    parser: &'a mut Parser,
+    parser: &'a mut Parser<'f>,
 }
 
-impl<'a> EndConstraintParser<'a> {
+impl<'a, 'f> EndConstraintParser<'a, 'f> {
     fn parse(&mut self) -> ParserResult {
         if self.parser.active_constraint == Constraint::End {
             self.handle_normal_occurrence()
@@ -224,12 +310,12 @@ impl<'a> EndConstraintParser<'a> {
     }
 }
 
-struct ContentEndConstraintsParser<'a> {
+struct ContentEndConstraintsParser<'a, 'f: 'a> {
     text: &'a str,
-    parser: &'a mut Parser,
+    parser: &'a mut Parser<'f>,
 }
 
-impl<'a> ContentEndConstraintsParser<'a> {
+impl<'a, 'f> ContentEndConstraintsParser<'a, 'f> {
     fn parse(&mut self) -> ParserResult {
         match *self.parser.next_constraint() {
             Constraint::Start => unreachable!(),
@@ -255,7 +341,7 @@ impl<'a> ContentEndConstraintsParser<'a> {
             }
             ParserResult::Invalid(is_match)
         } else {
-            if self.parser.filter.content.as_ref().unwrap().is_match(self.text) {
+            if self.parser.filter.is_content_match(self.text) {
                 self.parser.active_constraint = Constraint::Content;
             }
             ParserResult::Match
@@ -291,6 +377,87 @@ impl<'a> ContentEndConstraintsParser<'a> {
 }
 
 impl Filter {
+    /// Boundary filters group lines into blocks, so a new line may change
+    /// which of the older ones are shown.
+    pub fn has_boundaries(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+
+    /// Key correlating the lines of interleaved blocks, taken from the `id`
+    /// named group or else the first group of `group_by`.
+    pub fn group_key<'t>(&self, text: &'t str) -> Option<&'t str> {
+        self.group_by.as_ref().and_then(|regex| capture_key(regex, text))
+    }
+
+    /// Filter behind a tab that isn't part of the config, showing the lines
+    /// sharing an id.
+    pub fn correlated(correlation: Correlation) -> Filter {
+        let mut filter = Filter::opened_at_runtime(correlation.name());
+        filter.correlation = Some(correlation);
+
+        filter
+    }
+
+    /// Filter behind a tab that isn't part of the config, showing the lines
+    /// of a message template out of the ones the source filter shows.
+    pub fn templated(template: &Template, source: &Filter) -> Filter {
+        let text = template.to_string();
+        let name = if text.chars().count() > TEMPLATE_NAME_LENGTH {
+            format!("{}…", text.chars().take(TEMPLATE_NAME_LENGTH).collect::<String>())
+        } else {
+            text
+        };
+
+        let mut filter = source.clone();
+        filter.name = name;
+        filter.template = Some(template.to_regex());
+
+        filter
+    }
+
+    fn opened_at_runtime(name: String) -> Filter {
+        Filter {
+            name: name,
+            content: None,
+            start: None,
+            end: None,
+            context: Context::none(),
+            unread_color: None,
+            group_by: None,
+            correlation: None,
+            template: None,
+            max_block_length: None,
+            block_timeout: None,
+            collapse: None,
+            requires_end: false,
+        }
+    }
+
+    /// Tabs opened at runtime can be closed, unlike the ones from the config.
+    pub fn is_virtual(&self) -> bool {
+        self.correlation.is_some() || self.template.is_some()
+    }
+
+    /// Blocks that never end are only looked for when both boundaries are
+    /// known and a limit for how long they can stay open is given.
+    pub fn tracks_unterminated(&self) -> bool {
+        self.start.is_some() && self.end.is_some() &&
+        (self.max_block_length.is_some() || self.block_timeout.is_some())
+    }
+
+    /// Whether a block missing its end, given how many lines arrived since
+    /// its start and how many seconds ago, has stayed open for too long.
+    pub fn is_past_limits(&self, length: usize, age: i64) -> bool {
+        self.max_block_length.map_or(false, |value| length >= value) ||
+        self.block_timeout.map_or(false, |value| age >= value as i64)
+    }
+
+    pub fn is_content_match(&self, text: &str) -> bool {
+        self.content.as_ref().map_or(true, |content| content.is_match(text)) &&
+        self.correlation.as_ref().map_or(true, |correlation| correlation.is_match(text)) &&
+        self.template.as_ref().map_or(true, |template| template.is_match(text))
+    }
+
     fn determine_constraints(&self) -> Vec<Constraint> {
         let mut constraints = vec![];
 
@@ -298,7 +465,7 @@ impl Filter {
             constraints.push(Constraint::End);
         }
 
-        if self.content.is_some() {
+        if self.content.is_some() || self.correlation.is_some() || self.template.is_some() {
             constraints.push(Constraint::Content);
         }
 
@@ -309,7 +476,7 @@ impl Filter {
         constraints
     }
 
-    fn is_partial_match(&self, constraint: Constraint, text: &str) -> bool {
+    pub fn is_partial_match(&self, constraint: Constraint, text: &str) -> bool {
         match constraint {
             Constraint::Start => {
                 match self.start {
@@ -318,10 +485,8 @@ impl Filter {
                 }
             }
             Constraint::Content => {
-                match self.content {
-                    Some(ref content) => content.is_match(text),
-                    None => false,
-                }
+                (self.content.is_some() || self.correlation.is_some() || self.template.is_some()) &&
+                self.is_content_match(text)
             }
             Constraint::End => {
                 match self.end {
@@ -336,11 +501,22 @@ impl Filter {
 impl Decodable for Filter {
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Filter, D::Error> {
         decoder.read_struct("Filter", 2, |d| {
+            let name = try!(d.read_struct_field("name", 0, |d| d.read_str()));
             let filter = Filter {
-                name: try!(d.read_struct_field("name", 0, |d| d.read_str())),
-                content: field_to_regex(d, "contains", 1),
-                start: regex_to_boundary(field_to_regex(d, "starts_with", 2)),
-                end: regex_to_boundary(field_to_regex(d, "ends_with", 3)),
+                content: try!(field_to_regex(d, &name, "contains", 1)),
+                start: regex_to_boundary(try!(field_to_regex(d, &name, "starts_with", 2))),
+                end: regex_to_boundary(try!(field_to_regex(d, &name, "ends_with", 3))),
+                context: try!(fields_to_context(d, &name, 4)),
+                unread_color: try!(field_to_color(d, "unread_color", 7)),
+                group_by: try!(field_to_regex(d, &name, "group_by", 8)),
+                correlation: None,
+                template: None,
+                max_block_length: try!(field_to_usize(d, &name, "max_block_length", 9)),
+                block_timeout: try!(field_to_usize(d, &name, "block_timeout", 10))
+                    .map(|value| value as u64),
+                collapse: try!(field_to_collapse(d, "collapse", 11)),
+                requires_end: false,
+                name: name,
             };
 
             Ok(filter)
@@ -348,10 +524,89 @@ impl Decodable for Filter {
     }
 }
 
-fn field_to_regex<D: Decoder>(decoder: &mut D, name: &str, idx: usize) -> Option<Regex> {
+/// Missing fields are fine, while patterns that don't compile are reported.
+fn field_to_regex<D: Decoder>(decoder: &mut D,
+                              filter_name: &str,
+                              name: &str,
+                              idx: usize)
+                              -> Result<Option<Regex>, D::Error> {
     match decoder.read_struct_field(name, idx, |d| d.read_str()) {
-        Ok(val) => Some(Regex::new(&val).unwrap()),
-        Err(_) => None,
+        Ok(val) => {
+            match Regex::new(&val) {
+                Ok(value) => Ok(Some(value)),
+                Err(_) => {
+                    let message = format!("`{}` in filter `{}` isn't a valid regular expression",
+                                          name,
+                                          filter_name);
+                    Err(decoder.error(&message))
+                }
+            }
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Missing fields are fine, while ones that aren't whole numbers of zero or
+/// more are reported.
+fn field_to_usize<D: Decoder>(decoder: &mut D,
+                              filter_name: &str,
+                              name: &str,
+                              idx: usize)
+                              -> Result<Option<usize>, D::Error> {
+    match decoder.read_struct_field(name, idx, |d| Option::<i64>::decode(d)) {
+        Ok(Some(val)) if val >= 0 => Ok(Some(val as usize)),
+        Ok(None) => Ok(None),
+        _ => {
+            let message = format!("`{}` in filter `{}` needs to be a positive integer or 0",
+                                  name,
+                                  filter_name);
+            Err(decoder.error(&message))
+        }
+    }
+}
+
+fn fields_to_context<D: Decoder>(decoder: &mut D,
+                                 filter_name: &str,
+                                 idx: usize)
+                                 -> Result<Context, D::Error> {
+    let context = try!(field_to_usize(decoder, filter_name, "context", idx)).unwrap_or(0);
+
+    Ok(Context {
+        before: try!(field_to_usize(decoder, filter_name, "before_context", idx + 1))
+            .unwrap_or(context),
+        after: try!(field_to_usize(decoder, filter_name, "after_context", idx + 2))
+            .unwrap_or(context),
+    })
+}
+
+fn field_to_color<D: Decoder>(decoder: &mut D,
+                              name: &str,
+                              idx: usize)
+                              -> Result<Option<String>, D::Error> {
+    match decoder.read_struct_field(name, idx, |d| d.read_str()) {
+        Ok(val) => {
+            if COLOR_NAMES.contains(&val.as_str()) {
+                Ok(Some(val))
+            } else {
+                Err(decoder.error(&format!("unknown color `{}`", val)))
+            }
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+fn field_to_collapse<D: Decoder>(decoder: &mut D,
+                                 name: &str,
+                                 idx: usize)
+                                 -> Result<Option<Collapse>, D::Error> {
+    match decoder.read_struct_field(name, idx, |d| d.read_str()) {
+        Ok(val) => {
+            match Collapse::from_name(&val) {
+                Some(value) => Ok(Some(value)),
+                None => Err(decoder.error(&format!("unknown collapse mode `{}`", val))),
+            }
+        }
+        Err(_) => Ok(None),
     }
 }
 
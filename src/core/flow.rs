@@ -17,41 +17,102 @@
  */
 
 use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{Receiver, TryRecvError};
 use std::sync::atomic::Ordering;
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::io::{self, Write};
 
 use time;
 
 use ui::readline;
 use utils::settings::Settings;
 use ui::frame::Frame;
-use ui::event::{Event, QueuedEvent, Direction, SearchAction, Offset};
-use ui::navigation::State as NavigationState;
-use ui::search::State as QueryState;
+use ui::event::{Event, QueuedEvent, Direction, SearchAction, ExportAction, Offset};
+use ui::navigation::{State as NavigationState, HEIGHT as NAVIGATION_HEIGHT};
+use ui::search::{Query, State as QueryState};
 use ui::highlighter::Highlight;
+use ui::menu::Badge;
+use ui::template_list::TemplateList;
+use ui::status_bar::{Status, SPARKLINE_WIDTH};
+use ui::line_detail::LineDetail;
+use ui::help::{self, HelpOverlay, Section};
 
 use core::runner::RUNNING;
-use core::line::LineCollection;
-use core::buffer::BufferCollection;
+use core::line::{Line, LineCollection};
+use core::buffer::{Buffer, BufferCollection};
+use core::filter::Filter;
+use core::correlation::{Correlation, CorrelationRule};
+use core::template;
+use core::rate::RateMeter;
+use core::export;
+use utils::{clipboard, command};
 use ext::signal::{self, SIGQUIT};
 
 const NANOSECONDS_IN_A_MILISECOND: u64 = 1_000_000;
+const OVERDUE_BLOCKS_CHECK_INTERVAL: u64 = 1_000;
+const STATUS_REFRESH_INTERVAL: u64 = 1_000;
+const NOTICE_DURATION: i64 = 5;
+const CONFIG_WARNINGS_NOTICE_DURATION: i64 = 15;
 
 pub struct Flow {
     frame: Frame,
     lines: LineCollection,
     buffers: BufferCollection,
+    correlations: Vec<CorrelationRule>,
+    pipe_command: Option<String>,
+    /// Commands lines were piped to that are still running.
+    running_commands: Vec<(String, Receiver<io::Result<()>>)>,
     queue: HashMap<QueuedEvent, u64>,
+    marks: HashMap<char, (String, usize)>,
+    overdue_blocks_checked_at: u64,
+    rate: RateMeter,
+    status_refreshed_at: u64,
+    /// Id of the first line received since the view was paused.
+    paused_since: Option<usize>,
+    /// Whether the view was paused by opening the export prompt or the help.
+    paused_for_prompt: bool,
+    /// Shown in the status line until the given time.
+    notice: Option<(String, i64)>,
+    config_path: Option<PathBuf>,
+    /// Printed once the interface is gone, as the screen is cleared on startup.
+    config_warnings: Vec<String>,
 }
 
 impl Flow {
     pub fn new(settings: Settings) -> Flow {
-        Flow {
-            frame: Frame::new(settings.menu_item_names()),
+        let mut frame = Frame::new(settings.menu_item_names());
+        settings.theme.apply();
+        frame.content.highlight_rules = settings.highlights;
+        frame.key_map = settings.key_map;
+        frame.navigation.set_key_map(&frame.key_map);
+        frame.long_lines = settings.long_lines;
+        if settings.mouse {
+            frame.capture_mouse();
+        }
+
+        let mut flow = Flow {
+            frame: frame,
             lines: LineCollection::new(settings.max_lines_count),
             buffers: BufferCollection::from_filters(settings.filters),
+            correlations: settings.correlations,
+            pipe_command: settings.pipe_command,
+            running_commands: vec![],
             queue: HashMap::new(),
-        }
+            marks: HashMap::new(),
+            overdue_blocks_checked_at: 0,
+            rate: RateMeter::default(),
+            status_refreshed_at: 0,
+            paused_since: None,
+            paused_for_prompt: false,
+            notice: None,
+            config_path: settings.config_path,
+            config_warnings: settings.warnings,
+        };
+        flow.use_selected_buffer_context();
+        flow.notify_config_warnings();
+
+        flow
     }
 
     pub fn init(&self) {
@@ -63,18 +124,61 @@ impl Flow {
     pub fn terminate(&self) {
         readline::write_history();
         self.frame.destroy();
+
+        for warning in &self.config_warnings {
+            let _ = writeln!(io::stderr(), "Warning: {}", warning);
+        }
+    }
+
+    fn notify_config_warnings(&mut self) {
+        let text = match self.config_warnings.len() {
+            0 => return,
+            1 => format!("Warning: {}", self.config_warnings[0]),
+            count => format!("{} config warnings, listed on exit", count),
+        };
+        self.notice = Some((text, time::get_time().sec + CONFIG_WARNINGS_NOTICE_DURATION));
     }
 
     pub fn process(&mut self, lines: Arc<Mutex<Vec<String>>>) {
         while running!() {
             match self.frame.watch() {
                 Event::SelectMenuItem(direction) => self.select_menu_item(direction),
+                Event::CloseMenuItem => self.close_menu_item(),
+                Event::ToggleCollapse => self.toggle_collapse(),
+                Event::SelectTemplate(offset) => self.select_template(offset),
+                Event::OpenTemplate => self.open_template(),
+                Event::SelectBucket(offset) => self.select_bucket(offset),
+                Event::RepeatSearch(highlight, count) => self.repeat_search(highlight, count),
+                Event::MoveCursor(offset) => self.move_cursor(offset),
+                Event::CopyLine => self.copy_selected_line(),
+                Event::PipeLine => self.pipe_selected_line(),
+                Event::TogglePin => self.toggle_pin(),
+                Event::CorrelateLine => self.correlate_selected_line(),
+                Event::Click(row, column) => self.click(row, column),
+                Event::DoubleClick(row, column) => {
+                    self.click(row, column);
+                    self.copy_selected_line();
+                }
+                Event::SetMark(letter) => self.set_mark(letter),
+                Event::JumpToMark(letter) => self.jump_to_mark(letter),
                 Event::ScrollContents(offset) => self.scroll(offset),
+                Event::ScrollSideways(columns) => self.scroll_sideways(columns),
+                Event::CycleLongLines => self.cycle_long_lines(),
+                Event::TogglePause => self.toggle_pause(),
+                Event::Export(action) => self.handle_export(action),
+                Event::ScrollHelp(offset) => self.scroll_help(offset),
+                Event::CloseHelp => self.close_help(),
                 Event::Navigation(state) => {
                     if self.frame.navigation.change_state(state) {
                         match self.frame.navigation.state {
                             NavigationState::Search => readline::move_cursor(),
-                            NavigationState::Menu => self.reset_view(),
+                            NavigationState::Menu => self.close_panes(),
+                            NavigationState::Templates => self.show_template_list(),
+                            NavigationState::Histogram => self.show_histogram(),
+                            NavigationState::Selection => self.start_selection(),
+                            NavigationState::Detail => self.inspect_selected_line(),
+                            NavigationState::Export => self.open_export_prompt(),
+                            NavigationState::Help => self.show_help(),
                         }
                     }
                 }
@@ -83,11 +187,16 @@ impl Flow {
                 Event::Quit => self.quit(),
                 _ if !self.queue.is_empty() => self.execute_queue(),
                 _ => {
-                    let mut mutex_guarded_lines = lines.lock().unwrap();
-                    if !mutex_guarded_lines.is_empty() {
-                        let pending_lines = mutex_guarded_lines.drain(..).collect();
-                        self.append_incoming_lines(pending_lines);
+                    {
+                        let mut mutex_guarded_lines = lines.lock().unwrap();
+                        if !mutex_guarded_lines.is_empty() {
+                            let pending_lines = mutex_guarded_lines.drain(..).collect();
+                            self.append_incoming_lines(pending_lines);
+                        }
                     }
+                    self.check_overdue_blocks();
+                    self.check_running_commands();
+                    self.refresh_status();
                 }
             };
         }
@@ -104,9 +213,350 @@ impl Flow {
                 self.buffers.select_next();
             }
         };
+        self.show_selected_buffer();
+    }
+
+    /// Opens a tab with every line sharing the id found in the current match,
+    /// or switches to it when it's already open.
+    fn open_correlation(&mut self) {
+        let correlation = self.frame
+            .current_match_line()
+            .and_then(|line| Correlation::find(&self.correlations, &line.content_without_ansi));
+        self.open_correlation_tab(correlation);
+    }
+
+    /// Same as above, for the line under the cursor. The cursor is put away,
+    /// as the tab is browsed from the start.
+    fn correlate_selected_line(&mut self) {
+        let correlation = self.selected_line()
+            .and_then(|line| Correlation::find(&self.correlations, &line.content_without_ansi));
+        if correlation.is_some() {
+            self.frame.selected_line_id = None;
+        }
+        self.open_correlation_tab(correlation);
+    }
+
+    fn open_correlation_tab(&mut self, correlation: Option<Correlation>) {
+        if let Some(value) = correlation {
+            self.open_virtual_tab(Filter::correlated(value));
+        }
+    }
+
+    fn show_template_list(&mut self) {
+        let template_list = {
+            let buffer = self.buffers.selected_item();
+            let next_id = self.paused_since.unwrap_or(self.lines.next_id());
+            let templates =
+                template::cluster(&buffer.with_lines_before(&self.lines, self.paused_since));
+            TemplateList::new(templates, self.lines.first_id(), next_id)
+        };
+
+        self.frame.template_list = Some(template_list);
+        self.frame.render_template_list();
+    }
+
+    fn select_template(&mut self, offset: i32) {
+        if let Some(ref mut template_list) = self.frame.template_list {
+            template_list.select(offset);
+        }
+        self.frame.render_template_list();
+    }
+
+    /// Shows the lines of the selected template in a tab of their own.
+    fn open_template(&mut self) {
+        let filter = match self.frame.template_list.as_ref().and_then(|list| list.selected_item()) {
+            Some(template) => Filter::templated(template, &self.buffers.selected_item().filter),
+            None => return,
+        };
+
+        self.frame.template_list = None;
+        self.open_virtual_tab(filter);
+    }
+
+    fn show_histogram(&mut self) {
+        self.frame.open_histogram_pane();
+        self.reset_view();
+    }
+
+    /// Scrolls to the oldest line of the selected time range.
+    fn select_bucket(&mut self, offset: i32) {
+        let id = match self.frame.histogram_pane {
+            Some(ref mut pane) => {
+                pane.select(offset);
+                pane.selected_line_id()
+            }
+            None => None,
+        };
+
+        if let Some(value) = id {
+            let reverse_index = {
+                let buffer = self.buffers.selected_item();
+                let buffer_lines = buffer.with_lines_before(&self.lines, self.paused_since);
+                self.frame.reverse_index_of(&buffer_lines, value)
+            };
+            self.buffers
+                .selected_item()
+                .set_reverse_index(reverse_index, self.frame.max_scroll_value());
+            self.show_reverse_index();
+        }
+    }
+
+    /// Moves between the matches of the last search without going back to it.
+    /// Matching lines are only highlighted, even if the search was filtering.
+    /// They're searched for once, however many matches are skipped.
+    fn repeat_search(&mut self, highlight: Highlight, count: usize) {
+        let (query, next_query) = match (self.browsing_query(Highlight::Current),
+                                         self.browsing_query(highlight)) {
+            (Some(query), Some(next_query)) => (query, next_query),
+            _ => return,
+        };
+
+        let buffer = self.buffers.selected_item();
+        let buffer_lines = buffer.with_lines_before(&self.lines, self.paused_since);
+        self.frame.print(&buffer_lines, Some(query));
+        self.frame.advance_search(&buffer_lines, next_query, count);
+    }
+
+    fn browsing_query(&self, highlight: Highlight) -> Option<Query> {
+        self.frame.navigation.search.build_query(highlight).map(|mut query| {
+            query.filter = false;
+            query.inverse = false;
+            query
+        })
+    }
+
+    /// Remembers the line at the top of the viewport along with its tab.
+    fn set_mark(&mut self, letter: char) {
+        let buffer = self.buffers.selected_item();
+
+        if let Some(id) = self.frame.line_id_at(buffer.reverse_index.get() as i32, 0) {
+            self.marks.insert(letter, (buffer.filter.name.clone(), id));
+        }
+    }
+
+    /// Goes back to the tab the mark was set in and scrolls the marked line to
+    /// the top, as long as it's still held in memory.
+    fn jump_to_mark(&mut self, letter: char) {
+        let (name, id) = match self.marks.get(&letter) {
+            Some(&(ref name, id)) if id >= self.lines.first_id() => (name.clone(), id),
+            _ => return,
+        };
+
+        if let Some(index) = self.buffers.position_of(&name) {
+            if index != self.buffers.selected_index() {
+                self.select_tab(index);
+            }
+        }
+
+        let reverse_index = {
+            let buffer = self.buffers.selected_item();
+            let buffer_lines = buffer.with_lines_before(&self.lines, self.paused_since);
+            self.frame.reverse_index_of(&buffer_lines, id)
+        };
+        self.buffers
+            .selected_item()
+            .set_reverse_index(reverse_index, self.frame.max_scroll_value());
+        self.show_reverse_index();
+    }
+
+    fn close_panes(&mut self) {
+        self.frame.template_list = None;
+        self.frame.line_detail = None;
+        self.frame.selected_line_id = None;
+        self.frame.close_histogram_pane();
+        self.reset_view();
+    }
+
+    /// Switches to the tab having the same name when it's already open.
+    fn open_virtual_tab(&mut self, filter: Filter) {
+        let name = filter.name.clone();
+        let index = match self.buffers.position_of_virtual(&name) {
+            Some(value) => value,
+            None => {
+                let buffer = Buffer::new(filter);
+                buffer.update_index(&self.lines);
+                self.buffers.add(buffer);
+                self.frame.navigation.menu.add_item(&name);
+                self.buffers.len() - 1
+            }
+        };
+
+        self.frame.navigation.change_state(NavigationState::Menu);
+        self.select_tab(index);
+    }
+
+    fn select_tab(&mut self, index: usize) {
+        self.buffers.select(index);
+        self.frame.navigation.menu.select(index);
+        self.show_selected_buffer();
+    }
+
+    /// Clicking the menu switches tabs, while clicking the lines selects one.
+    fn click(&mut self, row: i32, column: i32) {
+        let state = &self.frame.navigation.state;
+        if *state == NavigationState::Search || *state == NavigationState::Detail {
+            return;
+        }
+
+        if row == self.frame.height - NAVIGATION_HEIGHT {
+            if let Some(index) = self.frame.navigation.menu.item_at(column) {
+                // The selected line belongs to the tab being left
+                if self.frame.navigation.change_state(NavigationState::Menu) {
+                    self.close_panes();
+                }
+                self.select_tab(index);
+            }
+            return;
+        }
+
+        let content_row = row - self.frame.content_top();
+        if content_row < 0 || content_row >= self.frame.content_height() {
+            return;
+        }
+
+        let reverse_index = self.buffers.selected_item().reverse_index.get() as i32;
+        self.frame.selected_line_id = self.frame.line_id_at(reverse_index, content_row);
+        if self.frame.navigation.change_state(NavigationState::Selection) {
+            self.start_selection();
+        } else {
+            self.frame.scroll(reverse_index);
+        }
+    }
+
+    /// Puts the cursor on the line at the bottom of the viewport, unless one
+    /// was clicked on. Coming back from the line details keeps the cursor.
+    fn start_selection(&mut self) {
+        self.frame.line_detail = None;
+
+        if self.frame.selected_line_id.is_none() {
+            let reverse_index = self.buffers.selected_item().reverse_index.get() as i32;
+            self.frame.select_last_visible_line(reverse_index);
+        }
+
+        if self.frame.selected_line_id.is_none() {
+            self.frame.navigation.change_state(NavigationState::Menu);
+        }
+        self.reset_view();
+    }
+
+    fn move_cursor(&mut self, offset: i32) {
+        self.frame.move_selection(offset);
+
+        if let Some(id) = self.frame.selected_line_id {
+            let buffer = self.buffers.selected_item();
+            let reverse_index = self.frame
+                .reverse_index_showing(id, buffer.reverse_index.get() as i32);
+            buffer.set_reverse_index(reverse_index, self.frame.max_scroll_value());
+            self.show_reverse_index();
+        }
+    }
+
+    fn selected_line(&self) -> Option<&Line> {
+        self.frame.selected_line_id.and_then(|id| self.lines.get_by_id(id))
+    }
+
+    fn copy_selected_line(&self) {
+        if let Some(line) = self.selected_line() {
+            clipboard::copy(&line.content_without_ansi);
+        }
+    }
+
+    fn pipe_selected_line(&mut self) {
+        let result = match (self.pipe_command.as_ref(), self.selected_line()) {
+            (None, _) => Err("No `pipe_command` set in the config".to_string()),
+            (Some(command), Some(line)) => {
+                command::pipe(command, &line.content_without_ansi)
+                    .map(|exit| Some((command.clone(), exit)))
+                    .map_err(|error| format!("Couldn't run `{}`: {}", command, error))
+            }
+            (Some(_), None) => Ok(None),
+        };
+
+        match result {
+            Ok(Some(running_command)) => self.running_commands.push(running_command),
+            Ok(None) => {}
+            Err(text) => self.show_notice(text),
+        }
+    }
+
+    /// Tells about the piped commands that failed once they're done.
+    fn check_running_commands(&mut self) {
+        let mut failures = vec![];
+        self.running_commands.retain(|&(ref command, ref exit)| {
+            match exit.try_recv() {
+                Ok(Err(error)) => {
+                    failures.push(format!("`{}` failed: {}", command, error));
+                    false
+                }
+                Err(TryRecvError::Empty) => true,
+                _ => false,
+            }
+        });
+
+        if let Some(text) = failures.pop() {
+            self.show_notice(text);
+        }
+    }
+
+    fn toggle_pin(&mut self) {
+        if let Some(line) = self.selected_line().cloned() {
+            self.frame.toggle_pin(line);
+            self.reset_view();
+        }
+    }
+
+    fn inspect_selected_line(&mut self) {
+        match self.selected_line().cloned() {
+            Some(line) => {
+                self.frame.line_detail = Some(LineDetail::new(line));
+                self.frame.render_line_detail();
+            }
+            None => {
+                self.frame.navigation.change_state(NavigationState::Selection);
+            }
+        }
+    }
+
+    fn close_menu_item(&mut self) {
+        let index = self.buffers.selected_index();
+
+        if self.buffers.remove_selected() {
+            self.frame.navigation.menu.remove_item(index);
+            self.frame.navigation.menu.select(self.buffers.selected_index());
+            self.show_selected_buffer();
+        }
+    }
+
+    fn toggle_collapse(&mut self) {
+        self.buffers.selected_item().toggle_collapse(&self.lines);
+        self.reset_view();
+    }
+
+    fn show_selected_buffer(&mut self) {
+        self.use_selected_buffer_context();
+        self.buffers.selected_item().mark_as_read(&self.lines);
+        self.update_menu_badges();
+        self.update_status();
         self.reset_view();
     }
 
+    fn update_menu_badges(&self) {
+        let badges = self.buffers
+            .iter()
+            .map(|buffer| {
+                Badge::new(buffer.match_count.get(),
+                           buffer.unread_count.get(),
+                           buffer.filter.unread_color.clone())
+            })
+            .collect();
+        self.frame.navigation.menu.update_badges(badges);
+    }
+
+    fn use_selected_buffer_context(&mut self) {
+        let context = self.buffers.selected_item().filter.context;
+        self.frame.navigation.search.set_context(context);
+    }
+
     fn scroll(&mut self, offset: Offset) {
         let buffer = self.buffers.selected_item();
 
@@ -118,6 +568,10 @@ impl Flow {
                 buffer.increment_reverse_index(value * self.frame.height - 4,
                                                self.frame.max_scroll_value());
             }
+            Offset::HalfViewport(value) => {
+                buffer.increment_reverse_index(value * self.frame.content_height() / 2,
+                                               self.frame.max_scroll_value());
+            }
             Offset::Top => {
                 buffer.reverse_index.set(self.frame.max_scroll_value() as usize);
             }
@@ -126,7 +580,187 @@ impl Flow {
             }
         };
 
-        self.frame.scroll(buffer.reverse_index.get() as i32);
+        self.show_reverse_index();
+    }
+
+    fn scroll_sideways(&mut self, columns: i32) {
+        self.frame.scroll_sideways(columns);
+        self.show_reverse_index();
+    }
+
+    /// Draws the lines at the scroll position, laying them out again when
+    /// only the ones around the previous position were.
+    fn show_reverse_index(&mut self) {
+        let buffer = self.buffers.selected_item();
+        let reverse_index = buffer.reverse_index.get() as i32;
+
+        if self.frame.is_laid_out_at(reverse_index) {
+            self.frame.scroll(reverse_index);
+        } else {
+            let query = match self.frame.navigation.state {
+                NavigationState::Search => {
+                    self.frame.navigation.search.build_query(Highlight::Current)
+                }
+                _ => None,
+            };
+            self.frame.print(&buffer.with_lines_before(&self.lines, self.paused_since), query);
+        }
+    }
+
+    fn cycle_long_lines(&mut self) {
+        self.frame.cycle_long_lines();
+        self.reset_view();
+    }
+
+    /// The lines shown are kept as they are while the path is typed in, so
+    /// what gets saved is what was on screen.
+    fn open_export_prompt(&mut self) {
+        self.pause_for_prompt();
+        self.frame.navigation.export_prompt.render();
+    }
+
+    fn pause_for_prompt(&mut self) {
+        if self.paused_since.is_none() {
+            self.toggle_pause();
+            self.paused_for_prompt = true;
+        }
+    }
+
+    fn resume_after_prompt(&mut self) {
+        if self.paused_for_prompt {
+            self.paused_for_prompt = false;
+            self.toggle_pause();
+        } else {
+            self.update_status();
+        }
+    }
+
+    fn handle_export(&mut self, action: ExportAction) {
+        match action {
+            ExportAction::Type(value) => self.frame.navigation.export_prompt.type_char(value),
+            ExportAction::Erase => self.frame.navigation.export_prompt.erase(),
+            ExportAction::CycleFormat => self.frame.navigation.export_prompt.cycle_format(),
+            ExportAction::Save => self.export(),
+            ExportAction::Cancel => self.close_export_prompt(),
+        }
+    }
+
+    /// Saves every line of the tab, or the ones left by a search in filter
+    /// mode, without collapsing repeated lines. Separators and markers are
+    /// kept as they're shown.
+    fn export(&mut self) {
+        let path = self.frame.navigation.export_prompt.path();
+        let format = self.frame.navigation.export_prompt.format.get();
+        if path.is_empty() {
+            return;
+        }
+
+        let result = {
+            let search = &self.frame.navigation.search;
+            let query = match self.frame.navigation.previous_state {
+                NavigationState::Search => search.build_query(Highlight::Current),
+                _ => None,
+            };
+            let buffer = self.buffers.selected_item();
+            let buffer_lines = buffer.with_lines_before(&self.lines, self.paused_since);
+            let lines = (&buffer_lines).into_iter().collect::<Vec<_>>();
+            let entries =
+                export::entries(lines, |line| buffer.ends_unterminated_block(line.id));
+
+            match query {
+                Some(ref query) if query.filter => {
+                    let entries =
+                        export::filtered(entries, &query.matcher, query.context, query.inverse);
+                    export::save(&path, entries, format)
+                }
+                _ => export::save(&path, entries, format),
+            }
+        };
+
+        match result {
+            Ok(count) => {
+                let text = format!("Saved {} lines to {}", count, path);
+                self.notice = Some((text, time::get_time().sec + NOTICE_DURATION));
+                self.close_export_prompt();
+            }
+            Err(error) => {
+                let message = format!("Couldn't save: {}", error);
+                self.frame.navigation.export_prompt.show_error(message);
+            }
+        }
+    }
+
+    /// Goes back to browsing or searching the lines that were exported.
+    fn close_export_prompt(&mut self) {
+        let state = self.frame.navigation.previous_state;
+        self.frame.navigation.change_state(state);
+        if state == NavigationState::Search {
+            readline::move_cursor();
+        }
+
+        self.resume_after_prompt();
+    }
+
+    /// Drawn over the view, which is kept as it is until the help is closed.
+    fn show_help(&mut self) {
+        self.pause_for_prompt();
+
+        let sections = self.help_sections();
+        if let Some(overlay) = self.frame.help_overlay.take() {
+            overlay.destroy();
+        }
+        self.frame.help_overlay = Some(HelpOverlay::new(help::title(&self.frame.key_map),
+                                                        &sections,
+                                                        self.frame.width,
+                                                        self.frame.height));
+    }
+
+    fn help_sections(&self) -> Vec<Section> {
+        let buffer = self.buffers.selected_item();
+        let modes = vec![("Long lines".to_string(), self.frame.long_lines.name().to_string()),
+                         ("Collapsing".to_string(),
+                          buffer.collapse().map_or("off", |collapse| collapse.name()).to_string()),
+                         ("Highlight rules".to_string(),
+                          self.frame.content.highlight_rules.len().to_string())];
+        let config_path = self.config_path
+            .as_ref()
+            .map_or("none found, using the defaults".to_string(),
+                    |path| path.display().to_string());
+        let filters = self.buffers.iter().map(|buffer| &buffer.filter).collect::<Vec<_>>();
+
+        vec![help::key_section(&self.frame.key_map,
+                               &self.frame.navigation.previous_state,
+                               self.frame.is_mouse_captured),
+             Section::new("Modes", modes),
+             help::filter_section(&filters),
+             Section::new("Config file", vec![(config_path, String::new())])]
+    }
+
+    fn scroll_help(&mut self, offset: i32) {
+        if let Some(ref mut overlay) = self.frame.help_overlay {
+            overlay.scroll(offset);
+        }
+    }
+
+    /// Goes back to where the help was opened from, drawing what was hidden
+    /// underneath it again.
+    fn close_help(&mut self) {
+        if let Some(overlay) = self.frame.help_overlay.take() {
+            overlay.destroy();
+        }
+        let state = self.frame.navigation.previous_state;
+        self.frame.navigation.change_state(state);
+
+        let reverse_index = self.buffers.selected_item().reverse_index.get() as i32;
+        self.frame.redraw(reverse_index);
+        if let Some(ref pane) = self.frame.histogram_pane {
+            pane.render();
+        }
+        if let Some(ref pane) = self.frame.bookmarks_pane {
+            pane.render();
+        }
+        self.frame.navigation.render();
+        self.resume_after_prompt();
     }
 
     fn handle_search(&mut self, action: SearchAction) {
@@ -154,44 +788,165 @@ impl Flow {
                 self.frame.navigation.search.toggle_filter();
                 self.perform_search(Highlight::VisibleOrLast);
             }
+            SearchAction::ToggleInverseMode => {
+                self.frame.navigation.search.toggle_inverse();
+                self.perform_search(Highlight::VisibleOrLast);
+            }
+            SearchAction::ToggleHighlightRule => {
+                if let Some(query) = self.frame.navigation.search.build_query(Highlight::Current) {
+                    self.frame.content.toggle_highlight_rule(query.matcher);
+                    self.reset_view_or_redo_search();
+                }
+            }
+            SearchAction::CycleContext => {
+                self.frame.navigation.search.cycle_context();
+                self.perform_search(Highlight::VisibleOrLast);
+            }
+            SearchAction::ToggleRegexMode => {
+                self.frame.navigation.search.toggle_regex();
+                self.perform_search(Highlight::VisibleOrLast);
+            }
+            SearchAction::ToggleCaseInsensitiveMode => {
+                self.frame.navigation.search.toggle_case_insensitive();
+                self.perform_search(Highlight::VisibleOrLast);
+            }
+            SearchAction::ToggleWholeWordMode => {
+                self.frame.navigation.search.toggle_whole_word();
+                self.perform_search(Highlight::VisibleOrLast);
+            }
+            SearchAction::OpenCorrelation => self.open_correlation(),
         }
     }
 
     fn resize(&mut self) {
         self.frame.resize();
+        self.update_status();
         self.reset_view_or_redo_search();
+
+        if self.frame.help_overlay.is_some() {
+            self.show_help();
+        }
     }
 
     fn append_incoming_lines(&mut self, pending_lines: Vec<String>) {
-        let count = pending_lines.len();
+        let first_new_id = self.lines.next_id();
+        let is_backlog = first_new_id == 0;
         self.lines.extend(pending_lines);
 
-        if self.frame.navigation.state == NavigationState::Search {
-            let mut state = self.frame.content.state.borrow_mut();
-            let new_highlighted_line = state.highlighted_line as i32 - count as i32;
-            if new_highlighted_line >= 0 {
-                state.highlighted_line -= count;
+        // Lines that were already in the file on startup aren't news to anyone
+        if is_backlog {
+            for buffer in self.buffers.iter() {
+                buffer.mark_as_read(&self.lines);
             }
         }
+        self.buffers.update_indexes(&self.lines);
+        self.update_menu_badges();
 
+        // Nor do they say anything about how fast lines are coming in
+        if !is_backlog {
+            let now = time::get_time().sec;
+            self.rate.record(self.lines.next_id() - first_new_id, now);
+            for buffer in self.buffers.iter() {
+                buffer.record_rate(first_new_id, now);
+            }
+        }
+        self.update_status();
+
+        // The lines are shown once the view is resumed
+        if self.paused_since.is_none() {
+            self.catch_up(first_new_id);
+        }
+    }
+
+    /// Redraws the lines, keeping a scrolled view on the same lines.
+    fn catch_up(&mut self, first_new_id: usize) {
         self.reset_view_or_redo_search();
 
         if self.buffers.selected_item().is_scrolled() {
-            let offset = self.frame.rendered_lines.last_lines_height(count);
+            let offset = {
+                let buffer = self.buffers.selected_item();
+                let buffer_lines = buffer.with_lines_before(&self.lines, self.paused_since);
+                self.frame.height_since(&buffer_lines, first_new_id)
+            };
             self.scroll(Offset::Line(offset));
         }
     }
 
+    fn toggle_pause(&mut self) {
+        match self.paused_since.take() {
+            Some(first_new_id) => self.catch_up(first_new_id),
+            None => self.paused_since = Some(self.lines.next_id()),
+        }
+        self.update_status();
+    }
+
+    fn check_overdue_blocks(&mut self) {
+        let current_time = time::precise_time_ns();
+        let interval = OVERDUE_BLOCKS_CHECK_INTERVAL * NANOSECONDS_IN_A_MILISECOND;
+        if current_time - self.overdue_blocks_checked_at < interval {
+            return;
+        }
+        self.overdue_blocks_checked_at = current_time;
+
+        if self.buffers.refresh_overdue_blocks(&self.lines) {
+            self.update_menu_badges();
+            if self.paused_since.is_none() {
+                self.reset_view_or_redo_search();
+            }
+        }
+    }
+
+    /// Rates drop when no lines arrive, so the status is kept up to date even
+    /// then.
+    fn refresh_status(&mut self) {
+        let current_time = time::precise_time_ns();
+        let interval = STATUS_REFRESH_INTERVAL * NANOSECONDS_IN_A_MILISECOND;
+        if current_time - self.status_refreshed_at < interval {
+            return;
+        }
+
+        self.update_status();
+    }
+
+    fn show_notice(&mut self, text: String) {
+        self.notice = Some((text, time::get_time().sec + NOTICE_DURATION));
+        self.update_status();
+    }
+
+    fn update_status(&mut self) {
+        let now = time::get_time().sec;
+        let buffer = self.buffers.selected_item();
+        let tab_rate = buffer.rate.borrow();
+        let status = Status {
+            received_count: self.lines.next_id(),
+            held_count: self.lines.len(),
+            capacity: self.lines.capacity(),
+            rate: self.rate.per_second(now),
+            tab_rate: tab_rate.per_second(now),
+            tab_sparkline: tab_rate.sparkline(now, SPARKLINE_WIDTH),
+            paused_count: self.paused_since.map(|id| self.lines.next_id() - id),
+            notice: self.notice
+                .as_ref()
+                .and_then(|&(ref text, until)| if now < until { Some(text.clone()) } else { None }),
+        };
+
+        self.frame.status_bar.render(&status);
+        self.frame.navigation.update();
+        self.status_refreshed_at = time::precise_time_ns();
+    }
+
     fn reset_view(&mut self) {
         let buffer = self.buffers.selected_item();
-        self.frame.print(&mut buffer.with_lines(&self.lines), None);
+        self.frame.print(&buffer.with_lines_before(&self.lines, self.paused_since), None);
     }
 
+    /// Searching lays the lines out by itself, so they're laid out once.
     fn reset_view_or_redo_search(&mut self) {
-        self.reset_view();
-
         if self.frame.navigation.state == NavigationState::Search {
+            self.frame.reset();
             self.perform_search(Highlight::Current);
+        } else {
+            self.reset_view();
         }
     }
 
@@ -232,7 +987,7 @@ impl Flow {
     fn perform_search(&mut self, highlight: Highlight) {
         let buffer = self.buffers.selected_item();
         let query = self.frame.navigation.search.build_query(highlight);
-        self.frame.print(&mut buffer.with_lines(&self.lines), query);
+        self.frame.print(&buffer.with_lines_before(&self.lines, self.paused_since), query);
         self.frame.navigation.search.render();
     }
 
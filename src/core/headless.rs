@@ -0,0 +1,199 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::max;
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use utils::settings::Settings;
+use core::tail::Tail;
+use core::line::LineCollection;
+use core::buffer::Buffer;
+use core::filter::Filter;
+use core::export::Format;
+use core::selection::UNTERMINATED_MARKER;
+
+/// Input path standing for the standard input.
+pub static STDIN_PATH: &'static str = "-";
+
+/// Writes the lines matching a filter to the standard output, without the
+/// interface.
+pub fn execute(settings: Settings) {
+    for warning in &settings.warnings {
+        let _ = writeln!(io::stderr(), "Warning: {}", warning);
+    }
+
+    let filter = find_filter(settings.filters, settings.filter_name.as_ref());
+    let mut printer = Printer::new(filter, settings.max_lines_count);
+    let (sender, receiver) = mpsc::channel();
+
+    let path = settings.path_to_target_file;
+    let follow = settings.follow;
+    thread::spawn(move || read_input(path, follow, sender));
+
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+
+    while let Ok(mut lines) = receiver.recv() {
+        while let Ok(pending_lines) = receiver.try_recv() {
+            lines.extend(pending_lines);
+        }
+
+        // Stops once whatever reads the output is gone
+        if write_lines(&mut output, printer.matching_lines(lines)).is_err() {
+            return;
+        }
+    }
+
+    let _ = write_lines(&mut output, printer.remaining_lines());
+}
+
+fn write_lines<W: Write>(output: &mut W, lines: Vec<String>) -> io::Result<()> {
+    for line in lines {
+        try!(writeln!(output, "{}", line));
+    }
+
+    output.flush()
+}
+
+fn find_filter(filters: Vec<Filter>, name: Option<&String>) -> Filter {
+    let names = filters.iter().map(|filter| filter.name.clone()).collect::<Vec<_>>();
+
+    match name {
+        Some(name) => {
+            filters.into_iter().find(|filter| filter.name == *name).unwrap_or_else(|| {
+                let message = format!("No filter named `{}`, available ones are: {}",
+                                      name,
+                                      names.join(", "));
+                critical_quit!(message);
+            })
+        }
+        None => filters.into_iter().next().unwrap(),
+    }
+}
+
+/// The standard input is read until it's closed, files until their end or,
+/// when following them, until flow is stopped.
+fn read_input(path: String, follow: bool, sender: Sender<Vec<String>>) {
+    if path == STDIN_PATH {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(value) => {
+                    let _ = sender.send(vec![value]);
+                }
+                Err(_) => break,
+            }
+        }
+        return;
+    }
+
+    let mut tail = Tail::new(path);
+    let _ = sender.send(tail.read_to_end());
+
+    if follow {
+        tail.watch(|lines| {
+            if !lines.is_empty() {
+                let _ = sender.send(lines);
+            }
+        });
+    }
+}
+
+/// Runs the lines through a filter the same way a tab does. Unlike a tab, a
+/// block is only printed once it can't change anymore.
+pub struct Printer {
+    lines: LineCollection,
+    buffer: Buffer,
+    printed_ids: HashSet<usize>,
+}
+
+impl Printer {
+    pub fn new(mut filter: Filter, capacity: usize) -> Printer {
+        filter.requires_end = true;
+
+        Printer {
+            lines: LineCollection::new(capacity),
+            buffer: Buffer::new(filter),
+            printed_ids: HashSet::new(),
+        }
+    }
+
+    /// Matching lines that haven't been printed yet, as they were received.
+    /// The lines are taken in chunks of half the capacity, so blocks up to
+    /// that length aren't evicted before they can end.
+    pub fn matching_lines(&mut self, new_lines: Vec<String>) -> Vec<String> {
+        let chunk_size = max(self.lines.capacity() / 2, 1);
+        let mut result = vec![];
+
+        for chunk in new_lines.chunks(chunk_size) {
+            self.lines.extend(chunk.iter().cloned());
+            self.buffer.update_index(&self.lines);
+
+            let first_id = self.lines.first_id();
+            self.printed_ids.retain(|id| *id >= first_id);
+
+            let held_back_id = self.held_back_id();
+            result.extend(self.take_lines(held_back_id));
+        }
+
+        result
+    }
+
+    /// Lines held back until now, once the input has ended.
+    pub fn remaining_lines(&mut self) -> Vec<String> {
+        self.take_lines(None)
+    }
+
+    /// Blocks of filters without an end last until the next one starts, so
+    /// the lines starting with the most recent start can't be printed yet.
+    fn held_back_id(&self) -> Option<usize> {
+        let filter = &self.buffer.filter;
+        match (filter.start.as_ref(), filter.end.as_ref()) {
+            (Some(start), None) => {
+                self.lines
+                    .entries
+                    .iter()
+                    .rev()
+                    .find(|line| start.regex.is_match(&line.content_without_ansi))
+                    .map(|line| line.id)
+            }
+            _ => None,
+        }
+    }
+
+    fn take_lines(&mut self, held_back_id: Option<usize>) -> Vec<String> {
+        let mut result = vec![];
+
+        for line in &self.buffer.with_lines(&self.lines) {
+            if held_back_id.map_or(false, |id| line.id >= id) ||
+               !self.printed_ids.insert(line.id) {
+                continue;
+            }
+            result.push(Format::Ansi.format_line(line));
+            if self.buffer.ends_unterminated_block(line.id) {
+                result.push(UNTERMINATED_MARKER.to_string());
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,101 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use rustc_serialize::{Decodable, Decoder};
+use ncurses::*;
+
+use core::matcher::Matcher;
+use ui::color::{self, ColorPair};
+
+/// Colors handed out to rules that don't specify one, in order.
+static PALETTE: [i16; 6] = [COLOR_MAGENTA, COLOR_CYAN, COLOR_GREEN, COLOR_YELLOW, COLOR_BLUE,
+                            COLOR_RED];
+
+/// A pattern that is always highlighted with its own color, regardless of the
+/// selected tab or the active search.
+#[derive(Clone)]
+pub struct HighlightRule {
+    pub matcher: Matcher,
+    pub color: i16,
+    explicit_color: bool,
+}
+
+impl HighlightRule {
+    pub fn new(matcher: Matcher, color: i16) -> HighlightRule {
+        HighlightRule {
+            matcher: matcher,
+            color: color,
+            explicit_color: true,
+        }
+    }
+
+    /// Hands palette colors to the rules decoded without a color, so that each
+    /// of them ends up with one no other rule uses.
+    pub fn assign_palette_colors(rules: &mut [HighlightRule]) {
+        let mut used_colors = rules.iter()
+            .filter(|rule| rule.explicit_color)
+            .map(|rule| rule.color)
+            .collect::<Vec<_>>();
+
+        for rule in rules.iter_mut().filter(|rule| !rule.explicit_color) {
+            rule.color = HighlightRule::unused_color(&used_colors);
+            used_colors.push(rule.color);
+        }
+    }
+
+    /// First palette color missing from the given ones. Once they're all taken,
+    /// colors are reused in order.
+    pub fn unused_color(used_colors: &[i16]) -> i16 {
+        PALETTE.iter()
+            .cloned()
+            .find(|color| !used_colors.contains(color))
+            .unwrap_or(PALETTE[used_colors.len() % PALETTE.len()])
+    }
+
+    pub fn color_pair(&self) -> ColorPair {
+        ColorPair::on(self.color)
+    }
+}
+
+impl Decodable for HighlightRule {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<HighlightRule, D::Error> {
+        decoder.read_struct("HighlightRule", 2, |d| {
+            let pattern = try!(d.read_struct_field("pattern", 0, |d| d.read_str()));
+            let regex = match Regex::new(&pattern) {
+                Ok(value) => value,
+                Err(_) => return Err(d.error(&format!("invalid highlight pattern `{}`", pattern))),
+            };
+
+            let color = match d.read_struct_field("color", 1, |d| d.read_str()) {
+                Ok(name) => {
+                    match color::from_name(&name) {
+                        Some(value) => Some(value),
+                        None => return Err(d.error(&format!("unknown highlight color `{}`", name))),
+                    }
+                }
+                Err(_) => None,
+            };
+
+            let mut rule = HighlightRule::new(Matcher::from_regex(regex),
+                                              color.unwrap_or(PALETTE[0]));
+            rule.explicit_color = color.is_some();
+            Ok(rule)
+        })
+    }
+}
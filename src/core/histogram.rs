@@ -0,0 +1,156 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::Regex;
+use time::{self, Timespec};
+
+use core::line::Line;
+
+lazy_static! {
+    static ref TIMESTAMP_MATCHER: Regex = Regex::new(
+        r"(?:\b(\d{4})-(\d{2})-(\d{2})[T ]|\b)(\d{2}):(\d{2}):(\d{2})\b"
+    ).unwrap();
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Bucket {
+    pub count: usize,
+    /// Oldest line falling within the bucket.
+    pub first_id: Option<usize>,
+}
+
+/// How the lines of a tab are spread over time, either by the timestamps
+/// they carry or by when they were received.
+pub struct Histogram {
+    pub buckets: Vec<Bucket>,
+    start: i64,
+    end: i64,
+    uses_log_timestamps: bool,
+}
+
+impl Histogram {
+    /// Lines without a timestamp of their own, such as stack traces, take the
+    /// one of the line before them. Arrival times are only used when none of
+    /// the lines carry a timestamp.
+    pub fn new<'a, I>(lines: I, width: usize) -> Histogram
+        where I: IntoIterator<Item = &'a Line>
+    {
+        let lines = lines.into_iter().collect::<Vec<_>>();
+        let mut timestamps = lines.iter()
+            .map(|line| timestamp(&line.content_without_ansi))
+            .collect::<Vec<_>>();
+        let uses_log_timestamps = timestamps.iter().any(|value| value.is_some());
+
+        if uses_log_timestamps {
+            let mut previous = timestamps.iter().filter_map(|value| *value).next();
+            for value in &mut timestamps {
+                match *value {
+                    Some(_) => previous = *value,
+                    None => *value = previous,
+                }
+            }
+        } else {
+            timestamps = lines.iter().map(|line| Some(line.received_at)).collect();
+        }
+
+        let timestamps = timestamps.into_iter().map(|value| value.unwrap()).collect::<Vec<_>>();
+        let start = timestamps.iter().cloned().min().unwrap_or(0);
+        let end = timestamps.iter().cloned().max().unwrap_or(0);
+        let mut histogram = Histogram {
+            buckets: vec![Bucket { count: 0, first_id: None }; width],
+            start: start,
+            end: end,
+            uses_log_timestamps: uses_log_timestamps,
+        };
+
+        if width > 0 {
+            for (line, value) in lines.iter().zip(timestamps) {
+                let index = histogram.index_of(value);
+                let bucket = &mut histogram.buckets[index];
+                bucket.count += 1;
+                if bucket.first_id.map_or(true, |id| line.id < id) {
+                    bucket.first_id = Some(line.id);
+                }
+            }
+        }
+
+        histogram
+    }
+
+    fn index_of(&self, timestamp: i64) -> usize {
+        let width = self.buckets.len() as i64;
+        ((timestamp - self.start) * width / self.span()) as usize
+    }
+
+    fn span(&self) -> i64 {
+        self.end - self.start + 1
+    }
+
+    pub fn highest_count(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.count).max().unwrap_or(0)
+    }
+
+    /// Time range covered by a bucket, such as "10:02:11 – 10:02:40".
+    pub fn label(&self, index: usize) -> String {
+        let width = self.buckets.len() as i64;
+        let from = self.start + index as i64 * self.span() / width;
+        let to = self.start + (index as i64 + 1) * self.span() / width - 1;
+
+        if to > from {
+            format!("{} – {}", self.clock_time(from), self.clock_time(to))
+        } else {
+            self.clock_time(from)
+        }
+    }
+
+    /// Timestamps found in the lines are taken as they are, without any
+    /// time zone.
+    fn clock_time(&self, seconds: i64) -> String {
+        let tm = if self.uses_log_timestamps {
+            time::at_utc(Timespec::new(seconds, 0))
+        } else {
+            time::at(Timespec::new(seconds, 0))
+        };
+
+        time::strftime("%H:%M:%S", &tm).unwrap()
+    }
+}
+
+/// Seconds since the epoch for a date and time such as "2016-10-02
+/// 14:03:55", or since midnight when there's only a time.
+pub fn timestamp(text: &str) -> Option<i64> {
+    let captures = match TIMESTAMP_MATCHER.captures(text) {
+        Some(value) => value,
+        None => return None,
+    };
+    let number = |index: usize| {
+        captures.get(index).map_or(0, |value| value.as_str().parse::<i32>().unwrap())
+    };
+
+    let tm = time::Tm {
+        tm_year: if captures.get(1).is_some() { number(1) - 1900 } else { 70 },
+        tm_mon: if captures.get(2).is_some() { number(2) - 1 } else { 0 },
+        tm_mday: if captures.get(3).is_some() { number(3) } else { 1 },
+        tm_hour: number(4),
+        tm_min: number(5),
+        tm_sec: number(6),
+        ..time::empty_tm()
+    };
+
+    Some(tm.to_timespec().sec)
+}
@@ -16,20 +16,29 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cmp::max;
-use std::collections::VecDeque;
+use std::cmp::{min, max};
+use std::collections::{VecDeque, HashMap};
 use std::iter::{Rev, DoubleEndedIterator};
 
-use unicode_width::UnicodeWidthStr;
+use time;
+use unicode_width::UnicodeWidthChar;
 
+use core::matcher::Matcher;
 use core::filter::{Filter, Parser as FilterParser, Constraint, ParserResult as FilterParserResult};
-use utils::ansi_decoder::{ComponentCollection, AnsiStr};
+use utils::ansi_decoder::{Component, ComponentCollection, AnsiStr};
+
+/// Columns between tab stops, as ncurses expands tabs.
+static TAB_WIDTH: usize = 8;
 
 #[derive(Clone)]
 pub struct Line {
     pub content_without_ansi: String,
     pub components: Option<ComponentCollection>,
     pub width: usize,
+    /// Position in the order lines were received, assigned by `LineCollection`.
+    pub id: usize,
+    /// Seconds since the epoch at which the line was read.
+    pub received_at: i64,
 }
 
 impl Line {
@@ -43,29 +52,103 @@ impl Line {
         };
 
         Line {
-            width: content_without_ansi.width(),
+            width: display_width(&content_without_ansi),
             content_without_ansi: content_without_ansi,
             components: components,
+            id: 0,
+            received_at: time::get_time().sec,
+        }
+    }
+
+    /// Adds text at the end of the line, in the style the line ends with.
+    pub fn append(&mut self, text: &str) {
+        self.content_without_ansi.push_str(text);
+        self.width = display_width(&self.content_without_ansi);
+
+        if let Some(ref mut components) = self.components {
+            components.items.push(Component::Content(text.to_string()));
         }
     }
 
+    /// Rows the line takes up once printed and wrapped to the given width.
     pub fn guess_height(&self, container_width: usize) -> usize {
-        max(1,
-            (self.width as f32 / container_width as f32).ceil() as usize)
+        let text = &self.content_without_ansi;
+        wrap(text, text.len(), container_width).0 + 1
+    }
+
+    /// Row and column the text starting at the given byte offset is printed
+    /// at, relative to the start of the line.
+    pub fn wrapped_position(&self, offset: usize, container_width: usize) -> (usize, usize) {
+        let text = &self.content_without_ansi;
+        let (row, column) = wrap(text, offset, container_width);
+        let next_width = text[offset..]
+            .chars()
+            .next()
+            .map_or(1, |character| max(character.width().unwrap_or(0), 1));
+
+        if column + next_width > max(container_width, 1) {
+            (row + 1, 0)
+        } else {
+            (row, column)
+        }
+    }
+
+    pub fn matches_for(&self, matcher: &Matcher) -> Vec<(usize, &str)> {
+        matcher.find_all(&self.content_without_ansi)
     }
 
-    pub fn matches_for(&self, text: &str) -> Vec<(usize, &str)> {
-        self.content_without_ansi.match_indices(text).collect()
+    pub fn contains(&self, matcher: &Matcher) -> bool {
+        matcher.is_match(&self.content_without_ansi)
     }
+}
+
+/// Width of the text on a single row, with tabs expanded.
+fn display_width(text: &str) -> usize {
+    text.chars().fold(0, |column, character| {
+        if character == '\t' {
+            (column / TAB_WIDTH + 1) * TAB_WIDTH
+        } else {
+            column + character.width().unwrap_or(0)
+        }
+    })
+}
 
-    pub fn contains(&self, text: &str) -> bool {
-        self.content_without_ansi.contains(text)
+/// Lays out the text up to the given byte offset the way a pad does: tabs
+/// move to the next tab stop, or the end of the row, and wide characters that
+/// don't fit at the end of a row go on the next one. Returns the row and
+/// column the text ends on, where a full row only wraps once more text follows.
+fn wrap(text: &str, offset: usize, container_width: usize) -> (usize, usize) {
+    let container_width = max(container_width, 1);
+    let (mut row, mut column) = (0, 0);
+
+    for character in text[..offset].chars() {
+        let width = if character == '\t' {
+            1
+        } else {
+            character.width().unwrap_or(0)
+        };
+        if width == 0 {
+            continue;
+        }
+
+        if column + width > container_width {
+            row += 1;
+            column = 0;
+        }
+        column = if character == '\t' {
+            min((column / TAB_WIDTH + 1) * TAB_WIDTH, container_width)
+        } else {
+            column + width
+        };
     }
+
+    (row, column)
 }
 
 pub struct LineCollection {
     pub entries: VecDeque<Line>,
     capacity: usize,
+    next_id: usize,
 }
 
 impl LineCollection {
@@ -73,9 +156,28 @@ impl LineCollection {
         LineCollection {
             entries: VecDeque::new(),
             capacity: capacity,
+            next_id: 0,
         }
     }
 
+    /// Id the next received line will get.
+    pub fn next_id(&self) -> usize {
+        self.next_id
+    }
+
+    /// Id of the oldest line still held in memory.
+    pub fn first_id(&self) -> usize {
+        self.entries.front().map(|line| line.id).unwrap_or(self.next_id)
+    }
+
+    /// Ids are sequential, so lookups don't need to scan the collection.
+    pub fn get_by_id(&self, id: usize) -> Option<&Line> {
+        if id < self.first_id() {
+            return None;
+        }
+        self.entries.get(id - self.first_id())
+    }
+
     fn clear_excess(&mut self) {
         while self.entries.len() > self.capacity {
             self.entries.pop_front();
@@ -86,8 +188,16 @@ impl LineCollection {
         self.entries.len()
     }
 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
     fn add(&mut self, item: String) {
-        self.entries.push_back(Line::new(item));
+        let mut line = Line::new(item);
+        line.id = self.next_id;
+        self.next_id += 1;
+
+        self.entries.push_back(line);
     }
 }
 
@@ -102,32 +212,93 @@ impl Extend<String> for LineCollection {
 }
 
 
-pub struct ParserState<'a, I>
+pub struct ParserState<'a, 'f, I>
     where I: DoubleEndedIterator<Item = &'a Line>
 {
     iterator: I,
-    parser: FilterParser,
+    parser: FilterParser<'f>,
     pending: Vec<&'a Line>,
+    grouped: bool,
+    groups: Option<Vec<(Vec<&'a Line>, bool)>>,
+    skipped: Vec<&'a Line>,
+    newest_id: Option<usize>,
+    unterminated: bool,
+    open_blocks: Vec<&'a Line>,
+    now: i64,
 }
 
-impl<'a, I> ParserState<'a, I>
+impl<'a, 'f, I> ParserState<'a, 'f, I>
     where I: DoubleEndedIterator<Item = &'a Line>
 {
+    fn new(iterator: I, filter: &'f Filter) -> ParserState<'a, 'f, I> {
+        let mut parser = FilterParser::new(filter);
+        if filter.requires_end || filter.tracks_unterminated() {
+            parser.require_end();
+        }
+
+        ParserState {
+            iterator: iterator,
+            parser: parser,
+            pending: vec![],
+            grouped: filter.group_by.is_some(),
+            groups: None,
+            skipped: vec![],
+            newest_id: None,
+            unterminated: false,
+            open_blocks: vec![],
+            now: time::get_time().sec,
+        }
+    }
+
+    /// Whether the block last returned by `next_block` never got its end.
+    pub fn is_unterminated(&self) -> bool {
+        self.unterminated
+    }
+
+    /// Starts of the blocks missing their end that were passed over so far
+    /// because they have their content but haven't run past a limit yet,
+    /// newest first.
+    pub fn open_blocks(&self) -> &[&'a Line] {
+        &self.open_blocks
+    }
+
+    /// Yields every line of the next matching block at once, newest first.
+    /// Filters without boundaries treat each line as its own block.
+    pub fn next_block(&mut self) -> Option<Vec<&'a Line>> {
+        match self.next() {
+            Some(line) => {
+                let mut block = vec![line];
+                while let Some(pending_line) = self.pending.pop() {
+                    block.push(pending_line);
+                }
+                Some(block)
+            }
+            None => None,
+        }
+    }
+
     fn handle_empty(&mut self) -> Option<I::Item> {
         self.iterator.next()
     }
 
     fn handle_content(&mut self) -> Option<I::Item> {
-        let matcher = self.parser.filter.content.as_ref().unwrap();
+        let filter = self.parser.filter;
 
-        (&mut self.iterator).filter(|line| matcher.is_match(&line.content_without_ansi)).next()
+        (&mut self.iterator)
+            .filter(|line| filter.is_content_match(&line.content_without_ansi))
+            .next()
     }
 
     fn handle_boundaries(&mut self) -> Option<I::Item> {
         if self.pending.is_empty() {
             let mut match_found = false;
+            self.unterminated = false;
+
+            while let Some(line) = self.iterator.next() {
+                if self.newest_id.is_none() {
+                    self.newest_id = Some(line.id);
+                }
 
-            for line in &mut self.iterator {
                 match self.parser.matches(&line.content_without_ansi) {
                     FilterParserResult::Match => self.pending.push(line),
                     FilterParserResult::LastMatch(append) => {
@@ -145,8 +316,29 @@ impl<'a, I> ParserState<'a, I>
                         }
 
                     }
-                    FilterParserResult::NoMatch => {}
+                    FilterParserResult::Unterminated(is_valid) => {
+                        let mut block = self.skipped.drain(..).collect::<Vec<_>>();
+                        block.push(line);
+
+                        if is_valid && self.has_content(&block) {
+                            if self.is_overdue(&block) {
+                                self.pending = block;
+                                self.unterminated = true;
+                                match_found = true;
+                                break;
+                            }
+                            self.open_blocks.push(line);
+                        }
+                        continue;
+                    }
+                    FilterParserResult::NoMatch => {
+                        if self.parser.filter.tracks_unterminated() {
+                            self.skipped.push(line);
+                        }
+                        continue;
+                    }
                 }
+                self.skipped.clear();
             }
             if !(match_found || self.parser.assume_found_matches()) {
                 return None;
@@ -157,28 +349,107 @@ impl<'a, I> ParserState<'a, I>
 
         self.pending.pop()
     }
+
+    /// Whether a block missing its end, given newest line first, has run
+    /// past the length or time limit.
+    fn is_overdue(&self, block: &[&'a Line]) -> bool {
+        let start = block[block.len() - 1];
+        let length = self.newest_id.unwrap_or(start.id) - start.id;
+
+        self.parser.filter.is_past_limits(length, self.now - start.received_at)
+    }
+
+    fn has_content(&self, block: &[&'a Line]) -> bool {
+        let filter = self.parser.filter;
+
+        block.iter().any(|line| filter.is_content_match(&line.content_without_ansi))
+    }
+
+    fn handle_groups(&mut self) -> Option<I::Item> {
+        if self.groups.is_none() {
+            self.groups = Some(self.collect_groups());
+        }
+
+        if self.pending.is_empty() {
+            match self.groups.as_mut().unwrap().pop() {
+                Some((block, unterminated)) => {
+                    self.pending = block;
+                    self.unterminated = unterminated;
+                }
+                None => return None,
+            }
+        }
+
+        self.pending.pop()
+    }
+
+    /// Lines are split by their group key and every group is parsed on its
+    /// own, as if the other ones weren't interleaved with it. Blocks are then
+    /// ordered by their most recent line. Each group usually has a single
+    /// block, so blocks that haven't ended yet can't be told apart from the
+    /// rest and are left out.
+    fn collect_groups(&mut self) -> Vec<(Vec<&'a Line>, bool)> {
+        let filter = self.parser.filter;
+        let mut streams: HashMap<&'a str, Vec<&'a Line>> = HashMap::new();
+
+        for line in &mut self.iterator {
+            if self.newest_id.is_none() {
+                self.newest_id = Some(line.id);
+            }
+
+            if let Some(key) = filter.group_key(&line.content_without_ansi) {
+                streams.entry(key).or_insert_with(Vec::new).push(line);
+            }
+        }
+
+        let mut blocks = vec![];
+        for (_, stream) in streams {
+            blocks.extend(parse_group(stream, filter, self.newest_id).0);
+        }
+        blocks.sort_by_key(|&(ref block, _)| block[block.len() - 1].id);
+
+        blocks
+    }
+}
+
+/// Parses the lines of a single group, given newest first, as if the lines
+/// of the other groups weren't interleaved with them. Returns its blocks,
+/// oldest line first and along with whether they never got their end, and
+/// the starts of the blocks left open.
+pub fn parse_group<'a>(stream: Vec<&'a Line>,
+                       filter: &Filter,
+                       newest_id: Option<usize>)
+                       -> (Vec<(Vec<&'a Line>, bool)>, Vec<&'a Line>) {
+    let mut state = ParserState::new(stream.into_iter(), filter);
+    state.parser.require_end();
+    state.grouped = false;
+    state.newest_id = newest_id;
+
+    let mut blocks = vec![];
+    while let Some(mut block) = state.next_block() {
+        block.reverse();
+        blocks.push((block, state.is_unterminated()));
+    }
+
+    (blocks, state.open_blocks)
 }
 
 pub trait Parser<'a>: Iterator<Item = &'a Line> {
-    fn parse(self, filter: Filter) -> ParserState<'a, Rev<Self>>
+    fn parse<'f>(self, filter: &'f Filter) -> ParserState<'a, 'f, Rev<Self>>
         where Self: DoubleEndedIterator + Sized;
 }
 
 impl<'a, I> Parser<'a> for I
     where I: Iterator<Item = &'a Line>
 {
-    fn parse(self, filter: Filter) -> ParserState<'a, Rev<Self>>
+    fn parse<'f>(self, filter: &'f Filter) -> ParserState<'a, 'f, Rev<Self>>
         where Self: DoubleEndedIterator + Sized
     {
-        ParserState {
-            iterator: self.rev(),
-            pending: vec![],
-            parser: FilterParser::new(filter),
-        }
+        ParserState::new(self.rev(), filter)
     }
 }
 
-impl<'a, I> Iterator for ParserState<'a, I>
+impl<'a, 'f, I> Iterator for ParserState<'a, 'f, I>
     where I: DoubleEndedIterator<Item = &'a Line>
 {
     type Item = I::Item;
@@ -188,6 +459,8 @@ impl<'a, I> Iterator for ParserState<'a, I>
             self.handle_empty()
         } else if self.parser.constraints == vec![Constraint::Content] {
             self.handle_content()
+        } else if self.grouped {
+            self.handle_groups()
         } else {
             self.handle_boundaries()
         }
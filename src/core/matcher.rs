@@ -0,0 +1,93 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use regex::{self, Regex};
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct MatchMode {
+    pub regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
+}
+
+impl MatchMode {
+    pub fn default() -> MatchMode {
+        MatchMode {
+            regex: false,
+            case_insensitive: false,
+            whole_word: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Matcher {
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    pub fn new(text: &str, mode: MatchMode) -> Matcher {
+        let mut pattern = if mode.regex {
+            text.to_string()
+        } else {
+            regex::escape(text)
+        };
+
+        if mode.whole_word {
+            pattern = format!(r"\b(?:{})\b", pattern);
+        }
+
+        if mode.case_insensitive {
+            pattern = format!("(?i){}", pattern);
+        }
+
+        Matcher { regex: Regex::new(&pattern).ok() }
+    }
+
+    pub fn from_regex(regex: Regex) -> Matcher {
+        Matcher { regex: Some(regex) }
+    }
+
+    pub fn pattern(&self) -> &str {
+        match self.regex {
+            Some(ref regex) => regex.as_str(),
+            None => "",
+        }
+    }
+
+    /// An incomplete regex (such as one still being typed) never matches.
+    pub fn is_match(&self, text: &str) -> bool {
+        match self.regex {
+            Some(ref regex) => regex.find_iter(text).any(|found| found.start() != found.end()),
+            None => false,
+        }
+    }
+
+    /// Returns the byte offset and value of every non empty match.
+    pub fn find_all<'a>(&self, text: &'a str) -> Vec<(usize, &'a str)> {
+        match self.regex {
+            Some(ref regex) => {
+                regex.find_iter(text)
+                    .filter(|found| found.start() != found.end())
+                    .map(|found| (found.start(), found.as_str()))
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+}
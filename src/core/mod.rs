@@ -21,4 +21,15 @@ pub mod tail;
 pub mod line;
 pub mod buffer;
 pub mod filter;
+pub mod highlight_rule;
+pub mod correlation;
+pub mod collapse;
+pub mod template;
+pub mod rate;
+pub mod fields;
+pub mod histogram;
+pub mod export;
+pub mod selection;
+pub mod matcher;
 pub mod runner;
+pub mod headless;
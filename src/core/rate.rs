@@ -0,0 +1,81 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+use utils::sparkline::sparkline;
+
+/// Seconds of history kept around for the sparkline.
+pub static HISTORY_LENGTH: i64 = 60;
+/// Seconds the current rate is averaged over, so it doesn't jump around.
+static AVERAGE_LENGTH: i64 = 5;
+
+/// Counts lines for each of the last seconds.
+pub struct RateMeter {
+    counts: VecDeque<(i64, usize)>,
+}
+
+impl RateMeter {
+    pub fn default() -> RateMeter {
+        RateMeter { counts: VecDeque::new() }
+    }
+
+    /// Seconds are given in the same unit as `time::get_time().sec`.
+    pub fn record(&mut self, count: usize, now: i64) {
+        if count == 0 {
+            return;
+        }
+
+        match self.counts.back_mut() {
+            Some(&mut (second, ref mut value)) if second == now => *value += count,
+            _ => self.counts.push_back((now, count)),
+        }
+
+        while self.counts.front().map_or(false, |&(second, _)| second <= now - HISTORY_LENGTH) {
+            self.counts.pop_front();
+        }
+    }
+
+    /// Lines per second over the last few seconds.
+    pub fn per_second(&self, now: i64) -> f32 {
+        let total = self.counts
+            .iter()
+            .filter(|&&(second, _)| second > now - AVERAGE_LENGTH && second <= now)
+            .fold(0, |sum, &(_, count)| sum + count);
+
+        total as f32 / AVERAGE_LENGTH as f32
+    }
+
+    /// How the rate changed over the last minute, oldest first.
+    pub fn sparkline(&self, now: i64, width: usize) -> String {
+        if width == 0 {
+            return String::new();
+        }
+
+        let mut buckets = vec![0; width];
+        for &(second, count) in &self.counts {
+            let age = now - second;
+            if age >= 0 && age < HISTORY_LENGTH {
+                let index = (HISTORY_LENGTH - 1 - age) as usize * width / HISTORY_LENGTH as usize;
+                buckets[index] += count;
+            }
+        }
+
+        sparkline(&buckets)
+    }
+}
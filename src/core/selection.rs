@@ -0,0 +1,89 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+
+use core::line::Line;
+use core::filter::Context;
+use core::matcher::Matcher;
+
+pub static SEPARATOR: &'static str = "--";
+pub static UNTERMINATED_MARKER: &'static str = "-- unterminated --";
+
+/// What an entry stands for, whether it's shown or exported.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Kind {
+    Normal,
+    /// A line kept around a match.
+    Context,
+    /// Splits groups of lines that aren't contiguous.
+    Separator,
+    /// Follows the last line of a block that never got its end.
+    Marker,
+}
+
+/// Keeps the matching lines along with the requested amount of surrounding
+/// context lines, as positions into the entries paired with the kind they're
+/// kept as. Groups that aren't contiguous are split by a separator, taking the
+/// position of the entry that follows it. Only normal entries are searched.
+pub fn matching(entries: &[(Kind, &Line)],
+                matcher: &Matcher,
+                context: Context)
+                -> Vec<(usize, Kind)> {
+    let matches = entries.iter()
+        .map(|&(kind, line)| kind == Kind::Normal && line.contains(matcher))
+        .collect::<Vec<_>>();
+    let mut visible = vec![false; matches.len()];
+
+    for (index, _) in matches.iter().enumerate().filter(|&(_, is_match)| *is_match) {
+        let first = index.saturating_sub(context.before);
+        let last = min(index + context.after, matches.len() - 1);
+        for value in &mut visible[first..last + 1] {
+            *value = true;
+        }
+    }
+
+    let mut result = vec![];
+    let mut previous_index = None;
+
+    for (index, &(kind, _)) in entries.iter().enumerate().filter(|&(i, _)| visible[i]) {
+        let is_detached = previous_index.map_or(false, |previous| previous + 1 < index);
+        if is_detached && !context.is_empty() {
+            result.push((index, Kind::Separator));
+        }
+
+        if !matches[index] && kind == Kind::Normal {
+            result.push((index, Kind::Context));
+        } else {
+            result.push((index, kind));
+        }
+        previous_index = Some(index);
+    }
+
+    result
+}
+
+/// Leaves out the matching lines. Separators and markers aren't lines of
+/// their own, so they're kept.
+pub fn excluding(entries: &[(Kind, &Line)], matcher: &Matcher) -> Vec<(usize, Kind)> {
+    entries.iter()
+        .enumerate()
+        .filter(|&(_, &(kind, line))| kind != Kind::Normal || !line.contains(matcher))
+        .map(|(index, &(kind, _))| (index, kind))
+        .collect()
+}
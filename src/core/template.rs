@@ -0,0 +1,179 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+use std::collections::HashMap;
+
+use regex::{self, Regex};
+
+use core::line::Line;
+use utils::sparkline::sparkline;
+
+/// Share of tokens two lines of the same length need in common to be
+/// considered instances of the same template.
+static SIMILARITY_THRESHOLD: f32 = 0.5;
+static WILDCARD: &'static str = "<*>";
+
+lazy_static! {
+    static ref VARIABLE_TOKENS: Regex = Regex::new(concat!(
+        r#"(?P<str>"[^"]*"|'[^']*')"#,
+        r"|(?P<uuid>\b[0-9a-fA-F]{8}(?:-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}\b)",
+        r"|(?P<ip>\b\d{1,3}(?:\.\d{1,3}){3}\b)",
+        r"|(?P<hex>\b0x[0-9a-fA-F]+\b|\b[0-9a-fA-F]{8,}\b)",
+        r"|(?P<num>\d+(?:\.\d+)?)"
+    )).unwrap();
+    static ref PLACEHOLDERS: Regex = Regex::new(r"<(str|uuid|ip|hex|num|\*)>").unwrap();
+}
+
+/// Lines sharing the same message once their variable parts are left out.
+#[derive(Clone)]
+pub struct Template {
+    pub tokens: Vec<String>,
+    /// Ids of the lines matching the template, oldest first.
+    pub ids: Vec<usize>,
+}
+
+impl Template {
+    fn new(tokens: Vec<String>, id: usize) -> Template {
+        Template {
+            tokens: tokens,
+            ids: vec![id],
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn similarity(&self, tokens: &[String]) -> f32 {
+        if tokens.is_empty() {
+            return 1.0;
+        }
+
+        let shared = self.tokens.iter().zip(tokens).filter(|&(a, b)| a == b).count();
+        shared as f32 / tokens.len() as f32
+    }
+
+    /// Tokens the line doesn't have in common with the template become
+    /// wildcards.
+    fn merge(&mut self, tokens: Vec<String>, id: usize) {
+        for (current, token) in self.tokens.iter_mut().zip(tokens) {
+            if *current != token {
+                *current = WILDCARD.to_string();
+            }
+        }
+        self.ids.push(id);
+    }
+
+    /// Pattern matching the lines of the template, including the ones
+    /// received later on.
+    pub fn to_regex(&self) -> Regex {
+        let tokens = self.tokens.iter().map(|token| token_to_regex(token)).collect::<Vec<_>>();
+        Regex::new(&format!(r"^\s*{}\s*$", tokens.join(r"\s+"))).unwrap()
+    }
+
+    /// How the lines of the template are spread across the given id range.
+    pub fn sparkline(&self, first_id: usize, next_id: usize, width: usize) -> String {
+        let range = next_id.saturating_sub(first_id);
+        if range == 0 || width == 0 {
+            return String::new();
+        }
+
+        let mut buckets = vec![0; width];
+        for id in self.ids.iter().filter(|id| **id >= first_id) {
+            buckets[(id - first_id) * width / range] += 1;
+        }
+
+        sparkline(&buckets)
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.tokens.join(" "))
+    }
+}
+
+/// Replaces strings, uuids, ips, hex ids and numbers with placeholders and
+/// splits what's left into words.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let masked = VARIABLE_TOKENS.replace_all(text, |captures: &regex::Captures| {
+        let name = ["str", "uuid", "ip", "hex", "num"]
+            .iter()
+            .find(|name| captures.name(name).is_some())
+            .unwrap();
+        format!("<{}>", name)
+    });
+
+    masked.split_whitespace().map(|token| token.to_string()).collect()
+}
+
+/// Groups lines into templates, in a similar way to Drain: lines having as
+/// many words are compared token by token, and a line joins the template it
+/// has the most in common with. Templates are ordered by how many lines they
+/// have.
+pub fn cluster<'a, I>(lines: I) -> Vec<Template>
+    where I: IntoIterator<Item = &'a Line>
+{
+    let mut templates_by_length: HashMap<usize, Vec<Template>> = HashMap::new();
+
+    for line in lines {
+        let tokens = tokenize(&line.content_without_ansi);
+        let templates = templates_by_length.entry(tokens.len()).or_insert_with(Vec::new);
+
+        let best_match = templates.iter()
+            .enumerate()
+            .map(|(index, template)| (index, template.similarity(&tokens)))
+            .filter(|&(_, similarity)| similarity >= SIMILARITY_THRESHOLD)
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        match best_match {
+            Some((index, _)) => templates[index].merge(tokens, line.id),
+            None => templates.push(Template::new(tokens, line.id)),
+        }
+    }
+
+    let mut templates = templates_by_length.into_iter()
+        .flat_map(|(_, templates)| templates)
+        .collect::<Vec<_>>();
+    templates.sort_by(|a, b| b.count().cmp(&a.count()).then(a.ids[0].cmp(&b.ids[0])));
+
+    templates
+}
+
+fn token_to_regex(token: &str) -> String {
+    let mut pattern = String::new();
+    let mut last_end = 0;
+
+    for captures in PLACEHOLDERS.captures_iter(token) {
+        let placeholder = captures.get(0).unwrap();
+        pattern.push_str(&regex::escape(&token[last_end..placeholder.start()]));
+        pattern.push_str(match captures.get(1).unwrap().as_str() {
+            "str" => r#"(?:"[^"]*"|'[^']*')"#,
+            "uuid" => r"[0-9a-fA-F]{8}(?:-[0-9a-fA-F]{4}){3}-[0-9a-fA-F]{12}",
+            "ip" => r"\d{1,3}(?:\.\d{1,3}){3}",
+            "hex" => r"(?:0x[0-9a-fA-F]+|[0-9a-fA-F]{8,})",
+            "num" => r"\d+(?:\.\d+)?",
+            _ => r"\S+",
+        });
+        last_end = placeholder.end();
+    }
+    pattern.push_str(&regex::escape(&token[last_end..]));
+
+    pattern
+}
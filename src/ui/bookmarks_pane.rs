@@ -0,0 +1,105 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::min;
+
+use ncurses::*;
+use time::{self, Timespec};
+
+use core::line::Line;
+
+static MAX_VISIBLE_LINES: usize = 4;
+
+/// Lines pinned while selecting, shown above the status line. They stay
+/// pinned even after being evicted from memory.
+pub struct BookmarksPane {
+    pub window: WINDOW,
+    lines: Vec<Line>,
+}
+
+impl BookmarksPane {
+    pub fn new(width: i32) -> BookmarksPane {
+        BookmarksPane {
+            window: newwin(1, width, 0, 0),
+            lines: vec![],
+        }
+    }
+
+    /// Pins the line, or unpins it when it's already pinned.
+    pub fn toggle(&mut self, line: Line) {
+        match self.lines.iter().position(|pinned_line| pinned_line.id == line.id) {
+            Some(index) => {
+                self.lines.remove(index);
+            }
+            None => self.lines.push(line),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Only the most recently pinned lines are shown, below a header.
+    pub fn height(&self) -> i32 {
+        if self.lines.is_empty() {
+            0
+        } else {
+            min(self.lines.len(), MAX_VISIBLE_LINES) as i32 + 1
+        }
+    }
+
+    /// Places the pane so it ends right above the given row.
+    pub fn place(&self, width: i32, bottom: i32) {
+        werase(self.window);
+        wrefresh(self.window);
+        wresize(self.window, self.height(), width);
+        mvwin(self.window, bottom - self.height(), 0);
+        self.render();
+    }
+
+    pub fn render(&self) {
+        let width = getmaxx(self.window) as usize;
+        werase(self.window);
+
+        wattron(self.window, A_BOLD());
+        let header = format!(" Pinned lines ({})", self.lines.len());
+        mvwprintw(self.window, 0, 0, &truncate(&header, width));
+        wattroff(self.window, A_BOLD());
+
+        let first_index = self.lines.len().saturating_sub(MAX_VISIBLE_LINES);
+        for (row, line) in self.lines.iter().skip(first_index).enumerate() {
+            let received_at = time::at(Timespec::new(line.received_at, 0));
+            let text = format!(" {} {}",
+                               time::strftime("%H:%M:%S", &received_at).unwrap(),
+                               line.content_without_ansi);
+            mvwprintw(self.window, row as i32 + 1, 0, &truncate(&text, width));
+        }
+
+        wrefresh(self.window);
+    }
+
+    pub fn destroy(&self) {
+        werase(self.window);
+        wrefresh(self.window);
+        delwin(self.window);
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -16,6 +16,9 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use ncurses::*;
 
 // A negative value is interpreted as the default (original) color.
@@ -23,6 +26,10 @@ use ncurses::*;
 // than the 8 colors already defined.
 pub static COLOR_DEFAULT: i16 = -9;
 
+lazy_static! {
+    static ref ENABLED: AtomicBool = AtomicBool::new(true);
+}
+
 pub struct ColorPair {
     pub foreground: i16,
     pub background: i16,
@@ -49,6 +56,18 @@ impl ColorPair {
         ColorPair::new(COLOR_DEFAULT, COLOR_DEFAULT)
     }
 
+    /// Pairs a background with a foreground that stays readable on top of it.
+    pub fn on(background: i16) -> ColorPair {
+        let dark_backgrounds = [COLOR_BLACK, COLOR_RED, COLOR_BLUE, COLOR_MAGENTA];
+        let foreground = if dark_backgrounds.contains(&background) {
+            COLOR_WHITE
+        } else {
+            COLOR_BLACK
+        };
+
+        ColorPair::new(foreground, background)
+    }
+
     fn calculate_id(&self) -> i16 {
         100 + self.foreground.abs() * 10 + self.background.abs()
     }
@@ -60,9 +79,44 @@ impl ColorPair {
     pub fn to_attr(&self) -> attr_t {
         COLOR_PAIR(self.calculate_id())
     }
+
+    /// Falls back to reverse video when colors are turned off, for what
+    /// would otherwise no longer stand out.
+    pub fn to_standout_attr(&self) -> attr_t {
+        if is_enabled() {
+            self.to_attr()
+        } else {
+            A_REVERSE()
+        }
+    }
+}
+
+/// Colors are left out when the terminal doesn't have any, or when asked to
+/// by the `NO_COLOR` environment variable.
+pub fn start() {
+    let enabled = is_allowed(env::var("NO_COLOR").ok()) && has_colors();
+    if enabled {
+        start_color();
+        use_default_colors();
+    }
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Any value other than an empty one turns colors off, as described at
+/// https://no-color.org
+pub fn is_allowed(no_color: Option<String>) -> bool {
+    no_color.map_or(true, |value| value.is_empty())
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
 }
 
 pub fn generate_pairs() {
+    if !is_enabled() {
+        return;
+    }
+
     let colors = [COLOR_BLACK,
                   COLOR_RED,
                   COLOR_GREEN,
@@ -79,3 +133,18 @@ pub fn generate_pairs() {
         }
     }
 }
+
+pub fn from_name(name: &str) -> Option<i16> {
+    match name {
+        "black" => Some(COLOR_BLACK),
+        "red" => Some(COLOR_RED),
+        "green" => Some(COLOR_GREEN),
+        "yellow" => Some(COLOR_YELLOW),
+        "blue" => Some(COLOR_BLUE),
+        "magenta" => Some(COLOR_MAGENTA),
+        "cyan" => Some(COLOR_CYAN),
+        "white" => Some(COLOR_WHITE),
+        "default" => Some(COLOR_DEFAULT),
+        _ => None,
+    }
+}
@@ -20,21 +20,61 @@ use std::cell::RefCell;
 
 use ncurses::*;
 
+use core::line::Line;
+use core::matcher::Matcher;
+use core::highlight_rule::HighlightRule;
 use ui::color::COLOR_DEFAULT;
 use ui::rendered_line::MatchedLine;
-
-static WINDOW_HEIGHT: i32 = 2500;
+use ui::highlighter::LineHighlighter;
 
 pub struct Content {
     pub window: WINDOW,
     pub state: RefCell<State>,
+    pub highlight_rules: Vec<HighlightRule>,
 }
 
 impl Content {
     pub fn new(width: i32) -> Content {
         Content {
-            window: newpad(WINDOW_HEIGHT, width),
+            window: newpad(LINES(), width),
             state: RefCell::new(State::default()),
+            highlight_rules: vec![],
+        }
+    }
+
+    /// Removes the rule having the same pattern, or adds a new one using a
+    /// palette color no other rule has.
+    pub fn toggle_highlight_rule(&mut self, matcher: Matcher) {
+        let position = self.highlight_rules
+            .iter()
+            .position(|rule| rule.matcher.pattern() == matcher.pattern());
+
+        match position {
+            Some(index) => {
+                self.highlight_rules.remove(index);
+            }
+            None => {
+                let used_colors = self.highlight_rules
+                    .iter()
+                    .map(|rule| rule.color)
+                    .collect::<Vec<_>>();
+                let color = HighlightRule::unused_color(&used_colors);
+                self.highlight_rules.push(HighlightRule::new(matcher, color));
+            }
+        }
+    }
+
+    pub fn print_highlight_rules(&self,
+                                 line: &Line,
+                                 container_width: i32,
+                                 accumulated_height: i32,
+                                 line_height: i32) {
+        for rule in &self.highlight_rules {
+            let highlighter = LineHighlighter::new(self.window,
+                                                   line,
+                                                   container_width,
+                                                   rule.color_pair().to_standout_attr());
+            highlighter.print(&rule.matcher, accumulated_height, line_height);
         }
     }
 
@@ -43,24 +83,16 @@ impl Content {
     }
 
     pub fn resize(&self, width: i32) {
-        wresize(self.window, WINDOW_HEIGHT, width);
+        wresize(self.window, getmaxy(self.window), width);
         wrefresh(self.window);
     }
 
-    pub fn height(&self) -> i32 {
-        let mut current_x: i32 = 0;
-        let mut current_y: i32 = 0;
-        getyx(self.window, &mut current_y, &mut current_x);
-
-        current_y
-    }
-
-    pub fn calculate_height_change<F>(&self, callback: F) -> i32
-        where F: Fn()
-    {
-        let initial_height = self.height();
-        callback();
-        self.height() - initial_height
+    /// Only the lines within the viewport are drawn, but the ones at its
+    /// edges may need more rows than the viewport has.
+    pub fn ensure_height(&self, height: i32) {
+        if getmaxy(self.window) < height {
+            wresize(self.window, height, getmaxx(self.window));
+        }
     }
 
     pub fn highlighted_line(&self) -> MatchedLine {
@@ -75,6 +107,7 @@ pub struct State {
     pub foreground: i16,
     pub background: i16,
     pub highlighted_line: usize,
+    pub highlighted_line_id: usize,
     pub highlighted_match: usize,
 }
 
@@ -85,6 +118,7 @@ impl State {
             foreground: COLOR_DEFAULT,
             background: COLOR_DEFAULT,
             highlighted_line: 0,
+            highlighted_line_id: 0,
             highlighted_match: 0,
         }
     }
@@ -16,9 +16,19 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cell::RefCell;
+use std::cmp::min;
+
 use ui::input::*;
 use ui::readline;
 use ui::navigation::State as NavigationState;
+use ui::key_map::{KeyMap, Action, Scope};
+use ui::highlighter::Highlight;
+
+static TEMPLATES_PAGE_SIZE: i32 = 10;
+static MAX_COUNT: usize = 99_999;
+static WHEEL_SCROLL_LINES: i32 = 3;
+static SIDEWAYS_SCROLL_COLUMNS: i32 = 8;
 
 pub enum Direction {
     Left,
@@ -29,20 +39,57 @@ pub enum Direction {
 pub enum SearchAction {
     ReadInput(Vec<i32>),
     ToggleFilterMode,
+    ToggleInverseMode,
+    CycleContext,
+    ToggleHighlightRule,
+    ToggleRegexMode,
+    ToggleCaseInsensitiveMode,
+    ToggleWholeWordMode,
+    OpenCorrelation,
     FindNextMatch,
     FindPreviousMatch,
 }
 
+pub enum ExportAction {
+    Type(char),
+    Erase,
+    CycleFormat,
+    Save,
+    Cancel,
+}
+
 pub enum Offset {
     Line(i32),
     Viewport(i32),
+    HalfViewport(i32),
     Top,
     Bottom,
 }
 
 pub enum Event {
     ScrollContents(Offset),
+    ScrollSideways(i32),
+    CycleLongLines,
+    TogglePause,
+    Export(ExportAction),
+    ScrollHelp(i32),
+    CloseHelp,
     SelectMenuItem(Direction),
+    CloseMenuItem,
+    ToggleCollapse,
+    SelectTemplate(i32),
+    OpenTemplate,
+    SelectBucket(i32),
+    MoveCursor(i32),
+    CopyLine,
+    PipeLine,
+    TogglePin,
+    CorrelateLine,
+    RepeatSearch(Highlight, usize),
+    Click(i32, i32),
+    DoubleClick(i32, i32),
+    SetMark(char),
+    JumpToMark(char),
     Navigation(NavigationState),
     Search(SearchAction),
     Resize,
@@ -50,81 +97,384 @@ pub enum Event {
     Other,
 }
 
+impl Event {
+    fn repeated(self, count: usize) -> Event {
+        let times = count as i32;
+
+        match self {
+            Event::ScrollContents(Offset::Line(value)) => {
+                Event::ScrollContents(Offset::Line(value * times))
+            }
+            Event::ScrollContents(Offset::Viewport(value)) => {
+                Event::ScrollContents(Offset::Viewport(value * times))
+            }
+            Event::ScrollContents(Offset::HalfViewport(value)) => {
+                Event::ScrollContents(Offset::HalfViewport(value * times))
+            }
+            Event::ScrollSideways(value) => Event::ScrollSideways(value * times),
+            Event::RepeatSearch(highlight, _) => Event::RepeatSearch(highlight, count),
+            event => event,
+        }
+    }
+}
+
+/// Keys typed while browsing that only make sense along with the next one,
+/// such as the count in `20j` or the `g` in `gg`.
+pub struct PendingKeys {
+    count: Option<usize>,
+    prefix: Option<Action>,
+}
+
+impl PendingKeys {
+    pub fn default() -> PendingKeys {
+        PendingKeys {
+            count: None,
+            prefix: None,
+        }
+    }
+
+    fn take_count(&mut self) -> usize {
+        self.prefix = None;
+        self.count.take().unwrap_or(1)
+    }
+}
+
 #[derive(Clone, Hash, PartialEq, Eq)]
 pub enum QueuedEvent {
     Unhighlight(SearchAction),
     PerformSearch,
 }
 
-pub struct EventBuilder {
+pub struct EventBuilder<'a> {
     input: Input,
     key: i32,
+    key_map: &'a KeyMap,
+    pending_keys: &'a RefCell<PendingKeys>,
 }
 
-impl EventBuilder {
-    pub fn new(input: Input, key: i32) -> EventBuilder {
+impl<'a> EventBuilder<'a> {
+    pub fn new(input: Input,
+               key: i32,
+               key_map: &'a KeyMap,
+               pending_keys: &'a RefCell<PendingKeys>)
+               -> EventBuilder<'a> {
         EventBuilder {
             input: input,
             key: key,
+            key_map: key_map,
+            pending_keys: pending_keys,
         }
     }
 
     pub fn construct(&self, current_navigation_state: &NavigationState) -> Event {
-        let mut result = self.create_global_event();
+        // No key was pressed before the read timed out
+        if let Input::None = self.input {
+            return Event::Other;
+        }
+
+        if *current_navigation_state == NavigationState::Help {
+            return self.create_help_event().unwrap_or(Event::Other);
+        }
+
+        // Typed in as part of the text everywhere else
+        if *current_navigation_state != NavigationState::Search &&
+           *current_navigation_state != NavigationState::Export &&
+           self.key_map.action_for(&self.input, Scope::Global, false) == Some(Action::ShowHelp) {
+            return Event::Navigation(NavigationState::Help);
+        }
+
+        // Templates are picked with the same keys that scroll the lines
+        if *current_navigation_state == NavigationState::Templates {
+            return self.create_templates_event().unwrap_or(Event::Other);
+        }
+
+        if *current_navigation_state == NavigationState::Detail {
+            return self.create_detail_event().unwrap_or(Event::Other);
+        }
+
+        if *current_navigation_state == NavigationState::Export {
+            return self.create_export_event().unwrap_or(Event::Other);
+        }
+
+        if *current_navigation_state == NavigationState::Menu {
+            if let Some(event) = self.create_pending_keys_event() {
+                return event;
+            }
+        }
+
+        // The cursor moves with the same keys that scroll the lines
+        if *current_navigation_state == NavigationState::Selection {
+            if let Some(event) = self.create_selection_event() {
+                return event;
+            }
+        }
+        let count = self.pending_keys.borrow_mut().take_count();
+
+        let is_typing = *current_navigation_state == NavigationState::Search;
+        let mut result = self.create_global_event(is_typing);
 
         if result.is_none() {
             result = match *current_navigation_state {
                 NavigationState::Menu => self.create_menu_event(),
                 NavigationState::Search => self.create_search_event(),
+                NavigationState::Histogram => self.create_histogram_event(),
+                NavigationState::Selection => None,
+                NavigationState::Templates | NavigationState::Detail |
+                NavigationState::Export | NavigationState::Help => unreachable!(),
             };
         }
-        result.unwrap_or(Event::Other)
+        result.map_or(Event::Other, |event| event.repeated(count))
+    }
+
+    /// Counts and the keys starting marks and `gg` are only read while
+    /// browsing. Digits bound to an action aren't taken as counts.
+    fn create_pending_keys_event(&self) -> Option<Event> {
+        let mut pending_keys = self.pending_keys.borrow_mut();
+        let value = match self.input {
+            Input::Kb(Key::Char(value), None) => Some(value),
+            Input::Kb(..) => None,
+            _ => return None,
+        };
+        let action = self.key_map.action_for(&self.input, Scope::Menu, false);
+
+        if let Some(prefix) = pending_keys.prefix {
+            pending_keys.take_count();
+            let event = match (prefix, value) {
+                (Action::GoToTop, _) if action == Some(Action::GoToTop) => {
+                    Event::ScrollContents(Offset::Top)
+                }
+                (Action::SetMark, Some(letter)) if letter.is_alphabetic() => {
+                    Event::SetMark(letter)
+                }
+                (Action::JumpToMark, Some(letter)) if letter.is_alphabetic() => {
+                    Event::JumpToMark(letter)
+                }
+                _ => Event::Other,
+            };
+            return Some(event);
+        }
+
+        match action {
+            Some(prefix @ Action::GoToTop) |
+            Some(prefix @ Action::SetMark) |
+            Some(prefix @ Action::JumpToMark) => {
+                pending_keys.prefix = Some(prefix);
+                return Some(Event::Other);
+            }
+            Some(_) => return None,
+            None => {}
+        }
+
+        match value {
+            Some(digit) if digit.is_digit(10) && (digit != '0' || pending_keys.count.is_some()) &&
+                           self.key_map.action_for(&self.input, Scope::Global, false).is_none() => {
+                let count = pending_keys.count.unwrap_or(0) * 10 +
+                            digit.to_digit(10).unwrap() as usize;
+                pending_keys.count = Some(min(count, MAX_COUNT));
+                Some(Event::Other)
+            }
+            _ => None,
+        }
     }
 
     fn create_menu_event(&self) -> Option<Event> {
-        match self.input {
-            Input::Kb(Key::Left, None) => Some(Event::SelectMenuItem(Direction::Left)),
-            Input::Kb(Key::Right, None) => Some(Event::SelectMenuItem(Direction::Right)),
-            Input::Kb(Key::Char('/'), None) |
-            Input::Kb(Key::Char('F'), Some(Modifier::Ctrl)) => {
-                Some(Event::Navigation(NavigationState::Search))
+        match self.key_map.action_for(&self.input, Scope::Menu, false) {
+            Some(Action::PreviousTab) => Some(Event::SelectMenuItem(Direction::Left)),
+            Some(Action::NextTab) => Some(Event::SelectMenuItem(Direction::Right)),
+            Some(Action::Search) => Some(Event::Navigation(NavigationState::Search)),
+            Some(Action::CloseTab) => Some(Event::CloseMenuItem),
+            Some(Action::ToggleCollapse) => Some(Event::ToggleCollapse),
+            Some(Action::ShowTemplates) => Some(Event::Navigation(NavigationState::Templates)),
+            Some(Action::ShowHistogram) => Some(Event::Navigation(NavigationState::Histogram)),
+            Some(Action::HalfPageUp) => Some(Event::ScrollContents(Offset::HalfViewport(1))),
+            Some(Action::HalfPageDown) => Some(Event::ScrollContents(Offset::HalfViewport(-1))),
+            Some(Action::RepeatSearch) => Some(Event::RepeatSearch(Highlight::Next, 1)),
+            Some(Action::RepeatSearchBackwards) => {
+                Some(Event::RepeatSearch(Highlight::Previous, 1))
             }
-            Input::Kb(Key::Char('q'), None) => Some(Event::Quit),
+            Some(Action::SelectLine) => Some(Event::Navigation(NavigationState::Selection)),
+            Some(Action::CycleLongLines) => Some(Event::CycleLongLines),
+            Some(Action::TogglePause) => Some(Event::TogglePause),
+            Some(Action::Quit) => Some(Event::Quit),
             _ => None,
         }
     }
 
     fn create_search_event(&self) -> Option<Event> {
-        match self.input {
-            Input::Kb(Key::Char('n'), Some(Modifier::Alt(_))) => {
-                Some(Event::Search(SearchAction::FindNextMatch))
+        let action = match self.key_map.action_for(&self.input, Scope::Search, false) {
+            Some(Action::NextMatch) => SearchAction::FindNextMatch,
+            Some(Action::PreviousMatch) => SearchAction::FindPreviousMatch,
+            Some(Action::ToggleFilterMode) => SearchAction::ToggleFilterMode,
+            Some(Action::ToggleInverseMode) => SearchAction::ToggleInverseMode,
+            Some(Action::ToggleHighlightRule) => SearchAction::ToggleHighlightRule,
+            Some(Action::CycleContext) => SearchAction::CycleContext,
+            Some(Action::ToggleRegexMode) => SearchAction::ToggleRegexMode,
+            Some(Action::ToggleCaseInsensitiveMode) => SearchAction::ToggleCaseInsensitiveMode,
+            Some(Action::ToggleWholeWordMode) => SearchAction::ToggleWholeWordMode,
+            Some(Action::OpenCorrelation) => SearchAction::OpenCorrelation,
+            Some(Action::LeaveSearch) if !readline::is_history() => {
+                return Some(Event::Navigation(NavigationState::Menu));
             }
-            Input::Kb(Key::Char('p'), Some(Modifier::Alt(_))) => {
-                Some(Event::Search(SearchAction::FindPreviousMatch))
+            _ => return self.create_input_event(),
+        };
+
+        Some(Event::Search(action))
+    }
+
+    fn create_selection_event(&self) -> Option<Event> {
+        match self.key_map.action_for(&self.input, Scope::Selection, false) {
+            Some(Action::MoveCursorUp) => Some(Event::MoveCursor(-1)),
+            Some(Action::MoveCursorDown) => Some(Event::MoveCursor(1)),
+            Some(Action::CopyLine) => Some(Event::CopyLine),
+            Some(Action::PipeLine) => Some(Event::PipeLine),
+            Some(Action::TogglePin) => Some(Event::TogglePin),
+            Some(Action::CorrelateLine) => Some(Event::CorrelateLine),
+            Some(Action::InspectLine) => Some(Event::Navigation(NavigationState::Detail)),
+            Some(Action::LeaveSelection) => Some(Event::Navigation(NavigationState::Menu)),
+            _ => {
+                match self.key_map.action_for(&self.input, Scope::Menu, false) {
+                    Some(Action::Quit) => Some(Event::Quit),
+                    _ => None,
+                }
             }
-            Input::Kb(Key::Char('m'), Some(Modifier::Alt(_))) => {
-                Some(Event::Search(SearchAction::ToggleFilterMode))
+        }
+    }
+
+    fn create_detail_event(&self) -> Option<Event> {
+        if let Input::Resize = self.input {
+            return Some(Event::Resize);
+        }
+
+        match self.key_map.action_for(&self.input, Scope::Detail, false) {
+            Some(Action::CloseDetail) => Some(Event::Navigation(NavigationState::Selection)),
+            _ => {
+                match self.key_map.action_for(&self.input, Scope::Selection, false) {
+                    Some(Action::InspectLine) |
+                    Some(Action::LeaveSelection) => {
+                        Some(Event::Navigation(NavigationState::Selection))
+                    }
+                    _ => None,
+                }
             }
-            Input::Kb(Key::Escape, None) if !readline::is_history() => {
-                Some(Event::Navigation(NavigationState::Menu))
+        }
+    }
+
+    /// The path is typed in, so only keys that can't be part of it are
+    /// taken as commands.
+    fn create_export_event(&self) -> Option<Event> {
+        let action = match self.key_map.action_for(&self.input, Scope::Export, true) {
+            Some(Action::SaveExport) => ExportAction::Save,
+            Some(Action::CycleExportFormat) => ExportAction::CycleFormat,
+            Some(Action::EraseExportPath) => ExportAction::Erase,
+            Some(Action::CancelExport) => ExportAction::Cancel,
+            _ => {
+                return match self.input {
+                    Input::Kb(Key::Char(value), None) if !value.is_control() => {
+                        Some(Event::Export(ExportAction::Type(value)))
+                    }
+                    Input::Resize => Some(Event::Resize),
+                    _ => None,
+                };
+            }
+        };
+
+        Some(Event::Export(action))
+    }
+
+    fn create_help_event(&self) -> Option<Event> {
+        match self.input {
+            Input::Mouse(Mouse::WheelUp) => return Some(Event::ScrollHelp(-1)),
+            Input::Mouse(Mouse::WheelDown) => return Some(Event::ScrollHelp(1)),
+            Input::Resize => return Some(Event::Resize),
+            _ => {}
+        }
+
+        match self.key_map.action_for(&self.input, Scope::Help, false) {
+            Some(Action::ScrollHelpUp) => Some(Event::ScrollHelp(-1)),
+            Some(Action::ScrollHelpDown) => Some(Event::ScrollHelp(1)),
+            Some(Action::HelpPageUp) => Some(Event::ScrollHelp(-TEMPLATES_PAGE_SIZE)),
+            Some(Action::HelpPageDown) => Some(Event::ScrollHelp(TEMPLATES_PAGE_SIZE)),
+            Some(Action::CloseHelp) => Some(Event::CloseHelp),
+            _ => {
+                match self.key_map.action_for(&self.input, Scope::Global, false) {
+                    Some(Action::ShowHelp) => Some(Event::CloseHelp),
+                    _ => None,
+                }
             }
-            _ => self.create_input_event(),
         }
     }
 
-    fn create_global_event(&self) -> Option<Event> {
+    fn create_templates_event(&self) -> Option<Event> {
         match self.input {
-            Input::Kb(Key::Up, None) => Some(Event::ScrollContents(Offset::Line(1))),
-            Input::Kb(Key::Down, None) => Some(Event::ScrollContents(Offset::Line(-1))),
-            Input::Kb(Key::PageUp, None) => Some(Event::ScrollContents(Offset::Viewport(1))),
-            Input::Kb(Key::PageDown, None) => Some(Event::ScrollContents(Offset::Viewport(-1))),
-            Input::Kb(Key::Home, None) => Some(Event::ScrollContents(Offset::Top)),
-            Input::Kb(Key::End, None) => Some(Event::ScrollContents(Offset::Bottom)),
-            Input::Resize => Some(Event::Resize),
-            _ => None,
+            Input::Mouse(Mouse::WheelUp) => return Some(Event::SelectTemplate(-1)),
+            Input::Mouse(Mouse::WheelDown) => return Some(Event::SelectTemplate(1)),
+            Input::Resize => return Some(Event::Resize),
+            _ => {}
+        }
+
+        match self.key_map.action_for(&self.input, Scope::Templates, false) {
+            Some(Action::PreviousTemplate) => Some(Event::SelectTemplate(-1)),
+            Some(Action::NextTemplate) => Some(Event::SelectTemplate(1)),
+            Some(Action::TemplatesPageUp) => Some(Event::SelectTemplate(-TEMPLATES_PAGE_SIZE)),
+            Some(Action::TemplatesPageDown) => Some(Event::SelectTemplate(TEMPLATES_PAGE_SIZE)),
+            Some(Action::OpenTemplate) => Some(Event::OpenTemplate),
+            Some(Action::CloseTemplates) => Some(Event::Navigation(NavigationState::Menu)),
+            _ => {
+                match self.key_map.action_for(&self.input, Scope::Menu, false) {
+                    Some(Action::ShowTemplates) => {
+                        Some(Event::Navigation(NavigationState::Menu))
+                    }
+                    Some(Action::Quit) => Some(Event::Quit),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn create_histogram_event(&self) -> Option<Event> {
+        match self.key_map.action_for(&self.input, Scope::Histogram, false) {
+            Some(Action::PreviousBucket) => Some(Event::SelectBucket(-1)),
+            Some(Action::NextBucket) => Some(Event::SelectBucket(1)),
+            Some(Action::CloseHistogram) => Some(Event::Navigation(NavigationState::Menu)),
+            _ => {
+                match self.key_map.action_for(&self.input, Scope::Menu, false) {
+                    Some(Action::ShowHistogram) => {
+                        Some(Event::Navigation(NavigationState::Menu))
+                    }
+                    Some(Action::Quit) => Some(Event::Quit),
+                    _ => None,
+                }
+            }
         }
     }
 
+    fn create_global_event(&self, is_typing: bool) -> Option<Event> {
+        match self.input {
+            Input::Resize => return Some(Event::Resize),
+            Input::Mouse(ref mouse) => return Some(create_mouse_event(mouse)),
+            _ => {}
+        }
+
+        let offset = match self.key_map.action_for(&self.input, Scope::Global, is_typing) {
+            Some(Action::ScrollLeft) => {
+                return Some(Event::ScrollSideways(-SIDEWAYS_SCROLL_COLUMNS));
+            }
+            Some(Action::ScrollRight) => {
+                return Some(Event::ScrollSideways(SIDEWAYS_SCROLL_COLUMNS));
+            }
+            Some(Action::Export) => return Some(Event::Navigation(NavigationState::Export)),
+            Some(Action::ScrollUp) => Offset::Line(1),
+            Some(Action::ScrollDown) => Offset::Line(-1),
+            Some(Action::PageUp) => Offset::Viewport(1),
+            Some(Action::PageDown) => Offset::Viewport(-1),
+            Some(Action::ScrollToTop) => Offset::Top,
+            Some(Action::ScrollToBottom) => Offset::Bottom,
+            _ => return None,
+        };
+
+        Some(Event::ScrollContents(offset))
+    }
+
     fn create_input_event(&self) -> Option<Event> {
         match self.input {
             Input::Kb(Key::Left, None) => {
@@ -158,3 +508,12 @@ impl EventBuilder {
         }
     }
 }
+
+fn create_mouse_event(mouse: &Mouse) -> Event {
+    match *mouse {
+        Mouse::WheelUp => Event::ScrollContents(Offset::Line(WHEEL_SCROLL_LINES)),
+        Mouse::WheelDown => Event::ScrollContents(Offset::Line(-WHEEL_SCROLL_LINES)),
+        Mouse::Click(row, column) => Event::Click(row, column),
+        Mouse::DoubleClick(row, column) => Event::DoubleClick(row, column),
+    }
+}
@@ -0,0 +1,153 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::{Cell, RefCell};
+
+use ncurses::*;
+
+use core::export::Format;
+use ui::key_map::{KeyMap, Action};
+use ui::theme::Role;
+
+static PROMPT: &'static str = " Export to: ";
+
+/// Asks where the lines shown should be written to, taking the place of the
+/// navigation while it's open.
+pub struct ExportPrompt {
+    pub window: WINDOW,
+    pub format: Cell<Format>,
+    path: RefCell<String>,
+    error: RefCell<Option<String>>,
+    key_map: KeyMap,
+    panel: PANEL,
+}
+
+impl ExportPrompt {
+    pub fn new(position_x: i32, position_y: i32) -> ExportPrompt {
+        let window = newwin(0, 0, position_x, position_y);
+
+        ExportPrompt {
+            window: window,
+            format: Cell::new(Format::Text),
+            path: RefCell::new(String::new()),
+            error: RefCell::new(None),
+            key_map: KeyMap::default(),
+            panel: new_panel(window),
+        }
+    }
+
+    pub fn render(&self) {
+        let path = self.path.borrow();
+        let hints = match *self.error.borrow() {
+            Some(ref message) => format!("   {}", message),
+            None => self.hints(),
+        };
+        let role = if self.error.borrow().is_some() {
+            Role::Alert
+        } else {
+            Role::Bar
+        };
+
+        werase(self.window);
+        wbkgd(self.window, role.to_attr());
+        mvwprintw(self.window, 0, 0, &format!("{}{}", PROMPT, path));
+        let (mut cursor_y, mut cursor_x) = (0, 0);
+        getyx(self.window, &mut cursor_y, &mut cursor_x);
+        wprintw(self.window, &hints);
+        wmove(self.window, cursor_y, cursor_x);
+        wrefresh(self.window);
+    }
+
+    /// Puts the cursor back at the end of the path, along with whatever else
+    /// was staged.
+    pub fn move_cursor(&self) {
+        wnoutrefresh(self.window);
+        doupdate();
+    }
+
+    /// The first key of each command, as in "Tab: text   Enter: save".
+    fn hints(&self) -> String {
+        let commands = [(Action::CycleExportFormat, self.format.get().name()),
+                        (Action::SaveExport, "save"),
+                        (Action::CancelExport, "cancel")];
+
+        commands.iter()
+            .filter_map(|&(action, description)| {
+                self.key_map
+                    .keys(action)
+                    .first()
+                    .map(|key| format!("   {}: {}", key.to_string(), description))
+            })
+            .collect()
+    }
+
+    pub fn set_key_map(&mut self, key_map: KeyMap) {
+        self.key_map = key_map;
+    }
+
+    pub fn path(&self) -> String {
+        self.path.borrow().clone()
+    }
+
+    pub fn type_char(&self, value: char) {
+        self.path.borrow_mut().push(value);
+        self.clear_error();
+    }
+
+    pub fn erase(&self) {
+        self.path.borrow_mut().pop();
+        self.clear_error();
+    }
+
+    pub fn cycle_format(&self) {
+        self.format.set(self.format.get().next());
+        self.clear_error();
+    }
+
+    /// Shown in place of the hints until the path or format change.
+    pub fn show_error(&self, message: String) {
+        *self.error.borrow_mut() = Some(message);
+        self.render();
+    }
+
+    fn clear_error(&self) {
+        *self.error.borrow_mut() = None;
+        self.render();
+    }
+
+    pub fn resize(&self, offset: i32) {
+        mvwin(self.window, offset, 0);
+    }
+
+    pub fn show(&self) {
+        *self.error.borrow_mut() = None;
+        self.render();
+        curs_set(CURSOR_VISIBILITY::CURSOR_VERY_VISIBLE);
+        show_panel(self.panel);
+    }
+
+    pub fn hide(&self) {
+        curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+        hide_panel(self.panel);
+    }
+
+    pub fn destroy(&self) {
+        del_panel(self.panel);
+        delwin(self.window);
+    }
+}
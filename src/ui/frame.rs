@@ -16,29 +16,61 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cmp::{min, max};
+use std::cell::RefCell;
+
 use ncurses::*;
 
 use core::buffer::BufferLines;
+use core::line::Line;
+use core::matcher::Matcher;
 use ui::readline;
 use ui::color;
 use ui::input::read_key;
-use ui::event::{EventBuilder, Event};
+use ui::event::{EventBuilder, Event, PendingKeys};
 use ui::navigation::{Navigation, HEIGHT as NAVIGATION_HEIGHT};
 use ui::content::Content;
 use ui::printer::LinesPrinter;
 use ui::search::Query;
-use ui::rendered_line::RenderedLineCollection;
+use ui::rendered_line::{RenderedLineCollection, Kind};
+use ui::highlighter::{LineHighlighter, MatchCache};
+use ui::template_list::TemplateList;
+use ui::status_bar::{StatusBar, HEIGHT as STATUS_BAR_HEIGHT};
+use ui::histogram_pane::{HistogramPane, HEIGHT as HISTOGRAM_PANE_HEIGHT};
+use ui::key_map::KeyMap;
+use ui::line_detail::LineDetail;
+use ui::bookmarks_pane::BookmarksPane;
+use ui::long_lines::LongLines;
+use ui::help::HelpOverlay;
+use ui::theme::Role;
+use core::histogram::Histogram;
 
-pub static NORMAL_HIGHLIGHT_COLOR: i16 = 5;
-pub static CURRENT_HIGHLIGHT_COLOR: i16 = 6;
+/// Lines aren't wrapped up to this width when they're scrolled sideways.
+static MAX_UNWRAPPED_WIDTH: i32 = 4096;
+static MOUSE_EVENTS: i32 = BUTTON1_CLICKED | BUTTON1_DOUBLE_CLICKED | BUTTON4_PRESSED |
+                           BUTTON5_PRESSED;
 
 pub struct Frame {
     pub width: i32,
     pub height: i32,
     pub rendered_lines: RenderedLineCollection,
     pub initial_rendered_lines: Option<RenderedLineCollection>,
+    pub search_matcher: Option<Matcher>,
+    pub match_cache: MatchCache,
     pub navigation: Navigation,
     pub content: Content,
+    pub template_list: Option<TemplateList>,
+    pub status_bar: StatusBar,
+    pub histogram_pane: Option<HistogramPane>,
+    pub key_map: KeyMap,
+    pub selected_line_id: Option<usize>,
+    pub line_detail: Option<LineDetail>,
+    pub bookmarks_pane: Option<BookmarksPane>,
+    pub help_overlay: Option<HelpOverlay>,
+    pub long_lines: LongLines,
+    pub horizontal_offset: i32,
+    pub is_mouse_captured: bool,
+    pending_keys: RefCell<PendingKeys>,
 }
 
 impl Frame {
@@ -54,8 +86,22 @@ impl Frame {
             height: LINES(),
             rendered_lines: RenderedLineCollection::default(),
             initial_rendered_lines: None,
+            search_matcher: None,
+            match_cache: MatchCache::new(),
             navigation: Navigation::new(LINES() - NAVIGATION_HEIGHT, 0, &menu_item_names),
             content: Content::new(COLS()),
+            template_list: None,
+            status_bar: StatusBar::new(LINES() - NAVIGATION_HEIGHT - STATUS_BAR_HEIGHT, 0),
+            histogram_pane: None,
+            key_map: KeyMap::default(),
+            selected_line_id: None,
+            line_detail: None,
+            bookmarks_pane: None,
+            help_overlay: None,
+            long_lines: LongLines::Wrap,
+            horizontal_offset: 0,
+            is_mouse_captured: false,
+            pending_keys: RefCell::new(PendingKeys::default()),
         }
     }
 
@@ -66,15 +112,25 @@ impl Frame {
     }
 
     pub fn select_left_menu_item(&self) {
-        self.navigation.menu.select(REQ_LEFT_ITEM);
+        self.navigation.menu.select_previous();
     }
 
     pub fn select_right_menu_item(&self) {
-        self.navigation.menu.select(REQ_RIGHT_ITEM);
+        self.navigation.menu.select_next();
     }
 
     pub fn destroy(&self) {
         self.navigation.destroy();
+        self.status_bar.destroy();
+        if let Some(ref pane) = self.histogram_pane {
+            pane.destroy();
+        }
+        if let Some(ref pane) = self.bookmarks_pane {
+            pane.destroy();
+        }
+        if let Some(ref overlay) = self.help_overlay {
+            overlay.destroy();
+        }
         endwin();
         readline::terminate();
     }
@@ -82,47 +138,345 @@ impl Frame {
     pub fn resize(&mut self) {
         getmaxyx(stdscr(), &mut self.height, &mut self.width);
 
-        self.content.resize(self.width);
-        self.navigation.resize(self.width, self.content_height());
+        self.content.resize(self.line_width());
+        if let Some(ref pane) = self.histogram_pane {
+            pane.resize(self.width);
+        }
+        self.status_bar.resize(self.width, self.status_bar_top());
+        if let Some(ref pane) = self.bookmarks_pane {
+            pane.place(self.width, self.status_bar_top());
+        }
+        self.navigation.resize(self.width, self.height - NAVIGATION_HEIGHT);
     }
 
-    pub fn print(&mut self, buffer_lines: &mut BufferLines, query: Option<Query>) {
-        buffer_lines.width = self.width as usize;
+    /// Left off unless asked for, as the terminal no longer selects text
+    /// while mouse events are read.
+    pub fn capture_mouse(&mut self) {
+        mousemask(MOUSE_EVENTS as mmask_t, None);
+        self.is_mouse_captured = true;
+    }
 
+    pub fn print(&mut self, buffer_lines: &BufferLines, query: Option<Query>) {
+        if getmaxx(self.content.window) != self.line_width() {
+            self.content.resize(self.line_width());
+        }
         LinesPrinter::new(self, buffer_lines, query).draw();
-        self.scroll(buffer_lines.buffer.reverse_index.get() as i32);
+
+        if let Some(ref mut pane) = self.histogram_pane {
+            pane.update(Histogram::new(buffer_lines, self.width as usize));
+        }
+
+        self.redraw(buffer_lines.buffer.reverse_index.get() as i32);
+    }
+
+    /// Moves between matches without laying out the lines again.
+    pub fn advance_search(&mut self, buffer_lines: &BufferLines, query: Query, times: usize) {
+        LinesPrinter::new(self, buffer_lines, Some(query)).advance(times);
+        self.redraw(buffer_lines.buffer.reverse_index.get() as i32);
+    }
+
+    /// Draws whatever takes up the content area again, without laying out
+    /// the lines.
+    pub fn redraw(&self, reversed_offset: i32) {
+        if self.template_list.is_some() {
+            self.render_template_list();
+        } else if self.line_detail.is_some() {
+            self.render_line_detail();
+        } else {
+            self.scroll(reversed_offset);
+        }
+    }
+
+    pub fn render_template_list(&self) {
+        if let Some(ref template_list) = self.template_list {
+            template_list.render(&self.content, self.width, self.content_height());
+        }
+    }
+
+    pub fn render_line_detail(&self) {
+        if let Some(ref line_detail) = self.line_detail {
+            line_detail.render(&self.content,
+                               self.width,
+                               self.content_top(),
+                               self.content_height());
+        }
     }
 
+    /// Pins the line, or unpins it when it's already pinned. The lines need
+    /// to be printed again afterwards, as they get more or less room.
+    pub fn toggle_pin(&mut self, line: Line) {
+        let mut pane = self.bookmarks_pane.take().unwrap_or_else(|| BookmarksPane::new(self.width));
+        pane.toggle(line);
+
+        if pane.is_empty() {
+            pane.destroy();
+        } else {
+            pane.place(self.width, self.status_bar_top());
+            self.bookmarks_pane = Some(pane);
+        }
+    }
+
+    /// Selects the line at the bottom of the viewport.
+    pub fn select_last_visible_line(&mut self, reversed_offset: i32) {
+        let last_row = min(self.rendered_lines.height(),
+                           self.offset(reversed_offset) + self.content_height()) - 1;
+        let (index, _) = self.rendered_lines.first_visible(last_row);
+
+        self.selected_line_id = if last_row < 0 {
+            None
+        } else {
+            self.rendered_lines.entries.get(index).map(|entry| entry.line.id)
+        };
+    }
+
+    /// Moves the selection by the given amount of lines, skipping separators
+    /// and markers.
+    pub fn move_selection(&mut self, offset: i32) {
+        let positions = self.rendered_lines
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|&(_, entry)| entry.kind == Kind::Normal)
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        let current = match self.selected_line_id
+            .and_then(|id| self.rendered_lines.position_of(id))
+            .and_then(|index| positions.iter().position(|position| *position == index)) {
+            Some(value) => value as i32,
+            None => return,
+        };
+
+        let next = min(max(0, current + offset), positions.len() as i32 - 1) as usize;
+        self.selected_line_id = Some(self.rendered_lines[positions[next]].line.id);
+    }
+
+    /// Scroll position bringing the given line into view, moving as little as
+    /// possible.
+    pub fn reverse_index_showing(&self, id: usize, reversed_offset: i32) -> i32 {
+        let index = match self.rendered_lines.position_of(id) {
+            Some(value) => value,
+            None => return reversed_offset,
+        };
+        let content_height = self.content_height();
+        let offset = self.offset(reversed_offset);
+        let top = self.rendered_lines.row_of(index);
+        let bottom = top + self.rendered_lines[index].height;
+
+        let new_offset = if top < offset {
+            top
+        } else if bottom > offset + content_height {
+            bottom - content_height
+        } else {
+            offset
+        };
+
+        self.rendered_lines.height() - content_height - new_offset
+    }
+
+    /// Draws the lines that are at least partially visible, the rest of them
+    /// only take part in the layout.
     pub fn scroll(&self, reversed_offset: i32) {
-        let offset = self.rendered_lines.height() - self.height + NAVIGATION_HEIGHT -
-                     reversed_offset;
+        let content_height = self.content_height();
+        let offset = self.offset(reversed_offset);
+        let (first_index, first_line_offset) = self.rendered_lines.first_visible(offset);
+        let visible_lines = self.rendered_lines
+            .entries
+            .iter()
+            .enumerate()
+            .skip(first_index)
+            .scan(first_line_offset, |line_offset, (index, rendered_line)| {
+                if *line_offset >= offset + content_height {
+                    return None;
+                }
+                let current_offset = *line_offset - first_line_offset;
+                *line_offset += rendered_line.height;
+                Some((index, current_offset, rendered_line))
+            })
+            .collect::<Vec<_>>();
+        let drawn_height = visible_lines.last()
+            .map_or(0, |&(_, line_offset, rendered_line)| line_offset + rendered_line.height);
+
+        self.content.clear();
+        self.content.ensure_height(max(drawn_height, content_height) + 1);
+
+        let highlighted_line = self.content.highlighted_line();
+        for (index, line_offset, rendered_line) in visible_lines {
+            rendered_line.print(&self.content, self.line_width(), line_offset);
+            if rendered_line.kind == Kind::Normal &&
+               self.selected_line_id == Some(rendered_line.line.id) {
+                rendered_line.select(&self.content, line_offset);
+            }
+
+            match self.search_matcher {
+                Some(ref matcher) if rendered_line.found_matches.is_some() => {
+                    rendered_line.highlight(matcher,
+                                            &self.content,
+                                            self.line_width(),
+                                            line_offset);
+                    if index == highlighted_line.line &&
+                       highlighted_line.match_index < rendered_line.match_count() &&
+                       self.navigation.search.matches_found {
+                        let highlighter = LineHighlighter::new(self.content.window,
+                                                               &rendered_line.line,
+                                                               self.line_width(),
+                                                               Role::CurrentMatch.to_attr());
+                        highlighter.print_single_match(matcher,
+                                                       highlighted_line.match_index,
+                                                       line_offset);
+                    }
+                }
+                _ => {}
+            }
+
+            self.mark_cut_edges(rendered_line.line.width, line_offset);
+        }
+
+        let content_top = self.content_top();
         prefresh(self.content.window,
-                 offset,
-                 0,
+                 offset - first_line_offset,
+                 self.horizontal_offset,
+                 content_top,
                  0,
-                 0,
-                 self.content_height() - 1,
-                 self.width);
+                 content_top + content_height - 1,
+                 self.width - 1);
+    }
+
+    /// Lines are only wrapped to the window width when they aren't scrolled
+    /// sideways.
+    pub fn line_width(&self) -> i32 {
+        match self.long_lines {
+            LongLines::Wrap => self.width,
+            _ => max(self.width, MAX_UNWRAPPED_WIDTH),
+        }
+    }
+
+    /// Keeps the offset between the left edge and the point where the widest
+    /// visible line is shown entirely.
+    pub fn scroll_sideways(&mut self, columns: i32) {
+        if self.long_lines == LongLines::Wrap {
+            return;
+        }
+
+        let widest = self.rendered_lines
+            .entries
+            .iter()
+            .map(|rendered_line| min(rendered_line.line.width as i32, MAX_UNWRAPPED_WIDTH))
+            .max()
+            .unwrap_or(0);
+        self.horizontal_offset = max(0, min(self.horizontal_offset + columns, widest - self.width));
+    }
+
+    pub fn cycle_long_lines(&mut self) {
+        self.long_lines = self.long_lines.next();
+        self.horizontal_offset = 0;
+    }
+
+    fn mark_cut_edges(&self, line_width: usize, row: i32) {
+        let (left, right) = self.long_lines.cut_edges(line_width,
+                                                      self.horizontal_offset as usize,
+                                                      self.width as usize);
+        if left {
+            mvwaddstr(self.content.window, row, self.horizontal_offset, "…");
+        }
+        if right {
+            mvwaddstr(self.content.window, row, self.horizontal_offset + self.width - 1, "…");
+        }
     }
 
     pub fn watch(&self) -> Event {
         let (input, key) = read_key();
-        EventBuilder::new(input, key).construct(&self.navigation.state)
+        EventBuilder::new(input, key, &self.key_map, &self.pending_keys)
+            .construct(&self.navigation.state)
     }
 
     pub fn reset(&mut self) {
         self.rendered_lines.clear();
         self.initial_rendered_lines = None;
-        self.content.clear();
+        self.search_matcher = None;
         self.navigation.search.matches_found = false;
     }
 
     pub fn max_scroll_value(&self) -> usize {
-        (self.rendered_lines.height() - self.content_height()) as usize
+        max(0, self.rendered_lines.height() - self.content_height()) as usize
+    }
+
+    /// Line of the search match that is currently highlighted, if any.
+    pub fn current_match_line(&self) -> Option<&Line> {
+        if self.search_matcher.is_none() || !self.navigation.search.matches_found {
+            return None;
+        }
+
+        let state = self.content.state.borrow();
+        self.rendered_lines.entries.get(state.highlighted_line).map(|entry| &entry.line)
     }
 
     pub fn content_height(&self) -> i32 {
-        self.height - NAVIGATION_HEIGHT
+        let bookmarks_height = self.bookmarks_pane.as_ref().map_or(0, |pane| pane.height());
+        self.status_bar_top() - bookmarks_height - self.content_top()
+    }
+
+    fn status_bar_top(&self) -> i32 {
+        self.height - NAVIGATION_HEIGHT - STATUS_BAR_HEIGHT
+    }
+
+    /// Row the lines start on, below the histogram when it's shown.
+    pub fn content_top(&self) -> i32 {
+        if self.histogram_pane.is_some() {
+            HISTOGRAM_PANE_HEIGHT
+        } else {
+            0
+        }
+    }
+
+    pub fn open_histogram_pane(&mut self) {
+        if self.histogram_pane.is_none() {
+            self.histogram_pane = Some(HistogramPane::new(self.width));
+        }
+    }
+
+    pub fn close_histogram_pane(&mut self) {
+        if let Some(pane) = self.histogram_pane.take() {
+            pane.destroy();
+        }
+    }
+
+    /// Id of the line shown on the given row of the viewport.
+    pub fn line_id_at(&self, reversed_offset: i32, row: i32) -> Option<usize> {
+        let (index, _) = self.rendered_lines.first_visible(self.offset(reversed_offset) + row);
+        self.rendered_lines.entries.get(index).map(|entry| entry.line.id)
+    }
+
+    /// Row of the content the viewport starts on.
+    fn offset(&self, reversed_offset: i32) -> i32 {
+        max(0,
+            self.rendered_lines.height() - self.content_height() - reversed_offset)
+    }
+
+    /// Scroll position placing the first line received starting with the given
+    /// id at the top of the viewport.
+    pub fn reverse_index_of(&self, buffer_lines: &BufferLines, id: usize) -> i32 {
+        let height_before = if self.rendered_lines.is_partial() {
+            buffer_lines.height_before(id)
+        } else {
+            self.rendered_lines.height_before(id)
+        };
+
+        self.rendered_lines.height() - height_before - self.content_height()
+    }
+
+    /// Height taken by the lines received starting with the given id.
+    pub fn height_since(&self, buffer_lines: &BufferLines, id: usize) -> i32 {
+        if self.rendered_lines.is_partial() {
+            buffer_lines.height_since(id)
+        } else {
+            self.rendered_lines.height_since(id)
+        }
+    }
+
+    /// Whether the lines shown at the given scroll position were laid out.
+    pub fn is_laid_out_at(&self, reversed_offset: i32) -> bool {
+        let offset = self.offset(reversed_offset);
+        self.rendered_lines.is_laid_out(offset, offset + self.content_height() - 1)
     }
 
     pub fn replace_rendered_lines(&mut self, rendered_lines: RenderedLineCollection) {
@@ -141,18 +495,10 @@ fn env_init() {
 
 fn ncurses_init() {
     initscr();
-    start_color();
-    use_default_colors();
+    color::start();
     cbreak();
     noecho();
     curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE);
     halfdelay(1);
     keypad(stdscr(), true);
-
-    init_pair(1, COLOR_WHITE, COLOR_BLUE);
-    init_pair(2, COLOR_BLACK, COLOR_YELLOW);
-    init_pair(3, COLOR_YELLOW, COLOR_BLUE);
-    init_pair(4, COLOR_WHITE, COLOR_MAGENTA);
-    init_pair(5, COLOR_BLACK, COLOR_WHITE);
-    init_pair(6, COLOR_BLACK, COLOR_YELLOW);
 }
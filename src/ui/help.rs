@@ -0,0 +1,247 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::{min, max};
+
+use ncurses::*;
+
+use core::filter::Filter;
+use ui::key_map::{KeyMap, Action, Scope};
+use ui::navigation::State as NavigationState;
+
+static TITLE: &'static str = "Help";
+static MAX_WIDTH: i32 = 100;
+static MARGIN: i32 = 2;
+static MAX_KEYS_WIDTH: usize = 24;
+
+/// Read before the key map is, while browsing.
+static COUNT_KEYS: [(&'static str, &'static str); 1] =
+    [("<number>", "Repeat the next movement or search that many times, as in 20j")];
+static MOUSE_KEYS: [(&'static str, &'static str); 4] =
+    [("Wheel", "Scroll"),
+     ("Click a tab", "Switch to the tab"),
+     ("Click a line", "Put the cursor on the line"),
+     ("Double-click a line", "Copy the line")];
+static SEARCH_MOUSE_KEYS: [(&'static str, &'static str); 1] = [("Wheel", "Scroll")];
+static TEMPLATES_MOUSE_KEYS: [(&'static str, &'static str); 1] = [("Wheel", "Select a template")];
+
+pub struct Section {
+    pub title: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl Section {
+    pub fn new(title: &str, entries: Vec<(String, String)>) -> Section {
+        Section {
+            title: title.to_string(),
+            entries: entries,
+        }
+    }
+}
+
+/// Keys that can be used in the given state, along with the mouse when its
+/// events are read.
+pub fn key_section(key_map: &KeyMap, state: &NavigationState, is_mouse_read: bool) -> Section {
+    let fixed_keys = |keys: &[(&str, &str)]| {
+        keys.iter()
+            .map(|&(key, description)| (key.to_string(), description.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let mut entries = match *state {
+        NavigationState::Menu => {
+            let mut entries = with_global_keys(key_map, key_map.describe(Scope::Menu));
+            entries.extend(fixed_keys(&COUNT_KEYS));
+            entries
+        }
+        NavigationState::Search => key_map.describe(Scope::Search),
+        NavigationState::Selection => {
+            with_global_keys(key_map, key_map.describe(Scope::Selection))
+        }
+        NavigationState::Histogram => {
+            with_global_keys(key_map, key_map.describe(Scope::Histogram))
+        }
+        NavigationState::Templates => key_map.describe(Scope::Templates),
+        NavigationState::Detail => key_map.describe(Scope::Detail),
+        NavigationState::Export | NavigationState::Help => vec![],
+    };
+
+    match *state {
+        _ if !is_mouse_read => {}
+        NavigationState::Menu | NavigationState::Selection | NavigationState::Histogram => {
+            entries.extend(fixed_keys(&MOUSE_KEYS))
+        }
+        NavigationState::Search => entries.extend(fixed_keys(&SEARCH_MOUSE_KEYS)),
+        NavigationState::Templates => entries.extend(fixed_keys(&TEMPLATES_MOUSE_KEYS)),
+        _ => {}
+    }
+
+    Section::new("Keys", entries)
+}
+
+/// Tells which keys close the help, as they can be rebound.
+pub fn title(key_map: &KeyMap) -> String {
+    match key_map.key_names(Action::CloseHelp) {
+        ref keys if keys.is_empty() => TITLE.to_string(),
+        keys => format!("{}  ({} closes)", TITLE, keys),
+    }
+}
+
+/// Global keys taken over by the state, such as the ones moving the cursor
+/// instead of scrolling, aren't listed twice.
+fn with_global_keys(key_map: &KeyMap,
+                    mut entries: Vec<(String, String)>)
+                    -> Vec<(String, String)> {
+    let global_entries = key_map.describe(Scope::Global)
+        .into_iter()
+        .filter(|&(ref keys, _)| !entries.iter().any(|&(ref taken, _)| taken == keys))
+        .collect::<Vec<_>>();
+    entries.extend(global_entries);
+
+    entries
+}
+
+/// Each filter along with the patterns it's made of.
+pub fn filter_section(filters: &[&Filter]) -> Section {
+    let entries = filters.iter()
+        .map(|filter| {
+            let mut patterns = vec![];
+            if let Some(ref regex) = filter.content {
+                patterns.push(format!("contains `{}`", regex.as_str()));
+            }
+            if let Some(ref start) = filter.start {
+                patterns.push(format!("starts with `{}`", start.regex.as_str()));
+            }
+            if let Some(ref end) = filter.end {
+                patterns.push(format!("ends with `{}`", end.regex.as_str()));
+            }
+            if let Some(ref regex) = filter.group_by {
+                patterns.push(format!("grouped by `{}`", regex.as_str()));
+            }
+            if let Some(ref correlation) = filter.correlation {
+                patterns.push(format!("lines having the id `{}`", correlation.id));
+            }
+            if let Some(ref regex) = filter.template {
+                patterns.push(format!("lines of the template `{}`", regex.as_str()));
+            }
+            if patterns.is_empty() {
+                patterns.push("every line".to_string());
+            }
+
+            (filter.name.clone(), patterns.join(", "))
+        })
+        .collect();
+
+    Section::new("Filters", entries)
+}
+
+/// The sections as rows of text, with the entries of each one aligned.
+pub fn lines(sections: &[Section]) -> Vec<String> {
+    let mut lines = vec![];
+
+    for section in sections {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(section.title.clone());
+
+        let keys_width = section.entries
+            .iter()
+            .map(|&(ref key, _)| key.chars().count())
+            .filter(|width| *width <= MAX_KEYS_WIDTH)
+            .max()
+            .unwrap_or(0);
+        for &(ref key, ref description) in &section.entries {
+            lines.push(format!("  {:width$}  {}", key, description, width = keys_width));
+        }
+    }
+
+    lines
+}
+
+/// Drawn over everything else, in a panel of its own.
+pub struct HelpOverlay {
+    pub window: WINDOW,
+    title: String,
+    lines: Vec<String>,
+    offset: usize,
+    panel: PANEL,
+}
+
+impl HelpOverlay {
+    pub fn new(title: String,
+               sections: &[Section],
+               container_width: i32,
+               container_height: i32)
+               -> HelpOverlay {
+        let width = min(MAX_WIDTH, container_width - MARGIN * 2);
+        let height = max(3, container_height - MARGIN * 2);
+        let window = newwin(height,
+                            width,
+                            (container_height - height) / 2,
+                            (container_width - width) / 2);
+
+        let overlay = HelpOverlay {
+            window: window,
+            title: title,
+            lines: lines(sections),
+            offset: 0,
+            panel: new_panel(window),
+        };
+        overlay.render();
+
+        overlay
+    }
+
+    pub fn scroll(&mut self, offset: i32) {
+        let last_offset = self.lines.len().saturating_sub(self.visible_height());
+        let value = max(0, self.offset as i32 + offset) as usize;
+        self.offset = min(value, last_offset);
+        self.render();
+    }
+
+    fn visible_height(&self) -> usize {
+        max(0, getmaxy(self.window) - 3) as usize
+    }
+
+    pub fn render(&self) {
+        let width = max(0, getmaxx(self.window) - 4) as usize;
+
+        werase(self.window);
+        box_(self.window, 0, 0);
+        wattron(self.window, A_BOLD());
+        mvwprintw(self.window, 0, 2, &truncate(&format!(" {} ", self.title), width));
+        wattroff(self.window, A_BOLD());
+
+        let visible_lines = self.lines.iter().skip(self.offset).take(self.visible_height());
+        for (row, line) in visible_lines.enumerate() {
+            mvwprintw(self.window, row as i32 + 2, 2, &truncate(line, width));
+        }
+
+        wrefresh(self.window);
+    }
+
+    pub fn destroy(&self) {
+        del_panel(self.panel);
+        delwin(self.window);
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -17,16 +17,18 @@
  */
 
 use std::cell::RefMut;
+use std::collections::HashMap;
 
-use unicode_width::UnicodeWidthStr;
 use ncurses::*;
 
 use core::line::Line;
+use core::matcher::Matcher;
+use core::collapse::Repetition;
 use ui::printer::Viewport;
 use ui::content::State as ContentState;
 use ui::rendered_line::RenderedLineCollection;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Highlight {
     VisibleOrLast,
     Next,
@@ -38,27 +40,31 @@ pub struct LineHighlighter<'a> {
     line: &'a Line,
     window: WINDOW,
     container_width: i32,
-    color_pair_id: i16,
+    attributes: attr_t,
 }
 
 impl<'a> LineHighlighter<'a> {
     pub fn new(window: WINDOW,
                line: &'a Line,
                container_width: i32,
-               color_pair_id: i16)
+               attributes: attr_t)
                -> LineHighlighter<'a> {
         LineHighlighter {
             line: line,
             window: window,
             container_width: container_width,
-            color_pair_id: color_pair_id,
+            attributes: attributes,
         }
     }
 
-    pub fn print(&self, text: &str, accumulated_height: i32, line_height: i32) -> Vec<usize> {
+    pub fn print(&self,
+                 matcher: &Matcher,
+                 accumulated_height: i32,
+                 line_height: i32)
+                 -> Vec<usize> {
         let mut locations = vec![];
 
-        let matches = &self.line.matches_for(text);
+        let matches = &self.line.matches_for(matcher);
 
         for &(offset_x, value) in matches {
             let location = self.handle_match(offset_x as i32, accumulated_height, value);
@@ -70,24 +76,96 @@ impl<'a> LineHighlighter<'a> {
         locations
     }
 
-    pub fn print_single_match(&self, text: &str, index: usize, offset_y: i32) {
-        let (offset_x, value) = self.line.matches_for(text)[index];
+    pub fn print_single_match(&self, matcher: &Matcher, index: usize, offset_y: i32) {
+        let (offset_x, value) = self.line.matches_for(matcher)[index];
         self.handle_match(offset_x as i32, offset_y, value);
     }
 
-    fn handle_match(&self, mut offset_x: i32, mut offset_y: i32, value: &str) -> usize {
-        let initial_offset_y = offset_y;
+    fn handle_match(&self, offset_x: i32, offset_y: i32, value: &str) -> usize {
+        let (row, column) = wrapped_position(self.line, offset_x as usize, self.container_width);
 
-        offset_x = self.line.content_without_ansi.split_at(offset_x as usize).0.width() as i32;
-        offset_y += offset_x / self.container_width;
-        offset_x %= self.container_width;
+        wattron(self.window, self.attributes);
+        mvwprintw(self.window, offset_y + row as i32, column, value);
+        wattroff(self.window, self.attributes);
 
-        wattron(self.window, COLOR_PAIR(self.color_pair_id));
-        mvwprintw(self.window, offset_y, offset_x, value);
-        wattroff(self.window, COLOR_PAIR(self.color_pair_id));
+        row
+    }
+}
+
+/// Rows, relative to the first row of the line, on which each match starts.
+pub fn match_offsets(line: &Line, matcher: &Matcher, container_width: i32) -> Vec<usize> {
+    line.matches_for(matcher)
+        .iter()
+        .map(|&(offset_x, _)| wrapped_position(line, offset_x, container_width).0)
+        .collect()
+}
+
+/// Rows of the matches found in the lines shown, by the id of their first
+/// line. They're kept for as long as the query and the width stay the same,
+/// so only lines that weren't searched yet are.
+pub struct MatchCache {
+    matcher: Option<Matcher>,
+    container_width: i32,
+    entries: HashMap<usize, (usize, Vec<usize>)>,
+}
+
+impl MatchCache {
+    pub fn new() -> MatchCache {
+        MatchCache {
+            matcher: None,
+            container_width: 0,
+            entries: HashMap::new(),
+        }
+    }
 
-        (offset_y - initial_offset_y) as usize
+    /// Forgets what was found for another query or width, along with the
+    /// lines no longer shown once they outnumber the ones that are.
+    pub fn prepare(&mut self,
+                   matcher: &Matcher,
+                   container_width: i32,
+                   first_id: usize,
+                   shown_count: usize) {
+        let is_stale = self.matcher
+            .as_ref()
+            .map_or(true, |current| current.pattern() != matcher.pattern());
+
+        if is_stale || self.container_width != container_width {
+            self.matcher = Some(matcher.clone());
+            self.container_width = container_width;
+            self.entries.clear();
+        } else if self.entries.len() > 2 * shown_count {
+            self.entries.retain(|id, _| *id >= first_id);
+        }
+    }
+
+    /// Searches the line only when it wasn't already, or when it has been
+    /// repeated since.
+    pub fn offsets(&mut self, repetition: &Repetition) -> &Vec<usize> {
+        let matcher = self.matcher.as_ref().unwrap();
+        let container_width = self.container_width;
+        let entry = self.entries.entry(repetition.first.id).or_insert((0, vec![]));
+
+        if entry.0 != repetition.count {
+            let offsets = if repetition.count > 1 {
+                match_offsets(&repetition.to_line(), matcher, container_width)
+            } else {
+                match_offsets(repetition.first, matcher, container_width)
+            };
+            *entry = (repetition.count, offsets);
+        }
+
+        &entry.1
     }
+
+    pub fn get(&self, id: usize) -> Option<&Vec<usize>> {
+        self.entries.get(&id).map(|entry| &entry.1)
+    }
+}
+
+fn wrapped_position(line: &Line, offset_x: usize, container_width: i32) -> (usize, i32) {
+    let (row, column) = line.wrapped_position(offset_x, container_width as usize);
+
+    (row, column as i32)
 }
 
 pub struct State<'a> {
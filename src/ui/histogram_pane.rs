@@ -0,0 +1,127 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::{min, max};
+
+use ncurses::*;
+
+use core::histogram::Histogram;
+use ui::theme::Role;
+
+static BAR_HEIGHT: i32 = 5;
+pub static HEIGHT: i32 = 6;
+static BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+static HINT: &'static str = "←/→ selects a time range, Esc closes ";
+
+/// Bar chart of the selected tab's lines over time, drawn above them.
+pub struct HistogramPane {
+    pub window: WINDOW,
+    histogram: Option<Histogram>,
+    selected: usize,
+}
+
+impl HistogramPane {
+    pub fn new(width: i32) -> HistogramPane {
+        HistogramPane {
+            window: newwin(HEIGHT, width, 0, 0),
+            histogram: None,
+            selected: usize::max_value(),
+        }
+    }
+
+    /// Keeps the selected bucket when possible, otherwise the most recent one
+    /// is selected.
+    pub fn update(&mut self, histogram: Histogram) {
+        self.selected = min(self.selected, histogram.buckets.len().saturating_sub(1));
+        self.histogram = Some(histogram);
+        self.render();
+    }
+
+    pub fn select(&mut self, offset: i32) {
+        let last_index = self.histogram.as_ref().map_or(0, |histogram| histogram.buckets.len());
+        let index = max(0, self.selected as i32 + offset);
+        self.selected = min(index as usize, last_index.saturating_sub(1));
+        self.render();
+    }
+
+    /// Oldest line of the selected bucket.
+    pub fn selected_line_id(&self) -> Option<usize> {
+        self.histogram
+            .as_ref()
+            .and_then(|histogram| histogram.buckets.get(self.selected))
+            .and_then(|bucket| bucket.first_id)
+    }
+
+    pub fn render(&self) {
+        werase(self.window);
+
+        if let Some(ref histogram) = self.histogram {
+            let highest = max(1, histogram.highest_count());
+
+            for (column, bucket) in histogram.buckets.iter().enumerate() {
+                let attributes = if column == self.selected {
+                    Role::CurrentMatch.to_attr()
+                } else {
+                    0
+                };
+                let mut eighths = (bucket.count * BAR_HEIGHT as usize * 8 / highest) as i32;
+                if bucket.count > 0 {
+                    eighths = max(1, eighths);
+                }
+
+                wattron(self.window, attributes);
+                for row in 0..BAR_HEIGHT {
+                    let level = eighths - (BAR_HEIGHT - 1 - row) * 8;
+                    let block = if level > 0 {
+                        BLOCKS[min(level, 8) as usize - 1]
+                    } else {
+                        ' '
+                    };
+                    mvwprintw(self.window, row, column as i32, &block.to_string());
+                }
+                wattroff(self.window, attributes);
+            }
+
+            if let Some(bucket) = histogram.buckets.get(self.selected) {
+                let label = format!("{} · {} lines", histogram.label(self.selected), bucket.count);
+                wattron(self.window, A_BOLD());
+                mvwprintw(self.window, BAR_HEIGHT, 0, &label);
+                wattroff(self.window, A_BOLD());
+            }
+        }
+
+        let hint_column = getmaxx(self.window) - HINT.chars().count() as i32;
+        if hint_column > 0 {
+            wattron(self.window, A_DIM());
+            mvwprintw(self.window, BAR_HEIGHT, hint_column, HINT);
+            wattroff(self.window, A_DIM());
+        }
+
+        wrefresh(self.window);
+    }
+
+    pub fn resize(&self, width: i32) {
+        wresize(self.window, HEIGHT, width);
+    }
+
+    pub fn destroy(&self) {
+        werase(self.window);
+        wrefresh(self.window);
+        delwin(self.window);
+    }
+}
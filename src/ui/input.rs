@@ -21,12 +21,15 @@ use std::char;
 use ncurses::*;
 
 pub static ESCAPE_CODE: i32 = 27;
+const ENTER_CODE: i32 = 10;
+const TAB_CODE: i32 = 9;
 pub static KEY_LEFT_SEQ: [i32; 3] = [27, 91, 68];
 pub static KEY_RIGHT_SEQ: [i32; 3] = [27, 91, 67];
 pub static KEY_HOME_SEQ: [i32; 3] = [27, 91, 72];
 pub static KEY_END_SEQ: [i32; 3] = [27, 91, 70];
 pub static KEY_BACKSPACE_SEQ: [i32; 1] = [127];
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Key {
     Left,
     Right,
@@ -37,6 +40,7 @@ pub enum Key {
     PageUp,
     PageDown,
     Tab,
+    Enter,
     Backspace,
     Delete,
     Char(char),
@@ -49,8 +53,17 @@ pub enum Modifier {
     Ctrl,
 }
 
+/// Clicks carry the row and column they were made at.
+pub enum Mouse {
+    WheelUp,
+    WheelDown,
+    Click(i32, i32),
+    DoubleClick(i32, i32),
+}
+
 pub enum Input {
     Kb(Key, Option<Modifier>),
+    Mouse(Mouse),
     Resize,
     None,
 }
@@ -61,6 +74,7 @@ pub fn read_key() -> (Input, i32) {
     let input = match key {
         ERR => Input::None,
         KEY_RESIZE => Input::Resize,
+        KEY_MOUSE => read_mouse(),
         KEY_LEFT => Input::Kb(Key::Left, None),
         KEY_RIGHT => Input::Kb(Key::Right, None),
         KEY_UP => Input::Kb(Key::Up, None),
@@ -71,12 +85,39 @@ pub fn read_key() -> (Input, i32) {
         KEY_END => Input::Kb(Key::End, None),
         KEY_DC => Input::Kb(Key::Delete, None),
         KEY_BACKSPACE => Input::Kb(Key::Backspace, None),
-        KEY_BTAB => Input::Kb(Key::Tab, None),
+        KEY_BTAB | TAB_CODE => Input::Kb(Key::Tab, None),
+        KEY_ENTER | ENTER_CODE => Input::Kb(Key::Enter, None),
         value => parse_key_code(value),
     };
     (input, key)
 }
 
+fn read_mouse() -> Input {
+    let mut event = MEVENT {
+        id: 0,
+        x: 0,
+        y: 0,
+        z: 0,
+        bstate: 0,
+    };
+    if getmouse(&mut event) != OK {
+        return Input::None;
+    }
+
+    let is_pressed = |button: i32| event.bstate & button as mmask_t != 0;
+    if is_pressed(BUTTON4_PRESSED) {
+        Input::Mouse(Mouse::WheelUp)
+    } else if is_pressed(BUTTON5_PRESSED) {
+        Input::Mouse(Mouse::WheelDown)
+    } else if is_pressed(BUTTON1_DOUBLE_CLICKED) {
+        Input::Mouse(Mouse::DoubleClick(event.y, event.x))
+    } else if is_pressed(BUTTON1_CLICKED) {
+        Input::Mouse(Mouse::Click(event.y, event.x))
+    } else {
+        Input::None
+    }
+}
+
 fn parse_key_code(code: i32) -> Input {
     let mut modifier = None;
     let mut pending = code;
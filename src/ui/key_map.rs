@@ -0,0 +1,459 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rustc_serialize::{Decodable, Decoder};
+
+use ui::input::{Input, Key, Modifier};
+
+/// Where an action is available: everywhere, while browsing tabs, while
+/// typing a search query, while a line is selected or while one of the panes
+/// taking over the keys is open.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Scope {
+    Global,
+    Menu,
+    Search,
+    Selection,
+    Detail,
+    Templates,
+    Histogram,
+    Export,
+    Help,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    ScrollUp,
+    ScrollDown,
+    PageUp,
+    PageDown,
+    ScrollToTop,
+    ScrollToBottom,
+    HalfPageUp,
+    HalfPageDown,
+    GoToTop,
+    SetMark,
+    JumpToMark,
+    ScrollLeft,
+    ScrollRight,
+    PreviousTab,
+    NextTab,
+    Search,
+    CloseTab,
+    ToggleCollapse,
+    ShowTemplates,
+    ShowHistogram,
+    RepeatSearch,
+    RepeatSearchBackwards,
+    SelectLine,
+    CycleLongLines,
+    TogglePause,
+    Export,
+    ShowHelp,
+    Quit,
+    LeaveSearch,
+    NextMatch,
+    PreviousMatch,
+    ToggleFilterMode,
+    ToggleInverseMode,
+    ToggleHighlightRule,
+    CycleContext,
+    ToggleRegexMode,
+    ToggleCaseInsensitiveMode,
+    ToggleWholeWordMode,
+    OpenCorrelation,
+    MoveCursorUp,
+    MoveCursorDown,
+    CopyLine,
+    PipeLine,
+    TogglePin,
+    CorrelateLine,
+    InspectLine,
+    LeaveSelection,
+    CloseDetail,
+    PreviousTemplate,
+    NextTemplate,
+    TemplatesPageUp,
+    TemplatesPageDown,
+    OpenTemplate,
+    CloseTemplates,
+    PreviousBucket,
+    NextBucket,
+    CloseHistogram,
+    SaveExport,
+    CycleExportFormat,
+    EraseExportPath,
+    CancelExport,
+    ScrollHelpUp,
+    ScrollHelpDown,
+    HelpPageUp,
+    HelpPageDown,
+    CloseHelp,
+}
+
+/// Every action along with its name in the config and its default keys.
+static DEFAULT_BINDINGS: [(Action, &'static str, Scope, &'static [&'static str]); 66] = [
+    (Action::ScrollUp, "scroll_up", Scope::Global, &["Up", "k"]),
+    (Action::ScrollDown, "scroll_down", Scope::Global, &["Down", "j"]),
+    (Action::PageUp, "page_up", Scope::Global, &["PageUp"]),
+    (Action::PageDown, "page_down", Scope::Global, &["PageDown"]),
+    (Action::ScrollToTop, "scroll_to_top", Scope::Global, &["Home"]),
+    (Action::ScrollToBottom, "scroll_to_bottom", Scope::Global, &["End", "G"]),
+    (Action::ScrollLeft, "scroll_left", Scope::Global, &["<"]),
+    (Action::ScrollRight, "scroll_right", Scope::Global, &[">"]),
+    (Action::Export, "export", Scope::Global, &["e", "Alt-e"]),
+    (Action::ShowHelp, "show_help", Scope::Global, &["?"]),
+    (Action::HalfPageUp, "half_page_up", Scope::Menu, &["Ctrl-U"]),
+    (Action::HalfPageDown, "half_page_down", Scope::Menu, &["Ctrl-D"]),
+    (Action::GoToTop, "go_to_top", Scope::Menu, &["g"]),
+    (Action::SetMark, "set_mark", Scope::Menu, &["m"]),
+    (Action::JumpToMark, "jump_to_mark", Scope::Menu, &["'"]),
+    (Action::PreviousTab, "previous_tab", Scope::Menu, &["Left"]),
+    (Action::NextTab, "next_tab", Scope::Menu, &["Right"]),
+    (Action::Search, "search", Scope::Menu, &["/", "Ctrl-F"]),
+    (Action::CloseTab, "close_tab", Scope::Menu, &["x"]),
+    (Action::ToggleCollapse, "toggle_collapse", Scope::Menu, &["c"]),
+    (Action::ShowTemplates, "show_templates", Scope::Menu, &["t"]),
+    (Action::ShowHistogram, "show_histogram", Scope::Menu, &["h"]),
+    (Action::RepeatSearch, "repeat_search", Scope::Menu, &["n"]),
+    (Action::RepeatSearchBackwards, "repeat_search_backwards", Scope::Menu, &["N"]),
+    (Action::SelectLine, "select_line", Scope::Menu, &["Enter"]),
+    (Action::CycleLongLines, "cycle_long_lines", Scope::Menu, &["w"]),
+    (Action::TogglePause, "toggle_pause", Scope::Menu, &["Space"]),
+    (Action::Quit, "quit", Scope::Menu, &["q"]),
+    (Action::LeaveSearch, "leave_search", Scope::Search, &["Escape"]),
+    (Action::NextMatch, "next_match", Scope::Search, &["Alt-n"]),
+    (Action::PreviousMatch, "previous_match", Scope::Search, &["Alt-p"]),
+    (Action::ToggleFilterMode, "toggle_filter_mode", Scope::Search, &["Alt-m"]),
+    (Action::ToggleInverseMode, "toggle_inverse_mode", Scope::Search, &["Alt-v"]),
+    (Action::ToggleHighlightRule, "toggle_highlight_rule", Scope::Search, &["Alt-h"]),
+    (Action::CycleContext, "cycle_context", Scope::Search, &["Alt-c"]),
+    (Action::ToggleRegexMode, "toggle_regex_mode", Scope::Search, &["Alt-r"]),
+    (Action::ToggleCaseInsensitiveMode, "toggle_case_insensitive_mode", Scope::Search, &["Alt-i"]),
+    (Action::ToggleWholeWordMode, "toggle_whole_word_mode", Scope::Search, &["Alt-w"]),
+    (Action::OpenCorrelation, "open_correlation", Scope::Search, &["Alt-t"]),
+    (Action::MoveCursorUp, "move_cursor_up", Scope::Selection, &["Up", "k"]),
+    (Action::MoveCursorDown, "move_cursor_down", Scope::Selection, &["Down", "j"]),
+    (Action::CopyLine, "copy_line", Scope::Selection, &["y"]),
+    (Action::PipeLine, "pipe_line", Scope::Selection, &["|"]),
+    (Action::TogglePin, "toggle_pin", Scope::Selection, &["p"]),
+    (Action::CorrelateLine, "correlate_line", Scope::Selection, &["t"]),
+    (Action::InspectLine, "inspect_line", Scope::Selection, &["Enter"]),
+    (Action::LeaveSelection, "leave_selection", Scope::Selection, &["Escape"]),
+    (Action::CloseDetail, "close_detail", Scope::Detail, &["Escape"]),
+    (Action::PreviousTemplate, "previous_template", Scope::Templates, &["Up"]),
+    (Action::NextTemplate, "next_template", Scope::Templates, &["Down"]),
+    (Action::TemplatesPageUp, "templates_page_up", Scope::Templates, &["PageUp"]),
+    (Action::TemplatesPageDown, "templates_page_down", Scope::Templates, &["PageDown"]),
+    (Action::OpenTemplate, "open_template", Scope::Templates, &["Enter"]),
+    (Action::CloseTemplates, "close_templates", Scope::Templates, &["Escape"]),
+    (Action::PreviousBucket, "previous_bucket", Scope::Histogram, &["Left"]),
+    (Action::NextBucket, "next_bucket", Scope::Histogram, &["Right"]),
+    (Action::CloseHistogram, "close_histogram", Scope::Histogram, &["Escape"]),
+    (Action::SaveExport, "save_export", Scope::Export, &["Enter"]),
+    (Action::CycleExportFormat, "cycle_export_format", Scope::Export, &["Tab"]),
+    // Most terminals send backspace as `^?`
+    (Action::EraseExportPath, "erase_export_path", Scope::Export, &["Backspace", "Ctrl-?"]),
+    (Action::CancelExport, "cancel_export", Scope::Export, &["Escape"]),
+    (Action::ScrollHelpUp, "scroll_help_up", Scope::Help, &["Up", "k"]),
+    (Action::ScrollHelpDown, "scroll_help_down", Scope::Help, &["Down", "j"]),
+    (Action::HelpPageUp, "help_page_up", Scope::Help, &["PageUp"]),
+    (Action::HelpPageDown, "help_page_down", Scope::Help, &["PageDown"]),
+    (Action::CloseHelp, "close_help", Scope::Help, &["Escape", "q"]),
+];
+
+static NAMED_KEYS: [(Key, &'static str); 14] = [(Key::Left, "Left"),
+                                                (Key::Right, "Right"),
+                                                (Key::Up, "Up"),
+                                                (Key::Down, "Down"),
+                                                (Key::Home, "Home"),
+                                                (Key::End, "End"),
+                                                (Key::PageUp, "PageUp"),
+                                                (Key::PageDown, "PageDown"),
+                                                (Key::Tab, "Tab"),
+                                                (Key::Enter, "Enter"),
+                                                (Key::Backspace, "Backspace"),
+                                                (Key::Delete, "Delete"),
+                                                (Key::Escape, "Escape"),
+                                                (Key::Char(' '), "Space")];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum KeyModifier {
+    Alt,
+    Ctrl,
+}
+
+/// A key along with an optional modifier, written as `q`, `PageUp`, `Ctrl-F`
+/// or `Alt-n`. Letters pressed along with Ctrl are case insensitive.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeySpec {
+    key: Key,
+    modifier: Option<KeyModifier>,
+}
+
+impl KeySpec {
+    pub fn parse(spec: &str) -> Option<KeySpec> {
+        let lowercase_spec = spec.to_lowercase();
+        let (modifier, name) = if lowercase_spec.starts_with("ctrl-") && spec.len() > 5 {
+            (Some(KeyModifier::Ctrl), &spec[5..])
+        } else if lowercase_spec.starts_with("alt-") && spec.len() > 4 {
+            (Some(KeyModifier::Alt), &spec[4..])
+        } else {
+            (None, spec)
+        };
+
+        let named_key = NAMED_KEYS.iter()
+            .find(|&&(_, key_name)| key_name.to_lowercase() == name.to_lowercase())
+            .map(|&(key, _)| key);
+        let mut chars = name.chars();
+        let key = match (named_key, chars.next(), chars.next()) {
+            (Some(value), _, _) => value,
+            (None, Some(value), None) => Key::Char(value),
+            _ => return None,
+        };
+
+        match (key, modifier) {
+            (Key::Char(value), Some(KeyModifier::Ctrl)) => {
+                let uppercase = value.to_uppercase().next().unwrap();
+                Some(KeySpec::new(Key::Char(uppercase), modifier))
+            }
+            (_, Some(KeyModifier::Ctrl)) => None,
+            _ => Some(KeySpec::new(key, modifier)),
+        }
+    }
+
+    fn new(key: Key, modifier: Option<KeyModifier>) -> KeySpec {
+        KeySpec {
+            key: key,
+            modifier: modifier,
+        }
+    }
+
+    pub fn matches(&self, input: &Input) -> bool {
+        match *input {
+            Input::Kb(key, ref modifier) => {
+                let modifier = match *modifier {
+                    Some(Modifier::Alt(_)) => Some(KeyModifier::Alt),
+                    Some(Modifier::Ctrl) => Some(KeyModifier::Ctrl),
+                    None => None,
+                };
+                key == self.key && modifier == self.modifier
+            }
+            _ => false,
+        }
+    }
+
+    /// The letter of keys pressed along with Alt, such as `n` for `Alt-n`.
+    pub fn alt_letter(&self) -> Option<char> {
+        match (self.key, self.modifier) {
+            (Key::Char(value), Some(KeyModifier::Alt)) if value.is_alphabetic() => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Keys that would otherwise be typed into the search field.
+    pub fn is_text(&self) -> bool {
+        match self.key {
+            Key::Char(_) => self.modifier.is_none(),
+            _ => false,
+        }
+    }
+}
+
+impl ToString for KeySpec {
+    fn to_string(&self) -> String {
+        let name = NAMED_KEYS.iter()
+            .find(|&&(key, _)| key == self.key)
+            .map(|&(_, name)| name.to_string())
+            .unwrap_or_else(|| {
+                match self.key {
+                    Key::Char(value) => value.to_string(),
+                    _ => String::new(),
+                }
+            });
+
+        match self.modifier {
+            Some(KeyModifier::Ctrl) => format!("Ctrl-{}", name),
+            Some(KeyModifier::Alt) => format!("Alt-{}", name),
+            None => name,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    action: Action,
+    name: &'static str,
+    scope: Scope,
+    keys: Vec<KeySpec>,
+}
+
+/// Tells which action each key triggers. Starts out with the default keys,
+/// which the `[keys]` section of the config can replace action by action.
+#[derive(Clone)]
+pub struct KeyMap {
+    bindings: Vec<Binding>,
+}
+
+impl KeyMap {
+    pub fn default() -> KeyMap {
+        let bindings = DEFAULT_BINDINGS.iter()
+            .map(|&(action, name, scope, keys)| {
+                Binding {
+                    action: action,
+                    name: name,
+                    scope: scope,
+                    keys: keys.iter().map(|key| KeySpec::parse(key).unwrap()).collect(),
+                }
+            })
+            .collect();
+
+        KeyMap { bindings: bindings }
+    }
+
+    /// Keys producing text are skipped while typing, so binding a letter to
+    /// a global action doesn't get in the way of searching.
+    pub fn action_for(&self, input: &Input, scope: Scope, is_typing: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.scope == scope)
+            .find(|binding| {
+                binding.keys.iter().any(|key| key.matches(input) && !(is_typing && key.is_text()))
+            })
+            .map(|binding| binding.action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeySpec] {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action)
+            .map_or(&[], |binding| &binding.keys[..])
+    }
+
+    /// Keys bound to the action, as in "Escape, q".
+    pub fn key_names(&self, action: Action) -> String {
+        self.keys(action).iter().map(|key| key.to_string()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Returns false for unknown action names.
+    pub fn bind(&mut self, name: &str, keys: Vec<KeySpec>) -> bool {
+        match self.bindings.iter_mut().find(|binding| binding.name == name) {
+            Some(binding) => {
+                binding.keys = keys;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Keys of each action available in the scope, along with what the
+    /// action does, as in ("Ctrl-F, /", "Search"). Keys only starting an
+    /// action are shown along with the key that completes it, as in "gg".
+    pub fn describe(&self, scope: Scope) -> Vec<(String, String)> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.scope == scope)
+            .map(|binding| {
+                let keys = binding.keys
+                    .iter()
+                    .map(|key| {
+                        let key = key.to_string();
+                        match binding.action {
+                            Action::GoToTop => format!("{0}{0}", key),
+                            Action::SetMark | Action::JumpToMark => format!("{}<letter>", key),
+                            _ => key,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let mut description = binding.name.replace('_', " ");
+                description = description[..1].to_uppercase() + &description[1..];
+
+                (keys.join(", "), description)
+            })
+            .collect()
+    }
+
+    /// The `[keys]` config section matching the current bindings.
+    pub fn to_toml(&self) -> String {
+        let mut lines = vec!["[keys]".to_string()];
+
+        for binding in &self.bindings {
+            let keys = binding.keys
+                .iter()
+                .map(|key| toml_string(&key.to_string()))
+                .collect::<Vec<_>>();
+            let value = if keys.len() == 1 {
+                keys[0].clone()
+            } else {
+                format!("[{}]", keys.join(", "))
+            };
+            lines.push(format!("{} = {}", binding.name, value));
+        }
+
+        lines.join("\n")
+    }
+}
+
+pub fn action_names() -> Vec<&'static str> {
+    DEFAULT_BINDINGS.iter().map(|&(_, name, _, _)| name).collect()
+}
+
+/// Each action takes either a single key or a list of them. Unknown actions
+/// are skipped.
+impl Decodable for KeyMap {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<KeyMap, D::Error> {
+        let mut key_map = KeyMap::default();
+
+        try!(decoder.read_map(|d, len| {
+            for index in 0..len {
+                let name = try!(d.read_map_elt_key(index, |d| d.read_str()));
+                let specs = try!(d.read_map_elt_val(index, |d| {
+                    match d.read_str() {
+                        Ok(value) => Ok(vec![value]),
+                        Err(_) => Vec::<String>::decode(d),
+                    }
+                }));
+
+                // Unknown actions are reported as warnings by the config file
+                if !action_names().contains(&name.as_str()) {
+                    continue;
+                }
+
+                let mut keys = vec![];
+                for spec in specs {
+                    match KeySpec::parse(&spec) {
+                        Some(key) => keys.push(key),
+                        None => {
+                            let message = format!("invalid key `{}` for `{}`", spec, name);
+                            return Err(d.error(&message));
+                        }
+                    }
+                }
+
+                key_map.bind(&name, keys);
+            }
+            Ok(())
+        }));
+
+        Ok(key_map)
+    }
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
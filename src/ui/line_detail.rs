@@ -0,0 +1,92 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::max;
+
+use ncurses::*;
+use time::{self, Timespec};
+
+use core::line::Line;
+use core::fields;
+use ui::content::Content;
+use ui::printer::Print;
+use ui::theme::Role;
+
+static HEADER: &'static str = "Line details  (Esc goes back)";
+
+/// The whole selected line along with the fields found in it, taking the
+/// place of the lines until it's closed.
+pub struct LineDetail {
+    line: Line,
+    fields: Vec<(String, String)>,
+}
+
+impl LineDetail {
+    pub fn new(line: Line) -> LineDetail {
+        let fields = fields::parse(&line.content_without_ansi);
+
+        LineDetail {
+            line: line,
+            fields: fields,
+        }
+    }
+
+    pub fn render(&self, content: &Content, width: i32, top: i32, height: i32) {
+        let fields_row = 3 + self.line.guess_height(width as usize) as i32;
+        let received_at = time::at(Timespec::new(self.line.received_at, 0));
+
+        content.clear();
+        content.ensure_height(max(height, fields_row + self.fields.len() as i32 + 1) + 1);
+
+        wattron(content.window, Role::Heading.to_attr());
+        mvwprintw(content.window, 0, 0, &truncate(HEADER, width as usize));
+        wattroff(content.window, Role::Heading.to_attr());
+
+        let summary = format!("Line #{} received at {}",
+                              self.line.id,
+                              time::strftime("%H:%M:%S", &received_at).unwrap());
+        wattron(content.window, Role::Context.to_attr());
+        mvwprintw(content.window, 1, 0, &truncate(&summary, width as usize));
+        wattroff(content.window, Role::Context.to_attr());
+
+        wmove(content.window, 2, 0);
+        self.line.print(content);
+
+        wattron(content.window, Role::Heading.to_attr());
+        if self.fields.is_empty() {
+            mvwprintw(content.window, fields_row, 0, "No fields found");
+        } else {
+            mvwprintw(content.window, fields_row, 0, "Fields");
+        }
+        wattroff(content.window, Role::Heading.to_attr());
+
+        for (index, &(ref key, ref value)) in self.fields.iter().enumerate() {
+            let text = format!("  {}: {}", key, value);
+            mvwprintw(content.window,
+                      fields_row + 1 + index as i32,
+                      0,
+                      &truncate(&text, width as usize));
+        }
+
+        prefresh(content.window, 0, 0, top, 0, top + height - 1, width - 1);
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
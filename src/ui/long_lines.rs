@@ -0,0 +1,82 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rustc_serialize::{Decodable, Decoder};
+
+/// How lines wider than the window are shown.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LongLines {
+    /// Continued on the rows below.
+    Wrap,
+    /// Cut at the window edge, the rest can be scrolled to sideways.
+    Scroll,
+    /// Same as scrolling, with an ellipsis wherever a line is cut.
+    Truncate,
+}
+
+impl LongLines {
+    pub fn from_name(name: &str) -> Option<LongLines> {
+        match name {
+            "wrap" => Some(LongLines::Wrap),
+            "scroll" => Some(LongLines::Scroll),
+            "truncate" => Some(LongLines::Truncate),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LongLines::Wrap => "wrap",
+            LongLines::Scroll => "scroll",
+            LongLines::Truncate => "truncate",
+        }
+    }
+
+    pub fn next(&self) -> LongLines {
+        match *self {
+            LongLines::Wrap => LongLines::Scroll,
+            LongLines::Scroll => LongLines::Truncate,
+            LongLines::Truncate => LongLines::Wrap,
+        }
+    }
+
+    /// Whether a line of the given width gets an ellipsis on its left and
+    /// right edges, when scrolled sideways by the given amount of columns.
+    pub fn cut_edges(&self,
+                     line_width: usize,
+                     offset: usize,
+                     window_width: usize)
+                     -> (bool, bool) {
+        if *self != LongLines::Truncate {
+            return (false, false);
+        }
+
+        (offset > 0 && line_width > 0, line_width > offset + window_width)
+    }
+}
+
+impl Decodable for LongLines {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<LongLines, D::Error> {
+        let name = try!(decoder.read_str());
+
+        match LongLines::from_name(&name) {
+            Some(value) => Ok(value),
+            None => Err(decoder.error(&format!("unknown long lines mode `{}`", name))),
+        }
+    }
+}
@@ -16,52 +16,150 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cell::{Cell, RefCell};
+
 use ncurses::*;
+use unicode_width::UnicodeWidthStr;
+
+use ui::color::{self, ColorPair};
+use ui::theme::Role;
+
+/// Counters shown next to a menu item name.
+pub struct Badge {
+    pub match_count: usize,
+    pub unread_count: usize,
+    /// Takes the place of the theme's unread color.
+    pub unread_color: Option<String>,
+}
+
+impl Badge {
+    pub fn new(match_count: usize, unread_count: usize, unread_color: Option<String>) -> Badge {
+        Badge {
+            match_count: match_count,
+            unread_count: unread_count,
+            unread_color: unread_color,
+        }
+    }
+}
+
+struct MenuItem {
+    name: String,
+    badge: Option<Badge>,
+}
+
+impl MenuItem {
+    fn label(&self) -> String {
+        match self.badge {
+            Some(ref badge) if badge.unread_count > 0 => {
+                format!(" {} {} +{} ", self.name, badge.match_count, badge.unread_count)
+            }
+            Some(ref badge) => format!(" {} {} ", self.name, badge.match_count),
+            None => format!(" {} ", self.name),
+        }
+    }
+
+    fn attributes(&self, is_selected: bool) -> attr_t {
+        match self.badge {
+            _ if is_selected => Role::Bar.to_attr(),
+            Some(ref badge) if badge.unread_count > 0 => {
+                match badge.unread_color.as_ref().and_then(|name| color::from_name(name)) {
+                    Some(color) => ColorPair::on(color).to_standout_attr(),
+                    None => Role::Unread.to_attr(),
+                }
+            }
+            _ => Role::Menu.to_attr(),
+        }
+    }
+}
 
 pub struct Menu {
     pub window: WINDOW,
     panel: PANEL,
-    object: MENU,
-    items: Vec<ITEM>,
+    items: RefCell<Vec<MenuItem>>,
+    selected: Cell<usize>,
 }
 
 impl Menu {
     pub fn new(position_x: i32, position_y: i32, item_names: &[String]) -> Menu {
         let window = newwin(0, 0, position_x, position_y);
 
-        let mut items = vec![];
-
-        for name in item_names {
-            items.push(new_item(format!(" {} ", name), String::new()));
-        }
+        let items = item_names.iter()
+            .map(|name| {
+                MenuItem {
+                    name: name.clone(),
+                    badge: None,
+                }
+            })
+            .collect();
 
         Menu {
             window: window,
             panel: new_panel(window),
-            object: new_menu(&mut items),
-            items: items,
+            items: RefCell::new(items),
+            selected: Cell::new(0),
         }
     }
 
-    pub fn select(&self, item: i32) {
-        menu_driver(self.object, item);
-        pos_menu_cursor(self.object);
-        wrefresh(self.window);
+    pub fn select_previous(&self) {
+        if self.selected.get() > 0 {
+            self.selected.set(self.selected.get() - 1);
+        }
+        self.render();
+    }
+
+    pub fn select_next(&self) {
+        if self.selected.get() + 1 < self.items.borrow().len() {
+            self.selected.set(self.selected.get() + 1);
+        }
+        self.render();
+    }
+
+    pub fn add_item(&self, name: &str) {
+        self.items.borrow_mut().push(MenuItem {
+            name: name.to_string(),
+            badge: None,
+        });
+    }
+
+    pub fn remove_item(&self, index: usize) {
+        let mut items = self.items.borrow_mut();
+        items.remove(index);
+
+        if self.selected.get() >= items.len() {
+            self.selected.set(items.len() - 1);
+        }
+    }
+
+    pub fn select(&self, index: usize) {
+        if index < self.items.borrow().len() {
+            self.selected.set(index);
+        }
+        self.render();
+    }
+
+    pub fn update_badges(&self, badges: Vec<Badge>) {
+        for (item, badge) in self.items.borrow_mut().iter_mut().zip(badges) {
+            item.badge = Some(badge);
+        }
+        self.render();
     }
 
     pub fn render(&self) {
-        set_menu_win(self.object, self.window);
-        set_menu_sub(self.object, derwin(self.window, 0, 0, 0, 0));
-
-        menu_opts_off(self.object, O_SHOWDESC);
-        set_menu_mark(self.object, "");
-        set_menu_fore(self.object, COLOR_PAIR(1));
-        set_menu_back(self.object, COLOR_PAIR(2));
-        set_menu_format(self.object, 1, self.items.len() as i32);
-        post_menu(self.object);
-
-        refresh();
-        wbkgd(self.window, COLOR_PAIR(2));
+        werase(self.window);
+        wbkgd(self.window, Role::Menu.to_attr());
+        wmove(self.window, 0, 0);
+
+        let items = self.items.borrow();
+        let selected = self.selected.get();
+
+        for (index, item) in items.iter().enumerate().skip(self.first_visible_index()) {
+            let attributes = item.attributes(index == selected);
+
+            wattron(self.window, attributes);
+            wprintw(self.window, &item.label());
+            wattroff(self.window, attributes);
+        }
+
         wrefresh(self.window);
     }
 
@@ -74,12 +172,47 @@ impl Menu {
     }
 
     pub fn destroy(&self) {
-        unpost_menu(self.object);
+        del_panel(self.panel);
+        delwin(self.window);
+    }
+
+    /// Index of the item shown at the given column.
+    pub fn item_at(&self, column: i32) -> Option<usize> {
+        let items = self.items.borrow();
+        let mut start = 0;
 
-        for item in &self.items {
-            free_item(*item);
+        for (index, item) in items.iter().enumerate().skip(self.first_visible_index()) {
+            let end = start + item.label().width() as i32;
+            if column >= start && column < end {
+                return Some(index);
+            }
+            start = end;
         }
 
-        free_menu(self.object);
+        None
+    }
+
+    /// Items preceding the selected one are skipped when they would push it
+    /// past the right edge of the window.
+    fn first_visible_index(&self) -> usize {
+        let items = self.items.borrow();
+        let selected = self.selected.get();
+        let mut available_width = getmaxx(self.window) as usize;
+        let mut index = selected + 1;
+
+        while index > 0 {
+            let width = items[index - 1].label().width();
+            if width > available_width {
+                break;
+            }
+            available_width -= width;
+            index -= 1;
+        }
+
+        if index > selected {
+            selected
+        } else {
+            index
+        }
     }
 }
@@ -28,3 +28,13 @@ pub mod color;
 pub mod printer;
 pub mod highlighter;
 pub mod rendered_line;
+pub mod template_list;
+pub mod theme;
+pub mod status_bar;
+pub mod histogram_pane;
+pub mod key_map;
+pub mod line_detail;
+pub mod bookmarks_pane;
+pub mod long_lines;
+pub mod export_prompt;
+pub mod help;
@@ -18,21 +18,33 @@
 
 use ncurses::*;
 
+use ui::readline;
+use ui::key_map::KeyMap;
 use ui::menu::Menu;
 use ui::search::Search;
+use ui::export_prompt::ExportPrompt;
 
 pub static HEIGHT: i32 = 1;
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum State {
     Menu,
     Search,
+    Templates,
+    Histogram,
+    Selection,
+    Detail,
+    Export,
+    Help,
 }
 
 pub struct Navigation {
     pub menu: Menu,
     pub search: Search,
+    pub export_prompt: ExportPrompt,
     pub state: State,
+    /// Where closing the export prompt or the help goes back to.
+    pub previous_state: State,
 }
 
 impl Navigation {
@@ -40,7 +52,9 @@ impl Navigation {
         Navigation {
             menu: Menu::new(position_x, position_y, menu_item_names),
             search: Search::new(position_x, position_y),
+            export_prompt: ExportPrompt::new(position_x, position_y),
             state: State::Menu,
+            previous_state: State::Menu,
         }
     }
 
@@ -52,12 +66,33 @@ impl Navigation {
 
     pub fn destroy(&self) {
         self.menu.destroy();
+        self.export_prompt.destroy();
+    }
+
+    /// The search options and the export prompt show the keys they're used
+    /// with.
+    pub fn set_key_map(&mut self, key_map: &KeyMap) {
+        self.search.set_key_map(key_map.clone());
+        self.export_prompt.set_key_map(key_map.clone());
+    }
+
+    /// Brings out whatever was staged, leaving the cursor where a query or a
+    /// path is being typed in.
+    pub fn update(&self) {
+        match self.state {
+            State::Search => readline::move_cursor(),
+            State::Export => self.export_prompt.move_cursor(),
+            _ => {
+                doupdate();
+            }
+        }
     }
 
     pub fn change_state(&mut self, new_state: State) -> bool {
         if self.state == new_state {
             false
         } else {
+            self.previous_state = self.state;
             self.state = new_state;
             self.handle_visibility();
             true
@@ -67,20 +102,29 @@ impl Navigation {
     pub fn resize(&self, container_width: i32, offset: i32) {
         self.search.resize(container_width, offset);
         mvwin(self.menu.window, offset, 0);
+        self.export_prompt.resize(offset);
 
         self.render();
     }
 
     fn handle_visibility(&self) {
         match self.state {
-            State::Menu => {
+            State::Menu | State::Templates | State::Histogram | State::Selection |
+            State::Detail | State::Help => {
                 self.search.hide();
+                self.export_prompt.hide();
                 self.menu.show();
             }
             State::Search => {
                 self.menu.hide();
+                self.export_prompt.hide();
                 self.search.show();
             }
+            State::Export => {
+                self.menu.hide();
+                self.search.hide();
+                self.export_prompt.show();
+            }
         }
 
         update_panels();
@@ -16,16 +16,20 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::cmp::{min, max};
+
 use ncurses::*;
 
 use core::line::Line;
 use core::buffer::BufferLines;
+use core::collapse::Repetition;
 use utils::ansi_decoder::{Component, Style};
-use ui::frame::{Frame, NORMAL_HIGHLIGHT_COLOR, CURRENT_HIGHLIGHT_COLOR};
+use ui::frame::Frame;
 use ui::color::ColorPair;
 use ui::content::Content;
 use ui::search::Query;
-use ui::highlighter::{Highlight, LineHighlighter, State as HighlightState};
+use ui::highlighter::{Highlight, State as HighlightState};
+use ui::rendered_line::Kind;
 
 pub trait Print {
     fn print(&self, content: &Content);
@@ -116,7 +120,6 @@ impl Viewport {
 
 pub struct LinesPrinter<'a> {
     frame: &'a mut Frame,
-    height: i32,
     buffer_lines: &'a BufferLines<'a>,
     query: Option<Query>,
 }
@@ -128,119 +131,401 @@ impl<'a> LinesPrinter<'a> {
                -> LinesPrinter<'a> {
         LinesPrinter {
             frame: frame,
-            height: 0,
             buffer_lines: lines,
             query: query,
         }
     }
 
     pub fn draw(&mut self) {
-        if self.query.is_some() {
-            if self.query.as_ref().unwrap().filter {
-                self.handle_filter()
-            } else {
-                if self.frame.initial_rendered_lines.is_some() {
+        match self.query.as_ref().map(|query| query.filter) {
+            Some(true) => {
+                // Filtering goes through every line, which are laid out once
+                // for as long as the filter is being typed in
+                let is_laid_out = {
+                    let rendered_lines = &self.frame.rendered_lines;
+                    self.frame.initial_rendered_lines.is_some() ||
+                    !(rendered_lines.is_empty() || rendered_lines.is_partial())
+                };
+                if !is_laid_out {
                     self.handle_print();
                 }
-                self.handle_search();
+                self.handle_filter();
             }
-        } else {
-            self.handle_print();
+            Some(false) => {
+                let is_laid_out = self.is_laid_out_for_search();
+                if !is_laid_out {
+                    self.handle_print();
+                }
+                self.handle_search(!is_laid_out);
+            }
+            None => self.handle_print(),
         }
     }
 
+    /// Lines laid out entirely are searched again as they are, unless only
+    /// those around the viewport could be.
+    fn is_laid_out_for_search(&self) -> bool {
+        self.frame.initial_rendered_lines.is_none() && !self.frame.rendered_lines.is_empty() &&
+        !self.frame.rendered_lines.is_partial() &&
+        !self.buffer_lines.lay_out(self.frame.line_width() as usize)
+    }
+
     fn handle_print(&mut self) {
         self.frame.reset();
-        self.height = 0;
 
-        for line in self.buffer_lines {
-            let actual_height = self.frame.content.calculate_height_change(|| {
-                line.print(&self.frame.content);
-            });
+        let line_width = self.frame.line_width() as usize;
+        let is_filtering = self.query.as_ref().map_or(false, |query| query.filter);
+        if !is_filtering && self.buffer_lines.lay_out(line_width) {
+            self.print_around_reverse_index();
+            return;
+        }
+
+        for repetition in self.buffer_lines.repetitions() {
+            self.create_rendered_line(repetition);
+        }
+    }
 
-            self.height += actual_height;
-            self.frame.rendered_lines.create(line.clone(), actual_height, None);
+    /// Lays out the lines within a viewport of the scroll position, the
+    /// rest of them only account for the rows they take.
+    fn print_around_reverse_index(&mut self) {
+        let content_height = self.frame.content_height();
+        let height = self.buffer_lines.height();
+        let reverse_index = self.buffer_lines.buffer.reverse_index.get() as i32;
+        let offset = max(0, height - content_height - reverse_index);
+
+        let first = self.buffer_lines.position_at(offset - content_height);
+        let last = min(self.buffer_lines.position_at(offset + 2 * content_height) + 1,
+                       self.buffer_lines.len());
+
+        for position in first..last {
+            if let Some(repetition) = self.buffer_lines.repetition(position) {
+                self.create_rendered_line(repetition);
+            }
         }
+
+        self.frame.rendered_lines.rows_above = self.buffer_lines.row_of(first);
+        self.frame.rendered_lines.rows_below = height - self.buffer_lines.row_of(last);
     }
 
-    fn handle_search(&mut self) {
+    fn create_rendered_line(&mut self, repetition: Repetition) {
+        let height = repetition.guess_height(self.frame.line_width() as usize) as i32;
+        let line = if repetition.count > 1 {
+            repetition.to_line()
+        } else {
+            repetition.first.clone()
+        };
+        self.frame.rendered_lines.create(line, height, None);
+
+        if self.buffer_lines.buffer.ends_unterminated_block(repetition.last.id) {
+            self.frame.rendered_lines.create_unterminated_marker(repetition.first.id);
+        }
+    }
+
+    fn handle_search(&mut self, is_laid_out_again: bool) {
+        if self.frame.rendered_lines.is_partial() {
+            return self.handle_partial_search();
+        }
+
         let query = self.query.as_ref().unwrap();
+        self.frame.search_matcher = Some(query.matcher.clone());
 
-        if query.highlight == Highlight::VisibleOrLast || query.highlight == Highlight::Current {
+        let is_highlight_kept = query.highlight == Highlight::VisibleOrLast ||
+                                query.highlight == Highlight::Current;
+        if is_highlight_kept || is_laid_out_again {
             self.frame.navigation.search.matches_found = false;
-            self.height = 0;
+            let line_width = self.frame.line_width();
 
             for rendered_line in self.frame
                 .rendered_lines
                 .entries
                 .iter_mut() {
-                if rendered_line.search(&query.text,
-                                        &self.frame.content,
-                                        self.frame.width,
-                                        self.height) {
+                if rendered_line.search(&query.matcher, line_width) {
                     self.frame.navigation.search.matches_found = true;
                 }
-
-                self.height += rendered_line.height;
             }
+        }
+
+        if is_highlight_kept {
+            self.restore_highlighted_line();
+
             if query.highlight == Highlight::Current && self.highlight_doesnt_require_update() {
-                self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
-            } else if self.frame.navigation.search.matches_found {
-                self.update_current_and_highlight_item();
+                return;
+            }
+        }
+
+        if self.frame.navigation.search.matches_found {
+            self.update_current_item();
+        }
+    }
+
+    /// Only the lines around the viewport are laid out, so matches past them
+    /// are found by going through the lines shown, which are searched once
+    /// for as long as the query stays the same.
+    fn handle_partial_search(&mut self) {
+        let (matcher, highlight) = {
+            let query = self.query.as_ref().unwrap();
+            (query.matcher.clone(), query.highlight)
+        };
+        let first_id = self.buffer_lines.repetition(0).map_or(0, |repetition| repetition.first.id);
+        self.frame
+            .match_cache
+            .prepare(&matcher, self.frame.line_width(), first_id, self.buffer_lines.len());
+        self.frame.search_matcher = Some(matcher);
+
+        let is_match_laid_out = self.search_laid_out_lines();
+        self.frame.navigation.search.matches_found = is_match_laid_out ||
+                                                     self.last_match().is_some();
+        {
+            let mut state = self.frame.content.state.borrow_mut();
+            state.highlighted_line = self.frame
+                .rendered_lines
+                .position_of(state.highlighted_line_id)
+                .unwrap_or(self.frame.rendered_lines.len());
+        }
+
+        if highlight == Highlight::Current && self.partial_highlight_doesnt_require_update() {
+            return;
+        }
+
+        if self.frame.navigation.search.matches_found {
+            self.move_partial_highlight(highlight, 1);
+        }
+    }
+
+    fn search_laid_out_lines(&mut self) -> bool {
+        let mut is_match_found = false;
+
+        for index in 0..self.frame.rendered_lines.len() {
+            if self.frame.rendered_lines[index].kind != Kind::Normal {
+                continue;
             }
-        } else if self.frame.navigation.search.matches_found {
-            self.highlight_current_item(&query.text, NORMAL_HIGHLIGHT_COLOR);
-            self.update_current_and_highlight_item();
+
+            let position = self.position_of_entry(index);
+            let offsets = self.match_offsets_at(position);
+            is_match_found = is_match_found || !offsets.is_empty();
+            self.frame.rendered_lines.entries[index].found_matches = if offsets.is_empty() {
+                None
+            } else {
+                Some(offsets)
+            };
         }
+
+        is_match_found
+    }
+
+    fn position_of_entry(&self, index: usize) -> usize {
+        self.buffer_lines.position_at(self.frame.rendered_lines.row_of(index))
+    }
+
+    fn match_offsets_at(&mut self, position: usize) -> Vec<usize> {
+        match self.buffer_lines.repetition(position) {
+            Some(repetition) => self.frame.match_cache.offsets(&repetition).clone(),
+            None => vec![],
+        }
+    }
+
+    fn partial_highlight_doesnt_require_update(&self) -> bool {
+        let state = self.frame.content.state.borrow();
+        let found_matches = match self.frame.rendered_lines.entries.get(state.highlighted_line) {
+            Some(rendered_line) => rendered_line.found_matches.as_ref(),
+            None => self.frame.match_cache.get(state.highlighted_line_id),
+        };
+
+        found_matches.map_or(false, |offsets| state.highlighted_match < offsets.len())
+    }
+
+    /// Moves between matches by the position of their lines, as the lines
+    /// they're on may not be laid out.
+    fn move_partial_highlight(&mut self, highlight: Highlight, times: usize) {
+        let mut current = self.current_match();
+
+        for _ in 0..times {
+            current = match (highlight, current) {
+                (Highlight::Next, Some((position, match_index))) => {
+                    self.next_match(position, match_index).or(current)
+                }
+                (Highlight::Previous, Some((position, match_index))) => {
+                    self.previous_match(position, match_index).or(current)
+                }
+                _ => self.visible_or_last_match(),
+            };
+        }
+
+        if let Some((position, match_index)) = current {
+            self.highlight_match(position, match_index);
+        }
+    }
+
+    fn current_match(&self) -> Option<(usize, usize)> {
+        let state = self.frame.content.state.borrow();
+        let position = match self.frame.rendered_lines.position_of(state.highlighted_line_id) {
+            Some(index) => Some(self.position_of_entry(index)),
+            None => self.buffer_lines.position_of(state.highlighted_line_id),
+        };
+
+        position.map(|position| (position, state.highlighted_match))
+    }
+
+    fn visible_or_last_match(&mut self) -> Option<(usize, usize)> {
+        let viewport = Viewport::new(self.buffer_lines.buffer.reverse_index.get(),
+                                     self.frame.content_height() as usize);
+        let visible_match = self.frame.rendered_lines.viewport_match(&viewport).map(|matched| {
+            let index = self.frame.rendered_lines.len() - matched.line - 1;
+            (self.position_of_entry(index), matched.match_index)
+        });
+
+        visible_match.or_else(|| self.last_match())
+    }
+
+    fn last_match(&mut self) -> Option<(usize, usize)> {
+        let len = self.buffer_lines.len();
+        self.previous_match(len, 0)
+    }
+
+    fn next_match(&mut self, position: usize, match_index: usize) -> Option<(usize, usize)> {
+        if match_index + 1 < self.match_offsets_at(position).len() {
+            return Some((position, match_index + 1));
+        }
+
+        (position + 1..self.buffer_lines.len())
+            .find(|&next| !self.match_offsets_at(next).is_empty())
+            .map(|next| (next, 0))
+    }
+
+    fn previous_match(&mut self, position: usize, match_index: usize) -> Option<(usize, usize)> {
+        if match_index > 0 {
+            return Some((position, match_index - 1));
+        }
+
+        (0..position)
+            .rev()
+            .map(|previous| (previous, self.match_offsets_at(previous).len()))
+            .find(|&(_, count)| count > 0)
+            .map(|(previous, count)| (previous, count - 1))
+    }
+
+    /// Scrolls to the match when it's out of view, laying out the lines
+    /// around it.
+    fn highlight_match(&mut self, position: usize, match_index: usize) {
+        let id = match self.buffer_lines.repetition(position) {
+            Some(repetition) => repetition.first.id,
+            None => return,
+        };
+        let row = self.buffer_lines.row_of(position) +
+                  self.match_offsets_at(position)[match_index] as i32;
+        {
+            let mut state = self.frame.content.state.borrow_mut();
+            state.highlighted_line_id = id;
+            state.highlighted_match = match_index;
+        }
+
+        let height = self.buffer_lines.height();
+        let content_height = self.frame.content_height();
+        let top = height - content_height - self.buffer_lines.buffer.reverse_index.get() as i32;
+        if row < top || row >= top + content_height {
+            self.buffer_lines
+                .buffer
+                .set_reverse_index(height - row - self.frame.height / 2,
+                                   self.frame.max_scroll_value());
+            let matcher = self.frame.search_matcher.clone();
+            self.handle_print();
+            self.frame.search_matcher = matcher;
+            self.frame.navigation.search.matches_found = true;
+            self.search_laid_out_lines();
+        }
+
+        let mut state = self.frame.content.state.borrow_mut();
+        state.highlighted_line = self.frame
+            .rendered_lines
+            .position_of(id)
+            .unwrap_or(self.frame.rendered_lines.len());
     }
 
     fn handle_filter(&mut self) {
         let query = self.query.as_ref().unwrap();
 
         if query.highlight == Highlight::VisibleOrLast || query.highlight == Highlight::Current {
-            self.frame.content.clear();
-            self.height = 0;
-
-            let mut filtered_rendered_lines = self.frame
-                .initial_rendered_lines
-                .as_mut()
-                .unwrap_or(&mut self.frame.rendered_lines)
-                .matching(&query.text);
+            let mut filtered_rendered_lines = {
+                let rendered_lines = self.frame
+                    .initial_rendered_lines
+                    .as_mut()
+                    .unwrap_or(&mut self.frame.rendered_lines);
+
+                if query.inverse {
+                    rendered_lines.excluding(&query.matcher)
+                } else {
+                    rendered_lines.matching(&query.matcher, query.context)
+                }
+            };
             self.frame.navigation.search.matches_found = !filtered_rendered_lines.is_empty();
 
             for rendered_line in filtered_rendered_lines.entries.iter_mut() {
-                rendered_line.print(&self.frame.content, self.height);
-                rendered_line.found_matches = rendered_line.highlight(&query.text,
-                                                                      &self.frame.content,
-                                                                      self.frame.width,
-                                                                      self.height);
-
-                self.height += rendered_line.height;
+                if rendered_line.kind == Kind::Normal && !query.inverse {
+                    rendered_line.search(&query.matcher, self.frame.line_width());
+                }
             }
 
             self.frame.replace_rendered_lines(filtered_rendered_lines);
 
+            // Lines left after an inverted filter have nothing to highlight
+            if query.inverse {
+                return;
+            }
+
+            self.frame.search_matcher = Some(query.matcher.clone());
+            self.restore_highlighted_line();
+
             if query.highlight == Highlight::Current && self.highlight_doesnt_require_update() {
-                self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
-            } else if self.frame.navigation.search.matches_found {
-                self.update_current_and_highlight_item();
+                return;
             }
-        } else if self.frame.navigation.search.matches_found {
-            self.highlight_current_item(&query.text, NORMAL_HIGHLIGHT_COLOR);
-            self.update_current_and_highlight_item();
+        } else if !query.inverse {
+            self.frame.search_matcher = Some(query.matcher.clone());
+        }
+
+        if self.frame.navigation.search.matches_found && !query.inverse {
+            self.update_current_item();
+        }
+    }
+
+    /// Moves the current match the given amount of times, using the matches
+    /// found when the lines were last printed.
+    pub fn advance(&mut self, times: usize) {
+        let query = self.query.as_ref().unwrap();
+        self.frame.search_matcher = Some(query.matcher.clone());
+
+        if !self.frame.navigation.search.matches_found {
+            return;
         }
+
+        if self.frame.rendered_lines.is_partial() {
+            self.move_partial_highlight(query.highlight, times);
+        } else {
+            self.update_current_item_times(times);
+        }
+    }
+
+    fn update_current_item(&self) {
+        self.update_current_item_times(1);
     }
 
-    fn update_current_and_highlight_item(&self) {
+    fn update_current_item_times(&self, times: usize) {
         let query = self.query.as_ref().unwrap();
         let viewport = Viewport::new(self.buffer_lines.buffer.reverse_index.get(),
                                      self.frame.content_height() as usize);
 
-        HighlightState::new(self.frame.content.state.borrow_mut(),
-                            &self.frame.rendered_lines,
-                            viewport)
-            .update(&query.highlight);
-        self.highlight_current_item(&query.text, CURRENT_HIGHLIGHT_COLOR);
+        {
+            let mut highlight_state = HighlightState::new(self.frame.content.state.borrow_mut(),
+                                                          &self.frame.rendered_lines,
+                                                          viewport);
+            for _ in 0..times {
+                highlight_state.update(&query.highlight);
+            }
+        }
+
+        {
+            let mut state = self.frame.content.state.borrow_mut();
+            state.highlighted_line_id = self.frame.rendered_lines[state.highlighted_line].line.id;
+        }
 
         let matched_line = self.frame.content.highlighted_line();
         if !self.frame.rendered_lines.is_match_in_viewport(matched_line, viewport) {
@@ -248,16 +533,14 @@ impl<'a> LinesPrinter<'a> {
         }
     }
 
-    fn highlight_current_item(&self, text: &str, color: i16) {
-        let state = self.frame.content.state.borrow();
-        let line = &self.frame.rendered_lines[state.highlighted_line].line;
+    /// Lines get laid out again whenever new ones arrive, so the current
+    /// match is followed by its line id rather than its position.
+    fn restore_highlighted_line(&self) {
+        let mut state = self.frame.content.state.borrow_mut();
 
-        let accumulated_height = self.frame
-            .rendered_lines
-            .height_up_to_index(state.highlighted_line);
-        let highlighter =
-            LineHighlighter::new(self.frame.content.window, line, self.frame.width, color);
-        highlighter.print_single_match(text, state.highlighted_match, accumulated_height);
+        if let Some(index) = self.frame.rendered_lines.position_of(state.highlighted_line_id) {
+            state.highlighted_line = index;
+        }
     }
 
     fn update_scroll_position(&self) {
@@ -272,6 +555,15 @@ impl<'a> LinesPrinter<'a> {
 
     fn highlight_doesnt_require_update(&self) -> bool {
         let state = self.frame.content.state.borrow();
-        self.frame.rendered_lines[state.highlighted_line].found_matches.is_some()
+
+        match self.frame.rendered_lines.entries.get(state.highlighted_line) {
+            Some(rendered_line) => {
+                rendered_line.line.id == state.highlighted_line_id &&
+                rendered_line.found_matches
+                    .as_ref()
+                    .map_or(false, |found_matches| state.highlighted_match < found_matches.len())
+            }
+            None => false,
+        }
     }
 }
@@ -173,7 +173,8 @@ pub fn move_cursor() {
     wmove(window,
           0,
           cursor_position - wrapping_offset(cursor_position));
-    wrefresh(window);
+    wnoutrefresh(window);
+    doupdate();
 }
 
 pub extern "C" fn handle_redisplay() {
@@ -18,19 +18,25 @@
 
 use std::ops::Index;
 
-use ncurses::wmove;
+use ncurses::{wmove, wattron, wattroff, mvwchgat, A_REVERSE};
 
 use core::line::Line;
+use core::matcher::Matcher;
+use core::filter::Context;
+use core::selection::{self, SEPARATOR, UNTERMINATED_MARKER};
 use ui::content::Content;
-use ui::frame::NORMAL_HIGHLIGHT_COLOR;
 use ui::printer::{Print, Viewport};
-use ui::highlighter::LineHighlighter;
+use ui::highlighter::{LineHighlighter, match_offsets};
+use ui::theme::Role;
+
+pub use core::selection::Kind;
 
 #[derive(Clone)]
 pub struct RenderedLine {
     pub line: Line,
     pub height: i32,
     pub found_matches: Option<Vec<usize>>,
+    pub kind: Kind,
 }
 
 impl RenderedLine {
@@ -39,32 +45,53 @@ impl RenderedLine {
             line: line,
             height: height,
             found_matches: found_matches,
+            kind: Kind::Normal,
+        }
+    }
+
+    /// Separators take the id of the line that follows them, so they're
+    /// placed along with it.
+    fn separator(id: usize) -> RenderedLine {
+        let mut line = Line::new(SEPARATOR.to_string());
+        line.id = id;
+
+        RenderedLine {
+            line: line,
+            height: 1,
+            found_matches: None,
+            kind: Kind::Separator,
         }
     }
 
-    pub fn search(&mut self,
-                  text: &str,
-                  content: &Content,
-                  container_width: i32,
-                  accumulated_height: i32)
-                  -> bool {
-        let is_match = self.line.contains(text);
-        let mut found_matches = None;
+    /// Follows the last line of a block that never got its end.
+    fn unterminated_marker(id: usize) -> RenderedLine {
+        let mut line = Line::new(UNTERMINATED_MARKER.to_string());
+        line.id = id;
 
-        if is_match {
-            self.print(content, accumulated_height);
-            found_matches = self.highlight(text, content, container_width, accumulated_height);
+        RenderedLine {
+            line: line,
+            height: 1,
+            found_matches: None,
+            kind: Kind::Marker,
         }
+    }
 
-        if self.update_found_matches(found_matches) && !is_match {
-            self.print(content, accumulated_height);
+    /// Locates the matches without drawing anything, as the line may well
+    /// be outside the viewport.
+    pub fn search(&mut self, matcher: &Matcher, container_width: i32) -> bool {
+        if self.kind != Kind::Normal {
+            self.found_matches = None;
+            return false;
         }
 
-        is_match
+        let offsets = match_offsets(&self.line, matcher, container_width);
+        self.found_matches = if offsets.is_empty() { None } else { Some(offsets) };
+
+        self.found_matches.is_some()
     }
 
     pub fn highlight(&self,
-                     text: &str,
+                     matcher: &Matcher,
                      content: &Content,
                      container_width: i32,
                      accumulated_height: i32)
@@ -72,22 +99,34 @@ impl RenderedLine {
         let highlighter = LineHighlighter::new(content.window,
                                                &self.line,
                                                container_width,
-                                               NORMAL_HIGHLIGHT_COLOR);
-        Some(highlighter.print(text, accumulated_height, self.height))
+                                               Role::Match.to_attr());
+        Some(highlighter.print(matcher, accumulated_height, self.height))
     }
 
-    pub fn print(&self, content: &Content, accumulated_height: i32) {
+    pub fn print(&self, content: &Content, container_width: i32, accumulated_height: i32) {
         wmove(content.window, accumulated_height, 0);
+
+        let attributes = match self.kind {
+            Kind::Normal => 0,
+            Kind::Marker => Role::Marker.to_attr(),
+            _ => Role::Context.to_attr(),
+        };
+        wattron(content.window, attributes);
         self.line.print(content);
-    }
+        wattroff(content.window, attributes);
 
-    pub fn update_found_matches(&mut self, found_matches: Option<Vec<usize>>) -> bool {
-        if self.found_matches != found_matches {
-            self.found_matches = found_matches;
+        if self.kind == Kind::Normal || self.kind == Kind::Context {
+            content.print_highlight_rules(&self.line,
+                                          container_width,
+                                          accumulated_height,
+                                          self.height);
+        }
+    }
 
-            true
-        } else {
-            false
+    /// Shows the line in reverse video.
+    pub fn select(&self, content: &Content, accumulated_height: i32) {
+        for row in accumulated_height..accumulated_height + self.height {
+            mvwchgat(content.window, row, 0, -1, A_REVERSE(), 0);
         }
     }
 
@@ -99,11 +138,23 @@ impl RenderedLine {
 #[derive(Clone)]
 pub struct RenderedLineCollection {
     pub entries: Vec<RenderedLine>,
+    /// Rows taken by the lines left out before and after the entries, when
+    /// only the ones around the viewport are laid out.
+    pub rows_above: i32,
+    pub rows_below: i32,
 }
 
 impl RenderedLineCollection {
     pub fn default() -> RenderedLineCollection {
-        RenderedLineCollection { entries: vec![] }
+        RenderedLineCollection::new(vec![])
+    }
+
+    fn new(entries: Vec<RenderedLine>) -> RenderedLineCollection {
+        RenderedLineCollection {
+            entries: entries,
+            rows_above: 0,
+            rows_below: 0,
+        }
     }
 
     pub fn create(&mut self, line: Line, height: i32, found_matches: Option<Vec<usize>>) {
@@ -111,22 +162,69 @@ impl RenderedLineCollection {
         self.entries.push(entry);
     }
 
-    pub fn matching(&mut self, text: &str) -> RenderedLineCollection {
-        RenderedLineCollection {
-            entries: self.entries
-                .iter()
-                .filter(|entry| entry.line.contains(text))
-                .map(|entry| entry.clone())
-                .collect::<Vec<_>>(),
+    pub fn create_unterminated_marker(&mut self, id: usize) {
+        self.entries.push(RenderedLine::unterminated_marker(id));
+    }
+
+    /// Keeps matching lines along with the requested amount of surrounding
+    /// context lines. Groups that aren't contiguous are split by a separator.
+    pub fn matching(&mut self, matcher: &Matcher, context: Context) -> RenderedLineCollection {
+        let selected = selection::matching(&self.selection_entries(), matcher, context);
+        self.select_entries(selected)
+    }
+
+    /// Separators and markers aren't lines of their own, so they're kept.
+    pub fn excluding(&mut self, matcher: &Matcher) -> RenderedLineCollection {
+        let selected = selection::excluding(&self.selection_entries(), matcher);
+        let mut result = self.select_entries(selected);
+        for entry in &mut result.entries {
+            entry.found_matches = None;
         }
+
+        result
+    }
+
+    fn selection_entries(&self) -> Vec<(Kind, &Line)> {
+        self.entries.iter().map(|entry| (entry.kind, &entry.line)).collect()
+    }
+
+    fn select_entries(&self, selected: Vec<(usize, Kind)>) -> RenderedLineCollection {
+        RenderedLineCollection::new(selected.into_iter()
+            .map(|(index, kind)| {
+                let entry = &self.entries[index];
+                match kind {
+                    Kind::Separator => RenderedLine::separator(entry.line.id),
+                    Kind::Context if entry.kind == Kind::Normal => {
+                        let mut entry = entry.clone();
+                        entry.kind = Kind::Context;
+                        entry.found_matches = None;
+                        entry
+                    }
+                    _ => entry.clone(),
+                }
+            })
+            .collect())
     }
 
     pub fn height(&self) -> i32 {
-        self.entries.iter().height()
+        self.rows_above + self.entries.iter().height() + self.rows_below
     }
 
     pub fn clear(&mut self) {
         self.entries.clear();
+        self.rows_above = 0;
+        self.rows_below = 0;
+    }
+
+    /// Whether some of the lines were left out of the layout.
+    pub fn is_partial(&self) -> bool {
+        self.rows_above > 0 || self.rows_below > 0
+    }
+
+    /// Whether the lines reaching into the given rows were laid out.
+    pub fn is_laid_out(&self, first_row: i32, last_row: i32) -> bool {
+        self.rows_above <= first_row &&
+        (self.rows_below == 0 || last_row < self.height() - self.rows_below)
     }
 
     pub fn len(&self) -> usize {
@@ -139,20 +237,48 @@ impl RenderedLineCollection {
 
     pub fn buffer_reverse_index(&self, line_index: usize, match_index: usize) -> i32 {
         let offset = self.entries[line_index].found_matches.as_ref().unwrap()[match_index];
-        self.entries.iter().skip(line_index).height() - offset as i32
+        self.entries.iter().skip(line_index).height() + self.rows_below - offset as i32
+    }
+
+    /// Row the entry at the given index starts on.
+    pub fn row_of(&self, index: usize) -> i32 {
+        self.rows_above + self.entries.iter().take(index).height()
     }
 
-    pub fn height_up_to_index(&self, index: usize) -> i32 {
-        self.entries.iter().take(index).height()
+    /// Height taken by the lines received starting with the given id.
+    pub fn height_since(&self, id: usize) -> i32 {
+        self.entries.iter().rev().take_while(|entry| entry.line.id >= id).height()
     }
 
-    pub fn last_lines_height(&self, count: usize) -> i32 {
-        self.entries.iter().rev().take(count).height()
+    /// Height taken by the lines preceding the first one received starting
+    /// with the given id.
+    pub fn height_before(&self, id: usize) -> i32 {
+        self.entries.iter().take_while(|entry| entry.line.id < id).height()
+    }
+
+    pub fn position_of(&self, id: usize) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.kind == Kind::Normal && entry.line.id == id)
+    }
+
+    /// Index of the first line reaching past the given row, along with the
+    /// row that line starts on.
+    pub fn first_visible(&self, offset: i32) -> (usize, i32) {
+        let mut accumulated_height = self.rows_above;
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            if accumulated_height + entry.height > offset {
+                return (index, accumulated_height);
+            }
+            accumulated_height += entry.height;
+        }
+
+        (self.entries.len(), accumulated_height)
     }
 
     pub fn is_match_in_viewport(&self, matched_line: MatchedLine, viewport: Viewport) -> bool {
         let limit = viewport.limit();
-        let accumulated_height = self.entries.iter().skip(matched_line.line).height() as usize;
+        let accumulated_height = (self.entries.iter().skip(matched_line.line).height() +
+                                  self.rows_below) as usize;
         let line = &self.entries[matched_line.line];
 
         if accumulated_height >= viewport.reverse_index {
@@ -167,7 +293,7 @@ impl RenderedLineCollection {
     }
 
     pub fn viewport_match(&self, viewport: &Viewport) -> Option<MatchedLine> {
-        let mut accumulated_height = 0;
+        let mut accumulated_height = self.rows_below as usize;
         let limit = viewport.limit();
 
         for (i, line) in self.entries.iter().rev().enumerate() {
@@ -16,21 +16,36 @@
  * along with this program. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::cmp::max;
 
-use regex::Regex;
 use ncurses::*;
 
+use core::matcher::{Matcher, MatchMode};
+use core::filter::Context;
 use ui::readline;
 use ui::highlighter::Highlight;
-
-static OPTIONS_WIDTH: i32 = 31;
-static WITH_MATCHES_COLOR_PAIR_ID: i16 = 1;
-static NO_MATCHES_COLOR_PAIR_ID: i16 = 4;
+use ui::key_map::{KeyMap, Action};
+use ui::theme::Role;
+
+/// Options are shortened, then dropped, to leave the query at least this wide.
+static MIN_INPUT_WIDTH: i32 = 20;
+static CONTEXT_STEPS: [usize; 6] = [0, 1, 2, 3, 5, 10];
+/// Actions of the options in the order they're shown, along with their names.
+static OPTIONS: [(Action, &'static str); 8] = [(Action::NextMatch, "Next"),
+                                               (Action::PreviousMatch, "Prev"),
+                                               (Action::ToggleFilterMode, "Filter Mode"),
+                                               (Action::ToggleInverseMode, "Invert"),
+                                               (Action::CycleContext, "Context"),
+                                               (Action::ToggleRegexMode, "Regex"),
+                                               (Action::ToggleCaseInsensitiveMode, "Icase"),
+                                               (Action::ToggleWholeWordMode, "Word")];
 
 pub struct Query {
-    pub text: String,
+    pub matcher: Matcher,
     pub filter: bool,
+    pub inverse: bool,
+    pub context: Context,
     pub highlight: Highlight,
 }
 
@@ -48,7 +63,7 @@ impl Search {
 
         Search {
             window: window,
-            options: Options::new(window),
+            options: Options::new(window, COLS()),
             input_field: InputField::new(window),
             panel: new_panel(window),
             matches_found: false,
@@ -56,7 +71,7 @@ impl Search {
     }
 
     pub fn render(&self) {
-        let color_pair = COLOR_PAIR(self.color_pair_id());
+        let color_pair = self.role().to_attr();
 
         wbkgd(self.window, color_pair);
         self.input_field.render(color_pair);
@@ -68,17 +83,26 @@ impl Search {
     pub fn resize(&self, container_width: i32, offset: i32) {
         mvwin(self.window, offset, 0);
 
-        self.input_field.resize(container_width, offset);
+        let width = input_width(container_width, self.options.context, &self.options.key_map);
+        self.input_field.resize(width, offset);
         self.options.resize(container_width);
     }
 
+    /// The options are labeled with the keys toggling them.
+    pub fn set_key_map(&mut self, key_map: KeyMap) {
+        self.options.key_map = key_map;
+        self.resize(getmaxx(self.window), getbegy(self.window));
+    }
+
     pub fn build_query(&self, highlight: Highlight) -> Option<Query> {
         if self.input_field.is_empty() {
             None
         } else {
             Some(Query {
-                text: self.input_field.text.borrow().clone(),
+                matcher: Matcher::new(&self.input_field.text.borrow(), self.options.mode),
                 filter: self.options.filter,
+                inverse: self.options.inverse,
+                context: self.options.context,
                 highlight: highlight,
             })
         }
@@ -89,6 +113,44 @@ impl Search {
         self.render();
     }
 
+    /// Inverting only makes sense while filtering, so it also enables filter mode.
+    pub fn toggle_inverse(&mut self) {
+        self.options.inverse = !self.options.inverse;
+        if self.options.inverse {
+            self.options.filter = true;
+        }
+        self.render();
+    }
+
+    pub fn cycle_context(&mut self) {
+        let current = max(self.options.context.before, self.options.context.after);
+        let next = CONTEXT_STEPS.iter().find(|&&step| step > current).unwrap_or(&0);
+        self.options.context = Context::new(*next, *next);
+        self.render();
+    }
+
+    /// Used when switching tabs, so each one starts with its configured context.
+    /// Contexts wider than the steps take more room, leaving less for the query.
+    pub fn set_context(&mut self, context: Context) {
+        self.options.context = context;
+        self.resize(getmaxx(self.window), getbegy(self.window));
+    }
+
+    pub fn toggle_regex(&mut self) {
+        self.options.mode.regex = !self.options.mode.regex;
+        self.render();
+    }
+
+    pub fn toggle_case_insensitive(&mut self) {
+        self.options.mode.case_insensitive = !self.options.mode.case_insensitive;
+        self.render();
+    }
+
+    pub fn toggle_whole_word(&mut self) {
+        self.options.mode.whole_word = !self.options.mode.whole_word;
+        self.render();
+    }
+
     pub fn show(&self) {
         self.render();
         curs_set(CURSOR_VISIBILITY::CURSOR_VERY_VISIBLE);
@@ -100,11 +162,11 @@ impl Search {
         hide_panel(self.panel);
     }
 
-    fn color_pair_id(&self) -> i16 {
+    fn role(&self) -> Role {
         if !self.matches_found && self.input_field.text.borrow().len() > 0 {
-            NO_MATCHES_COLOR_PAIR_ID
+            Role::Alert
         } else {
-            WITH_MATCHES_COLOR_PAIR_ID
+            Role::Bar
         }
     }
 }
@@ -121,7 +183,8 @@ pub struct InputField {
 
 impl InputField {
     fn new(parent_window: WINDOW) -> InputField {
-        let window = derwin(parent_window, 1, COLS() - OPTIONS_WIDTH, 0, 1);
+        let width = input_width(COLS(), Context::none(), &KeyMap::default());
+        let window = derwin(parent_window, 1, width, 0, 1);
         syncok(window, true);
 
         InputField {
@@ -136,8 +199,8 @@ impl InputField {
         wattron(self.window, color_pair);
     }
 
-    fn resize(&self, container_width: i32, offset: i32) {
-        wresize(self.window, 1, container_width - OPTIONS_WIDTH);
+    fn resize(&self, width: i32, offset: i32) {
+        wresize(self.window, 1, width);
         mvwin(self.window, offset, 1);
         wrefresh(self.window);
     }
@@ -163,20 +226,148 @@ impl InputField {
     }
 }
 
+/// Width left for the query once the options are laid out.
+pub fn input_width(container_width: i32, context: Context, key_map: &KeyMap) -> i32 {
+    let layout = Layout::for_width(container_width, context, key_map);
+    max(container_width - 1 - layout.width(context, key_map), 1)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layout {
+    Full,
+    Compact,
+    Hidden,
+}
+
+impl Layout {
+    pub fn for_width(container_width: i32, context: Context, key_map: &KeyMap) -> Layout {
+        [Layout::Full, Layout::Compact]
+            .iter()
+            .cloned()
+            .find(|layout| {
+                container_width - 1 - layout.width(context, key_map) >= MIN_INPUT_WIDTH
+            })
+            .unwrap_or(Layout::Hidden)
+    }
+
+    /// Room is kept for the widest of the context steps, so the query doesn't
+    /// move while cycling through them.
+    pub fn width(&self, context: Context, key_map: &KeyMap) -> i32 {
+        if *self == Layout::Hidden {
+            return 1;
+        }
+
+        let widest_step = CONTEXT_STEPS[CONTEXT_STEPS.len() - 1];
+        let widest_step_amounts = format!("{}/{}", widest_step, widest_step);
+        max(labels_width(&self.labels(key_map, &context_amounts(context)), *self),
+            labels_width(&self.labels(key_map, &widest_step_amounts), *self))
+    }
+
+    fn separator(&self) -> &'static str {
+        if *self == Layout::Full { " / " } else { " " }
+    }
+
+    /// Labels of the options in the order they're shown. The letter typed
+    /// along with Alt to toggle each option is underlined, while other keys
+    /// are spelled out.
+    pub fn labels(&self, key_map: &KeyMap, context_amounts: &str) -> Vec<Label> {
+        OPTIONS.iter()
+            .map(|&(action, name)| {
+                let mut label = self.label(name, key_map, action);
+                if action == Action::CycleContext {
+                    if *self == Layout::Full {
+                        label.text.push(' ');
+                    }
+                    label.text.push_str(context_amounts);
+                }
+                label
+            })
+            .collect()
+    }
+
+    fn label(&self, name: &str, key_map: &KeyMap, action: Action) -> Label {
+        let keys = key_map.keys(action);
+        let letter = keys.iter().filter_map(|key| key.alt_letter()).next();
+        let key_name = keys.first().map(|key| key.to_string());
+
+        match (*self, letter) {
+            (Layout::Full, _) => {
+                let position = letter.and_then(|letter| {
+                    name.chars().position(|value| value.to_lowercase().eq(letter.to_lowercase()))
+                });
+                match (position, key_name) {
+                    (Some(index), _) => Label::new(name.to_string(), Some(index)),
+                    (None, Some(key_name)) => Label::new(format!("{} ({})", name, key_name), None),
+                    (None, None) => Label::new(name.to_string(), None),
+                }
+            }
+            (_, Some(letter)) => Label::new(letter.to_uppercase().collect(), Some(0)),
+            (_, None) => Label::new(key_name.unwrap_or_else(|| name[..1].to_string()), None),
+        }
+    }
+}
+
+/// An option as it's printed, along with the position of the character
+/// standing for its key.
+pub struct Label {
+    pub text: String,
+    pub shortcut: Option<usize>,
+}
+
+impl Label {
+    fn new(text: String, shortcut: Option<usize>) -> Label {
+        Label {
+            text: text,
+            shortcut: shortcut,
+        }
+    }
+}
+
+/// Lines shown before and after matches, spelled out once when they're the same.
+fn context_amounts(context: Context) -> String {
+    if context.before == context.after {
+        context.before.to_string()
+    } else {
+        format!("{}/{}", context.before, context.after)
+    }
+}
+
+/// Columns taken by the labels once printed.
+fn labels_width(labels: &[Label], layout: Layout) -> i32 {
+    let separator_width = layout.separator().len();
+    let width = labels.iter()
+        .fold(2, |sum, label| sum + separator_width + label.text.chars().count());
+    width as i32
+}
+
 pub struct Options {
     pub window: WINDOW,
     pub next: bool,
     pub previous: bool,
     filter: bool,
+    inverse: bool,
+    context: Context,
+    mode: MatchMode,
+    layout: Cell<Layout>,
+    key_map: KeyMap,
 }
 
 impl Options {
-    fn new(parent_window: WINDOW) -> Options {
+    fn new(parent_window: WINDOW, container_width: i32) -> Options {
+        let key_map = KeyMap::default();
+        let layout = Layout::for_width(container_width, Context::none(), &key_map);
+        let width = layout.width(Context::none(), &key_map);
+
         Options {
-            window: derwin(parent_window, 1, OPTIONS_WIDTH, 0, COLS() - OPTIONS_WIDTH),
+            window: derwin(parent_window, 1, width, 0, container_width - width),
+            layout: Cell::new(layout),
             next: false,
             previous: false,
             filter: false,
+            inverse: false,
+            context: Context::none(),
+            mode: MatchMode::default(),
+            key_map: key_map,
         }
     }
 
@@ -184,46 +375,62 @@ impl Options {
         wclear(self.window);
         readline::handle_redisplay();
         wbkgd(self.window, color_pair);
+
+        let layout = self.layout.get();
+        if layout == Layout::Hidden {
+            return;
+        }
         wprintw(self.window, "  ");
 
-        self.print_label("[N]ext", self.next, color_pair);
-        self.print_label("[P]rev", self.previous, color_pair);
-        self.print_label("Filter [M]ode", self.filter, color_pair);
+        let active = [self.next,
+                      self.previous,
+                      self.filter,
+                      self.inverse,
+                      !self.context.is_empty(),
+                      self.mode.regex,
+                      self.mode.case_insensitive,
+                      self.mode.whole_word];
+        let labels = layout.labels(&self.key_map, &context_amounts(self.context));
+        for (label, is_active) in labels.iter().zip(active.iter()) {
+            self.print_label(label, *is_active, layout.separator());
+        }
     }
 
     fn resize(&self, container_width: i32) {
-        wresize(self.window, 1, OPTIONS_WIDTH);
-        mvderwin(self.window, 0, container_width - OPTIONS_WIDTH);
+        let layout = Layout::for_width(container_width, self.context, &self.key_map);
+        let width = layout.width(self.context, &self.key_map);
+        self.layout.set(layout);
+
+        // Shrunk first so that it fits inside its parent wherever it's moved.
+        wresize(self.window, 1, 1);
+        mvderwin(self.window, 0, container_width - width);
+        wresize(self.window, 1, width);
         wrefresh(self.window);
     }
 
-    fn print_label(&self, text: &str, active: bool, color_pair: attr_t) {
-        lazy_static! {
-            static ref SHORTCUT_MATCHER: Regex = Regex::new(r"(.*)?(\[(\w)\])(.*)?").unwrap();
-        }
-
-        wprintw(self.window, " / ");
+    fn print_label(&self, label: &Label, active: bool, separator: &str) {
+        wprintw(self.window, separator);
 
         if active {
-            wattron(self.window, COLOR_PAIR(2));
+            wattron(self.window, Role::Menu.to_attr());
         }
 
-        for (i, capture) in SHORTCUT_MATCHER.captures(text).unwrap().iter().skip(1).enumerate() {
-            match i {
-                2 => {
-                    wattron(self.window, A_UNDERLINE());
-                    waddch(self.window, capture.unwrap().as_str().chars().next().unwrap() as chtype);
-                    wattroff(self.window, A_UNDERLINE());
-                }
-                0 | 3 => {
-                    wprintw(self.window, capture.unwrap().as_str());
-                }
-                _ => {}
+        match label.shortcut {
+            Some(index) => {
+                let chars = label.text.chars().collect::<Vec<_>>();
+                wprintw(self.window, &chars[..index].iter().collect::<String>());
+                wattron(self.window, A_UNDERLINE());
+                wprintw(self.window, &chars[index].to_string());
+                wattroff(self.window, A_UNDERLINE());
+                wprintw(self.window, &chars[index + 1..].iter().collect::<String>());
+            }
+            None => {
+                wprintw(self.window, &label.text);
             }
         }
 
         if active {
-            wattroff(self.window, color_pair);
+            wattroff(self.window, Role::Menu.to_attr());
         }
     }
 }
@@ -0,0 +1,105 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use ncurses::*;
+
+use ui::theme::Role;
+
+pub static HEIGHT: i32 = 1;
+pub static SPARKLINE_WIDTH: usize = 30;
+
+/// Figures about the input, shown between the lines and the navigation.
+pub struct Status {
+    pub received_count: usize,
+    pub held_count: usize,
+    pub capacity: usize,
+    pub rate: f32,
+    pub tab_rate: f32,
+    pub tab_sparkline: String,
+    /// Lines received since the view was paused, if it is.
+    pub paused_count: Option<usize>,
+    /// Outcome of the last command, such as an export.
+    pub notice: Option<String>,
+}
+
+impl Status {
+    pub fn text(&self) -> String {
+        let paused = match self.paused_count {
+            Some(count) => format!(" PAUSED, {} new lines ·", group_digits(count)),
+            None => String::new(),
+        };
+        let notice = match self.notice {
+            Some(ref text) => format!(" {} ·", text),
+            None => String::new(),
+        };
+
+        format!("{}{} {} lines · {}/{} in memory · {:.1}/s · tab {:.1}/s, last minute {}",
+                notice,
+                paused,
+                self.received_count,
+                self.held_count,
+                self.capacity,
+                self.rate,
+                self.tab_rate,
+                self.tab_sparkline)
+    }
+}
+
+/// Separates thousands by commas, as in "1,234".
+pub fn group_digits(value: usize) -> String {
+    let digits = value.to_string();
+    let mut result = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+
+    result
+}
+
+pub struct StatusBar {
+    pub window: WINDOW,
+}
+
+impl StatusBar {
+    pub fn new(position_x: i32, position_y: i32) -> StatusBar {
+        StatusBar { window: newwin(HEIGHT, COLS(), position_x, position_y) }
+    }
+
+    /// Only staged, as it's redrawn while a query or a path is typed in. The
+    /// cursor is put back there as the screen is updated.
+    pub fn render(&self, status: &Status) {
+        werase(self.window);
+        wbkgd(self.window, Role::Bar.to_attr());
+        let text = status.text().chars().take(getmaxx(self.window) as usize).collect::<String>();
+        mvwprintw(self.window, 0, 0, &text);
+        wnoutrefresh(self.window);
+    }
+
+    pub fn resize(&self, container_width: i32, offset: i32) {
+        wresize(self.window, HEIGHT, container_width);
+        mvwin(self.window, offset, 0);
+    }
+
+    pub fn destroy(&self) {
+        delwin(self.window);
+    }
+}
@@ -0,0 +1,93 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::{min, max};
+
+use ncurses::*;
+
+use core::template::Template;
+use ui::content::Content;
+
+static SPARKLINE_WIDTH: usize = 16;
+static HEADER: &'static str = "Message templates  (Enter opens a tab with its lines, Esc closes)";
+
+/// Overview of what the lines of a tab mostly say, taking the place of the
+/// lines until it's closed.
+pub struct TemplateList {
+    rows: Vec<(Template, String)>,
+    selected: usize,
+}
+
+impl TemplateList {
+    pub fn new(templates: Vec<Template>, first_id: usize, next_id: usize) -> TemplateList {
+        let rows = templates.into_iter()
+            .map(|template| {
+                let sparkline = template.sparkline(first_id, next_id, SPARKLINE_WIDTH);
+                (template, sparkline)
+            })
+            .collect();
+
+        TemplateList {
+            rows: rows,
+            selected: 0,
+        }
+    }
+
+    pub fn select(&mut self, offset: i32) {
+        let last_index = self.rows.len().saturating_sub(1) as i32;
+        self.selected = min(max(0, self.selected as i32 + offset), last_index) as usize;
+    }
+
+    pub fn selected_item(&self) -> Option<&Template> {
+        self.rows.get(self.selected).map(|&(ref template, _)| template)
+    }
+
+    /// The header takes the first row, and the rest scroll along with the
+    /// selected template.
+    pub fn render(&self, content: &Content, width: i32, height: i32) {
+        let visible_rows = max(1, height - 1) as usize;
+        let first_index = (self.selected + 1).saturating_sub(visible_rows);
+
+        content.clear();
+        content.ensure_height(height + 1);
+
+        wattron(content.window, A_BOLD());
+        mvwprintw(content.window, 0, 0, &truncate(HEADER, width as usize));
+        wattroff(content.window, A_BOLD());
+
+        let rows = self.rows.iter().enumerate().skip(first_index).take(visible_rows);
+        for (row, (index, &(ref template, ref sparkline))) in rows.enumerate() {
+            let attributes = if index == self.selected { A_REVERSE() } else { 0 };
+            let text = format!("{:>7} {:<width$} {}",
+                               template.count(),
+                               sparkline,
+                               template,
+                               width = SPARKLINE_WIDTH);
+
+            wattron(content.window, attributes);
+            mvwprintw(content.window, row as i32 + 1, 0, &truncate(&text, width as usize));
+            wattroff(content.window, attributes);
+        }
+
+        prefresh(content.window, 0, 0, 0, 0, height - 1, width - 1);
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,224 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use rustc_serialize::{Decodable, Decoder};
+use ncurses::*;
+
+use ui::color::{self, COLOR_DEFAULT};
+
+/// Parts of the interface that get their colors from the theme.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    /// Status line, search bar, export prompt and the selected tab.
+    Bar,
+    /// Tabs, along with the search options that are turned on.
+    Menu,
+    /// Search bar without matches and export errors.
+    Alert,
+    Match,
+    /// Current search match and the selected histogram time range.
+    CurrentMatch,
+    /// Tabs with new entries, unless their filter picks its own color.
+    Unread,
+    /// Follows the last line of a block that never got its end.
+    Marker,
+    /// Lines shown around search matches in filter mode, the separators
+    /// between them and the line details summary.
+    Context,
+    /// Headings of the line details.
+    Heading,
+}
+
+static ROLES: [(Role, &'static str); 9] = [(Role::Bar, "bar"),
+                                           (Role::Menu, "menu"),
+                                           (Role::Alert, "alert"),
+                                           (Role::Match, "match"),
+                                           (Role::CurrentMatch, "current_match"),
+                                           (Role::Unread, "unread"),
+                                           (Role::Marker, "marker"),
+                                           (Role::Context, "context"),
+                                           (Role::Heading, "heading")];
+
+/// Foreground and background of each role, in the order above.
+static THEMES: [(&'static str, [&'static str; 9]); 4] =
+    [("dark",
+      ["white on blue", "black on yellow", "white on magenta", "black on white",
+       "black on yellow", "white on red", "white on red", "cyan", "yellow"]),
+     ("light",
+      ["white on blue", "black on cyan", "white on red", "black on yellow", "white on magenta",
+       "white on red", "white on red", "blue", "magenta"]),
+     ("solarized",
+      ["black on cyan", "black on white", "white on red", "black on yellow", "black on green",
+       "white on magenta", "white on red", "cyan", "yellow"]),
+     ("high-contrast",
+      ["white on black", "black on white", "white on red", "black on yellow", "black on cyan",
+       "black on yellow", "white on red", "white", "black on white"])];
+
+impl Role {
+    fn pair_id(&self) -> i16 {
+        match *self {
+            Role::Bar => 1,
+            Role::Menu => 2,
+            Role::Alert => 3,
+            Role::Match => 4,
+            Role::CurrentMatch => 5,
+            Role::Unread => 6,
+            Role::Marker => 7,
+            Role::Context => 8,
+            Role::Heading => 9,
+        }
+    }
+
+    /// Without colors, roles are told apart by their attributes.
+    pub fn to_attr(&self) -> attr_t {
+        if color::is_enabled() {
+            return COLOR_PAIR(self.pair_id());
+        }
+
+        match *self {
+            Role::Bar | Role::Match | Role::Unread => A_REVERSE(),
+            Role::Menu | Role::Heading => A_BOLD(),
+            Role::Alert => A_BOLD() | A_UNDERLINE(),
+            Role::CurrentMatch => A_REVERSE() | A_BOLD() | A_UNDERLINE(),
+            Role::Marker => A_REVERSE() | A_BOLD(),
+            Role::Context => A_DIM(),
+        }
+    }
+}
+
+pub struct Theme {
+    pub name: String,
+    colors: Vec<(i16, i16)>,
+}
+
+impl Theme {
+    pub fn default() -> Theme {
+        Theme::from_name(THEMES[0].0).unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        THEMES.iter().find(|&&(theme_name, _)| theme_name == name).map(|&(_, ref specs)| {
+            Theme {
+                name: name.to_string(),
+                colors: specs.iter().map(|spec| parse_colors(spec).unwrap()).collect(),
+            }
+        })
+    }
+
+    pub fn colors(&self, role: Role) -> (i16, i16) {
+        self.colors[role_index(role)]
+    }
+
+    pub fn set_colors(&mut self, role: Role, colors: (i16, i16)) {
+        self.colors[role_index(role)] = colors;
+    }
+
+    pub fn apply(&self) {
+        if !color::is_enabled() {
+            return;
+        }
+
+        for &(role, _) in &ROLES {
+            let (foreground, background) = self.colors(role);
+            init_pair(role.pair_id(), foreground, background);
+        }
+    }
+}
+
+/// Keys of the `[theme]` config section.
+pub fn key_names() -> Vec<&'static str> {
+    let mut names = vec!["name"];
+    names.extend(ROLES.iter().map(|&(_, name)| name));
+    names
+}
+
+pub fn theme_names() -> Vec<&'static str> {
+    THEMES.iter().map(|&(name, _)| name).collect()
+}
+
+/// Reads colors given as in "white on blue", or just "white" to keep the
+/// terminal background.
+pub fn parse_colors(spec: &str) -> Option<(i16, i16)> {
+    let names = spec.split(" on ").map(|name| name.trim()).collect::<Vec<_>>();
+
+    match names.len() {
+        1 => color::from_name(names[0]).map(|foreground| (foreground, COLOR_DEFAULT)),
+        2 => {
+            match (color::from_name(names[0]), color::from_name(names[1])) {
+                (Some(foreground), Some(background)) => Some((foreground, background)),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn role_index(role: Role) -> usize {
+    ROLES.iter().position(|&(value, _)| value == role).unwrap()
+}
+
+/// Starts from the named theme, or the default one, and changes the colors
+/// of the roles that are listed. Unknown keys are skipped.
+impl Decodable for Theme {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<Theme, D::Error> {
+        let entries = try!(decoder.read_map(|d, len| {
+            let mut entries = vec![];
+            for index in 0..len {
+                let key = try!(d.read_map_elt_key(index, |d| d.read_str()));
+                let value = try!(d.read_map_elt_val(index, |d| d.read_str()));
+                entries.push((key, value));
+            }
+            Ok(entries)
+        }));
+
+        let mut theme = match entries.iter().find(|&&(ref key, _)| key == "name") {
+            Some(&(_, ref name)) => {
+                match Theme::from_name(name) {
+                    Some(value) => value,
+                    None => {
+                        let message = format!("unknown theme `{}`, available ones are: {}",
+                                              name,
+                                              theme_names().join(", "));
+                        return Err(decoder.error(&message));
+                    }
+                }
+            }
+            None => Theme::default(),
+        };
+
+        for &(ref key, ref spec) in entries.iter().filter(|&&(ref key, _)| key != "name") {
+            // Unknown keys are reported as warnings by the config file
+            let role = match ROLES.iter().find(|&&(_, name)| name == key) {
+                Some(&(role, _)) => role,
+                None => continue,
+            };
+            match parse_colors(spec) {
+                Some(colors) => theme.set_colors(role, colors),
+                None => {
+                    let message = format!("invalid colors `{}` for `{}`, expected as in \
+                                           \"white on blue\"",
+                                          spec,
+                                          key);
+                    return Err(decoder.error(&message));
+                }
+            }
+        }
+
+        Ok(theme)
+    }
+}
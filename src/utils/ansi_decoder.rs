@@ -64,6 +64,9 @@ lazy_static! {
     };
 }
 
+/// Codes turning each attribute on and off, by attribute id.
+static ATTRIBUTE_CODES: [(usize, usize); 5] = [(1, 22), (3, 23), (4, 24), (7, 27), (9, 29)];
+
 #[derive(Clone)]
 pub enum Component {
     Style(&'static Style),
@@ -98,6 +101,19 @@ impl ComponentCollection {
         components
     }
 
+    /// The line as it was received, escape sequences included.
+    pub fn to_ansi(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| {
+                match *item {
+                    Component::Style(style) => style.to_ansi(),
+                    Component::Content(ref value) => value.clone(),
+                }
+            })
+            .collect()
+    }
+
     fn new() -> ComponentCollection {
         ComponentCollection { items: Vec::new() }
     }
@@ -113,6 +129,30 @@ pub enum Style {
     Reset,
 }
 
+impl Style {
+    fn to_ansi(&self) -> String {
+        let codes = match *self {
+            Style::Attribute(id, _, active) => {
+                let (on, off) = ATTRIBUTE_CODES[id - 1];
+                vec![if active { on } else { off }]
+            }
+            Style::Color(foreground, background) => {
+                let mut codes = vec![];
+                if let Some(color) = foreground {
+                    codes.push(if color == COLOR_DEFAULT { 39 } else { 30 + color as usize });
+                }
+                if let Some(color) = background {
+                    codes.push(if color == COLOR_DEFAULT { 49 } else { 40 + color as usize });
+                }
+                codes
+            }
+            Style::Reset => vec![0],
+        };
+
+        codes.iter().map(|code| format!("\x1b[{}m", code)).collect()
+    }
+}
+
 pub trait AnsiStr {
     fn has_ansi_escape_sequence(&self) -> bool;
 
@@ -21,6 +21,8 @@ use std::path::PathBuf;
 use std::fs;
 
 use utils::config_file::ConfigFile;
+use ui::key_map::KeyMap;
+use core::headless::STDIN_PATH;
 
 const VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
@@ -29,9 +31,14 @@ pub struct Args {
     pub flag_config: Option<String>,
     pub flag_max: Option<usize>,
     pub flag_lines: Option<usize>,
+    pub flag_filter: Option<String>,
+    pub flag_print: bool,
+    pub flag_follow: bool,
+    pub flag_mouse: bool,
     arg_input: Option<String>,
     flag_init: Option<String>,
     flag_version: bool,
+    flag_print_keys: bool,
 }
 
 impl Args {
@@ -42,8 +49,11 @@ impl Args {
             self.write_config();
         } else if self.flag_version {
             self.display_version();
+        } else if self.flag_print_keys {
+            self.print_keys();
         }
 
+        self.assert_print_flags();
         callback(self);
     }
 
@@ -65,11 +75,30 @@ impl Args {
         quit!(message);
     }
 
+    fn print_keys(&self) {
+        let (config, _) = ConfigFile::locate(self.get_config());
+        quit!(config.keys.unwrap_or_else(KeyMap::default).to_toml());
+    }
+
+    /// Flags that only tell how lines are printed mean nothing to the
+    /// interface.
+    fn assert_print_flags(&self) {
+        assert_quit!(self.flag_print || (self.flag_filter.is_none() && !self.flag_follow),
+                     "`--filter` and `--follow` can only be used along with `--print`.");
+    }
+
+    /// The standard input is only read when printing, as the interface needs
+    /// the terminal for keys.
     pub fn get_target(&self) -> String {
         let target = self.arg_input.as_ref().unwrap_or_else(|| {
             critical_quit!("No input file provided");
         });
-        assert_file_exists(&PathBuf::from(target));
+        if target == STDIN_PATH {
+            assert_quit!(self.flag_print,
+                         "Reading from the standard input (`-`) requires `--print`.");
+        } else {
+            assert_file_exists(&PathBuf::from(target));
+        }
 
         target.to_string()
     }
@@ -0,0 +1,33 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::{self, Write};
+
+use rustc_serialize::base64::{ToBase64, STANDARD};
+
+/// Terminal escape sequence asking the terminal to place the text on the
+/// system clipboard. Works over ssh as well, as long as the terminal allows it.
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", text.as_bytes().to_base64(STANDARD))
+}
+
+pub fn copy(text: &str) {
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(osc52_sequence(text).as_bytes());
+    let _ = stdout.flush();
+}
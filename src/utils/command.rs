@@ -0,0 +1,66 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Runs the command through the shell, with the text as its input. Its output
+/// is discarded so it doesn't end up on top of the interface, and it's left
+/// to finish in the background, in case it never reads its input. Failing to
+/// start it is returned right away, while how it exited is sent once it did,
+/// along with what it wrote to its standard error.
+pub fn pipe(command: &str, text: &str) -> io::Result<Receiver<io::Result<()>>> {
+    let mut child = try!(Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn());
+
+    let stdin = child.stdin.take();
+    let input = format!("{}\n", text);
+    let (sender, receiver) = mpsc::channel();
+
+    // Written apart from waiting, so a command filling up its standard error
+    // before reading its input doesn't hang
+    thread::spawn(move || {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(input.as_bytes());
+        }
+    });
+    thread::spawn(move || {
+        let result = child.wait_with_output().and_then(|output| {
+            if output.status.success() {
+                return Ok(());
+            }
+
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let message = match stderr.trim() {
+                "" => output.status.to_string(),
+                text => format!("{} - {}", output.status, text),
+            };
+            Err(io::Error::new(io::ErrorKind::Other, message))
+        });
+        let _ = sender.send(result);
+    });
+
+    Ok(receiver)
+}
@@ -17,23 +17,71 @@
  */
 
 use std::path::PathBuf;
+use std::cmp::min;
 use std::{env, process};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use toml;
+use rustc_serialize::Decodable;
 
 use core::filter::Filter;
+use core::highlight_rule::HighlightRule;
+use core::correlation::CorrelationRule;
+use ui::key_map::{self, KeyMap};
+use ui::long_lines::LongLines;
+use ui::theme::{self, Theme};
 
 const SAMPLE: &'static [u8] = include_bytes!("../etc/sample-config.toml");
 const DEFAULT: &'static str = include_str!("../etc/default-config.toml");
 
+/// Highest config format version this release understands. Files without a
+/// `version` key predate versioning and are treated as version 1.
+pub const CURRENT_VERSION: i64 = 1;
+const INITIAL_VERSION: i64 = 1;
+
+const TOP_LEVEL_KEYS: &'static [&'static str] = &["version",
+                                                   "filters",
+                                                   "highlights",
+                                                   "correlations",
+                                                   "keys",
+                                                   "pipe_command",
+                                                   "long_lines",
+                                                   "mouse",
+                                                   "theme"];
+const FILTER_KEYS: &'static [&'static str] = &["name",
+                                                "contains",
+                                                "starts_with",
+                                                "ends_with",
+                                                "context",
+                                                "before_context",
+                                                "after_context",
+                                                "unread_color",
+                                                "group_by",
+                                                "max_block_length",
+                                                "block_timeout",
+                                                "collapse"];
+const HIGHLIGHT_KEYS: &'static [&'static str] = &["pattern", "color"];
+const CORRELATION_KEYS: &'static [&'static str] = &["name", "pattern"];
+
 #[derive(RustcDecodable)]
 pub struct ConfigFile {
     pub filters: Vec<Filter>,
+    pub highlights: Vec<HighlightRule>,
+    pub correlations: Vec<CorrelationRule>,
+    pub keys: Option<KeyMap>,
+    pub pipe_command: Option<String>,
+    pub long_lines: Option<LongLines>,
+    /// Mouse events take over text selection in the terminal, so they're only
+    /// read when enabled.
+    pub mouse: Option<bool>,
+    pub theme: Option<Theme>,
 }
 
+/// Problems that don't stop the config from loading, such as unknown keys.
+pub type Warnings = Vec<String>;
+
 impl ConfigFile {
-    pub fn from_path(path: PathBuf) -> Option<ConfigFile> {
+    pub fn from_path(path: PathBuf) -> Option<(ConfigFile, Warnings)> {
         if !path.exists() {
             return None;
         }
@@ -52,21 +100,28 @@ impl ConfigFile {
         Some(ConfigFile::new(contents))
     }
 
-    pub fn from_current_dir() -> Option<ConfigFile> {
-        let mut path = env::current_dir().unwrap();
-        path.push(".flow");
+    pub fn from_current_dir() -> Option<(ConfigFile, Warnings)> {
+        ConfigFile::from_path(current_dir_path())
+    }
 
-        ConfigFile::from_path(path)
+    pub fn from_home_dir() -> Option<(ConfigFile, Warnings)> {
+        ConfigFile::from_path(home_dir_path())
     }
 
-    pub fn from_home_dir() -> Option<ConfigFile> {
-        let mut path = env::home_dir().unwrap();
-        path.push(".flow");
+    /// Uses the provided path, then looks in the current directory and user
+    /// home, falling back to the default config.
+    pub fn locate(path: PathBuf) -> (ConfigFile, Warnings) {
+        ConfigFile::locate_path(path)
+            .and_then(ConfigFile::from_path)
+            .unwrap_or_else(ConfigFile::default)
+    }
 
-        ConfigFile::from_path(path)
+    /// Path of the file `locate` loads, if any.
+    pub fn locate_path(path: PathBuf) -> Option<PathBuf> {
+        vec![path, current_dir_path(), home_dir_path()].into_iter().find(|path| path.exists())
     }
 
-    pub fn default() -> ConfigFile {
+    pub fn default() -> (ConfigFile, Warnings) {
         ConfigFile::new(DEFAULT)
     }
 
@@ -85,19 +140,146 @@ impl ConfigFile {
         let _ = file_handle.write(SAMPLE);
     }
 
-    fn new(contents: &str) -> ConfigFile {
+    pub fn parse(contents: &str) -> Result<(ConfigFile, Warnings), String> {
         let parsed_contents = match toml::Parser::new(contents).parse() {
             Some(value) => value,
-            None => {
-                critical_quit!("Provided config file doesn't have a valid format.");
-            }
+            None => return Err("Provided config file doesn't have a valid format.".to_string()),
         };
 
-        match toml::decode(toml::Value::Table(parsed_contents)) {
-            Some(value) => value,
-            None => {
-                critical_quit!("Error deserializing config");
+        let warnings = try!(validate(&parsed_contents));
+
+        let mut decoder = toml::Decoder::new(toml::Value::Table(parsed_contents));
+        match ConfigFile::decode(&mut decoder) {
+            Ok(mut value) => {
+                HighlightRule::assign_palette_colors(&mut value.highlights);
+                Ok((value, warnings))
+            }
+            Err(message) => {
+                let mut message = format!("Error deserializing config - {}", message);
+                for warning in warnings {
+                    message.push_str(&format!("\n  {}", warning));
+                }
+                Err(message)
+            }
+        }
+    }
+
+    fn new(contents: &str) -> (ConfigFile, Warnings) {
+        match ConfigFile::parse(contents) {
+            Ok(value) => value,
+            Err(message) => {
+                critical_quit!(message);
+            }
+        }
+    }
+}
+
+fn current_dir_path() -> PathBuf {
+    let mut path = env::current_dir().unwrap();
+    path.push(".flow");
+    path
+}
+
+fn home_dir_path() -> PathBuf {
+    let mut path = env::home_dir().unwrap();
+    path.push(".flow");
+    path
+}
+
+/// Checks the config version, returning the unknown keys as warnings.
+fn validate(table: &toml::Table) -> Result<Warnings, String> {
+    try!(read_version(table));
+    let mut warnings = unknown_keys(table, TOP_LEVEL_KEYS, "");
+
+    if let Some(&toml::Value::Array(ref filters)) = table.get("filters") {
+        for (index, filter) in filters.iter().enumerate() {
+            if let toml::Value::Table(ref filter_table) = *filter {
+                let location = match filter_table.get("name").and_then(|name| name.as_str()) {
+                    Some(name) => format!(" in filter `{}`", name),
+                    None => format!(" in filter #{}", index + 1),
+                };
+                warnings.extend(unknown_keys(filter_table, FILTER_KEYS, &location));
             }
         }
     }
+
+    if let Some(&toml::Value::Array(ref highlights)) = table.get("highlights") {
+        for (index, highlight) in highlights.iter().enumerate() {
+            if let toml::Value::Table(ref highlight_table) = *highlight {
+                let location = format!(" in highlight #{}", index + 1);
+                warnings.extend(unknown_keys(highlight_table, HIGHLIGHT_KEYS, &location));
+            }
+        }
+    }
+
+    if let Some(&toml::Value::Array(ref correlations)) = table.get("correlations") {
+        for (index, correlation) in correlations.iter().enumerate() {
+            if let toml::Value::Table(ref correlation_table) = *correlation {
+                let location = format!(" in correlation #{}", index + 1);
+                warnings.extend(unknown_keys(correlation_table, CORRELATION_KEYS, &location));
+            }
+        }
+    }
+
+    if let Some(&toml::Value::Table(ref keys)) = table.get("keys") {
+        warnings.extend(unknown_keys(keys, &key_map::action_names(), " in keys"));
+    }
+
+    if let Some(&toml::Value::Table(ref theme)) = table.get("theme") {
+        warnings.extend(unknown_keys(theme, &theme::key_names(), " in theme"));
+    }
+
+    Ok(warnings.into_iter().map(|warning| format!("Unknown config key {}", warning)).collect())
+}
+
+fn read_version(table: &toml::Table) -> Result<i64, String> {
+    match table.get("version") {
+        None => Ok(INITIAL_VERSION),
+        Some(&toml::Value::Integer(value)) if value > CURRENT_VERSION => {
+            Err(format!("Config file version {} is newer than the supported version {}. Please \
+                         upgrade flow.",
+                        value,
+                        CURRENT_VERSION))
+        }
+        Some(&toml::Value::Integer(value)) if value >= INITIAL_VERSION => Ok(value),
+        Some(_) => Err("Config file `version` needs to be a positive integer.".to_string()),
+    }
+}
+
+fn unknown_keys(table: &toml::Table, known_keys: &[&str], location: &str) -> Vec<String> {
+    table.keys()
+        .filter(|key| !known_keys.contains(&key.as_str()))
+        .map(|key| {
+            match closest_key(key, known_keys) {
+                Some(suggestion) => {
+                    format!("`{}`{}, did you mean `{}`?", key, location, suggestion)
+                }
+                None => format!("`{}`{}", key, location),
+            }
+        })
+        .collect()
+}
+
+fn closest_key<'a>(key: &str, known_keys: &[&'a str]) -> Option<&'a str> {
+    known_keys.iter()
+        .map(|known_key| (edit_distance(key, known_key), *known_key))
+        .filter(|&(distance, _)| distance <= 2)
+        .min()
+        .map(|(_, known_key)| known_key)
+}
+
+fn edit_distance(first: &str, second: &str) -> usize {
+    let second_chars = second.chars().collect::<Vec<_>>();
+    let mut previous = (0..second_chars.len() + 1).collect::<Vec<_>>();
+
+    for (i, first_char) in first.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, second_char) in second_chars.iter().enumerate() {
+            let substitution = previous[j] + if first_char == *second_char { 0 } else { 1 };
+            current.push(min(substitution, min(previous[j + 1], current[j]) + 1));
+        }
+        previous = current;
+    }
+
+    previous[second_chars.len()]
 }
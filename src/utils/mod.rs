@@ -20,3 +20,6 @@ pub mod ansi_decoder;
 pub mod args;
 pub mod settings;
 pub mod config_file;
+pub mod sparkline;
+pub mod clipboard;
+pub mod command;
@@ -17,10 +17,16 @@
  */
 
 use std::process;
+use std::path::PathBuf;
 
 use utils::args::Args;
 use utils::config_file::ConfigFile;
 use core::filter::Filter;
+use core::highlight_rule::HighlightRule;
+use core::correlation::CorrelationRule;
+use ui::key_map::KeyMap;
+use ui::long_lines::LongLines;
+use ui::theme::Theme;
 
 static DEFAULT_LAST_LINES_SHOWN: usize = 10;
 static DEFAULT_MAX_LINES_STORED: usize = 3000;
@@ -30,13 +36,27 @@ pub struct Settings {
     pub last_lines_count: usize,
     pub max_lines_count: usize,
     pub filters: Vec<Filter>,
+    pub highlights: Vec<HighlightRule>,
+    pub correlations: Vec<CorrelationRule>,
+    pub key_map: KeyMap,
+    pub pipe_command: Option<String>,
+    pub long_lines: LongLines,
+    /// Whether clicks and the wheel are read instead of selecting text.
+    pub mouse: bool,
+    pub theme: Theme,
+    /// Whether matching lines are printed instead of shown.
+    pub print: bool,
+    pub follow: bool,
+    pub filter_name: Option<String>,
+    /// Config file in use, unless the default one is.
+    pub config_path: Option<PathBuf>,
+    /// Problems found in the config file that didn't stop it from loading.
+    pub warnings: Vec<String>,
 }
 
 impl Settings {
     pub fn from_args(args: Args) -> Settings {
-        let config = ConfigFile::from_path(args.get_config())
-            .unwrap_or(ConfigFile::from_current_dir()
-                .unwrap_or(ConfigFile::from_home_dir().unwrap_or_else(ConfigFile::default)));
+        let (config, warnings) = ConfigFile::locate(args.get_config());
 
         assert_quit!(!config.filters.is_empty(),
                      "At least one filter needs to be defined.");
@@ -46,6 +66,18 @@ impl Settings {
             last_lines_count: args.flag_lines.unwrap_or(DEFAULT_LAST_LINES_SHOWN),
             max_lines_count: args.flag_max.unwrap_or(DEFAULT_MAX_LINES_STORED),
             filters: config.filters,
+            highlights: config.highlights,
+            correlations: config.correlations,
+            key_map: config.keys.unwrap_or_else(KeyMap::default),
+            pipe_command: config.pipe_command,
+            long_lines: config.long_lines.unwrap_or(LongLines::Wrap),
+            mouse: args.flag_mouse || config.mouse.unwrap_or(false),
+            theme: config.theme.unwrap_or_else(Theme::default),
+            print: args.flag_print,
+            follow: args.flag_follow,
+            filter_name: args.flag_filter.clone(),
+            config_path: ConfigFile::locate_path(args.get_config()),
+            warnings: warnings,
         }
     }
 
@@ -0,0 +1,35 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+static LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Draws a character for each bucket, scaled against the highest one. Empty
+/// buckets are left blank.
+pub fn sparkline(buckets: &[usize]) -> String {
+    let highest = buckets.iter().cloned().max().unwrap_or(0);
+
+    buckets.iter()
+        .map(|count| {
+            if *count == 0 {
+                ' '
+            } else {
+                LEVELS[(count * (LEVELS.len() - 1)) / highest]
+            }
+        })
+        .collect()
+}
@@ -63,3 +63,10 @@ fn breaks_text_into_ncurses_components() {
     let components = text_with_unknown_ansi.to_components();
     assert_eq!(7, components.items.len());
 }
+
+#[test]
+fn restores_ansi_string_from_components() {
+    let text_with_ansi = "\x1b[1m\x1b[36mHello\x1b[0m,\x1b[1m\x1b[41m ncurses!\x1b[49m\x1b[22m";
+
+    assert_eq!(text_with_ansi, text_with_ansi.to_components().to_ansi());
+}
@@ -0,0 +1,471 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate toml;
+extern crate flow;
+
+use flow::core::filter::Filter;
+use flow::core::buffer::Buffer;
+use flow::core::collapse::Repetition;
+use flow::core::line::{LineCollection, Parser};
+
+const REQUEST_LINES: [&'static str; 10] = ["Started GET \"/\"",
+                                           "Rendered index",
+                                           "Completed 200 OK",
+                                           "noise",
+                                           "Started GET \"/missing\"",
+                                           "Completed 404 Not Found",
+                                           "Started POST \"/login\"",
+                                           "Rendered form",
+                                           "noise",
+                                           "Completed 200 OK"];
+
+#[test]
+fn counts_matching_lines() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "error"
+    "##));
+    let mut lines = LineCollection::new(10);
+    lines.extend(strings(&["error one", "info", "error two"]));
+
+    buffer.update_index(&lines);
+
+    assert_eq!(2, buffer.match_count.get());
+}
+
+#[test]
+fn counts_unread_lines_since_last_marked_as_read() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "error"
+    "##));
+    let mut lines = LineCollection::new(10);
+    lines.extend(strings(&["error one", "info"]));
+    buffer.mark_as_read(&lines);
+
+    lines.extend(strings(&["error two", "error three", "info"]));
+    buffer.update_index(&lines);
+
+    assert_eq!(3, buffer.match_count.get());
+    assert_eq!(2, buffer.unread_count.get());
+
+    buffer.mark_as_read(&lines);
+    assert_eq!(0, buffer.unread_count.get());
+}
+
+#[test]
+fn evicted_lines_are_no_longer_counted() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "error"
+    "##));
+    let mut lines = LineCollection::new(2);
+    lines.extend(strings(&["error one", "error two", "info"]));
+
+    buffer.update_index(&lines);
+
+    assert_eq!(1, buffer.match_count.get());
+    assert_eq!(1, buffer.unread_count.get());
+}
+
+#[test]
+fn indexes_boundary_blocks_incrementally() {
+    let filters = [r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+    "##,
+                   r##"
+       name = "Requests"
+       starts_with = "Started (?P<matching>GET)"
+    "##,
+                   r##"
+       name = "Completed"
+       ends_with = "Completed"
+       contains = "Rendered"
+    "##];
+
+    for contents in filters.iter() {
+        let buffer = Buffer::new(toml_string_to_filter(contents));
+        let mut lines = LineCollection::new(20);
+
+        for line in REQUEST_LINES.iter() {
+            lines.extend(strings(&[line]));
+            buffer.update_index(&lines);
+
+            assert_eq!(parsed_contents(&buffer, &lines), indexed_contents(&buffer, &lines));
+        }
+    }
+}
+
+#[test]
+fn indexes_grouped_blocks_incrementally() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       group_by = "^\\[(\\w+)\\]"
+    "##));
+    let mut lines = LineCollection::new(20);
+
+    let incoming = ["[a] Started GET",
+                    "[b] Started GET",
+                    "[a] Completed 200",
+                    "[b] Rendered",
+                    "[b] Completed 200"];
+
+    for line in incoming.iter() {
+        lines.extend(strings(&[line]));
+        buffer.update_index(&lines);
+
+        assert_eq!(parsed_contents(&buffer, &lines), indexed_contents(&buffer, &lines));
+    }
+    assert_eq!(5, buffer.match_count.get());
+}
+
+#[test]
+fn reindexes_grouped_blocks_from_the_oldest_one_changed() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       group_by = "^\\[(\\w+)\\]"
+    "##));
+    let mut lines = LineCollection::new(8);
+    lines.extend(strings(&["[a] Started GET", "[a] Completed 200", "[b] Started GET",
+                           "[c] Started GET", "[b] Completed 200", "[c] Completed 200"]));
+    buffer.update_index(&lines);
+    assert_eq!(6, buffer.match_count.get());
+
+    // Lines of groups whose blocks stay the same leave the index as it is
+    lines.extend(strings(&["[b] noise"]));
+    assert_eq!(6, buffer.update_index(&lines));
+
+    // The block left open keeps the index unchanged until it's completed
+    lines.extend(strings(&["[d] Started GET"]));
+    assert_eq!(6, buffer.update_index(&lines));
+
+    // The first block of `a` loses its start, while `b` and `c` are kept
+    lines.extend(strings(&["[d] Completed 200"]));
+    assert_eq!(0, buffer.update_index(&lines));
+    assert_eq!(6, buffer.match_count.get());
+
+    // Dropping evicted blocks leaves the rest of the index as it is
+    lines.extend(strings(&["[e] noise"]));
+    assert_eq!(6, buffer.update_index(&lines));
+    assert_eq!(strings(&["[b] Started GET",
+                         "[b] Completed 200",
+                         "[c] Started GET",
+                         "[c] Completed 200",
+                         "[d] Started GET",
+                         "[d] Completed 200"]),
+               indexed_contents(&buffer, &lines));
+}
+
+#[test]
+fn counts_unread_lines_as_blocks_change() {
+    let contents = r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       group_by = "^\\[(\\w+)\\]"
+       max_block_length = 6
+    "##;
+    let buffer = Buffer::new(toml_string_to_filter(contents));
+    let mut lines = LineCollection::new(8);
+    let values = ["[a] Started GET", "[b] Started GET", "[a] Rendered", "[c] Started GET",
+                  "[a] Completed 200", "[b] Rendered", "[c] Completed 200", "[d] noise",
+                  "[d] noise", "[b] Started GET", "[d] noise", "[b] Completed 200", "[d] noise",
+                  "[d] noise", "[d] noise", "[d] noise", "[d] noise", "[d] noise"];
+
+    for (position, value) in values.iter().enumerate() {
+        if position == 6 {
+            buffer.mark_as_read(&lines);
+        }
+        let first_unread_id = if position < 6 { 0 } else { 6 };
+
+        lines.extend(strings(&[value]));
+        buffer.update_index(&lines);
+
+        let buffer_lines = buffer.with_lines(&lines);
+        let unread_count = (&buffer_lines)
+            .into_iter()
+            .filter(|line| line.id >= first_unread_id)
+            .count();
+        assert_eq!(unread_count, buffer.unread_count.get());
+    }
+}
+
+#[test]
+fn marks_the_end_of_unterminated_blocks() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       max_block_length = 3
+    "##));
+    let mut lines = LineCollection::new(20);
+    lines.extend(strings(&["Started GET /a", "Rendered a", "Started GET /b"]));
+    buffer.update_index(&lines);
+
+    assert_eq!(0, buffer.match_count.get());
+
+    lines.extend(strings(&["Completed 200"]));
+    buffer.update_index(&lines);
+
+    assert_eq!(4, buffer.match_count.get());
+    assert!(buffer.ends_unterminated_block(1));
+    assert!(!buffer.ends_unterminated_block(3));
+}
+
+#[test]
+fn indexes_unterminated_blocks_incrementally() {
+    let contents = r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       contains = "Rendered"
+       max_block_length = 7
+    "##;
+    let buffer = Buffer::new(toml_string_to_filter(contents));
+    let mut lines = LineCollection::new(30);
+    let values = ["Started GET /a", "Rendered a", "Started GET /b", "Rendered b", "noise",
+                  "Completed 200", "Started GET /c", "noise", "Started GET /d", "Rendered d",
+                  "noise", "noise", "noise", "Started GET /e", "Rendered e", "Completed 200",
+                  "noise", "noise", "noise", "noise"];
+
+    for value in values.iter() {
+        lines.extend(strings(&[value]));
+        buffer.update_index(&lines);
+
+        assert_indexed_at_once(&buffer, contents, &lines);
+    }
+    assert!(buffer.unterminated_count() > 0);
+}
+
+#[test]
+fn indexes_unterminated_grouped_blocks_incrementally() {
+    let contents = r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       group_by = "^\\[(\\w+)\\]"
+       max_block_length = 6
+    "##;
+    let buffer = Buffer::new(toml_string_to_filter(contents));
+    let mut lines = LineCollection::new(8);
+    let values = ["[a] Started GET", "[b] Started GET", "[a] Rendered", "[c] Started GET",
+                  "[a] Completed 200", "[b] Rendered", "[c] Completed 200", "[d] noise",
+                  "[d] noise", "[b] Started GET", "[d] noise", "[b] Completed 200", "[d] noise",
+                  "[d] noise", "[d] noise", "[d] noise", "[d] noise", "[d] noise"];
+
+    for value in values.iter() {
+        lines.extend(strings(&[value]));
+        buffer.update_index(&lines);
+
+        assert_indexed_at_once(&buffer, contents, &lines);
+    }
+}
+
+#[test]
+fn keeps_runs_of_repeated_lines_as_lines_arrive() {
+    let filters = [r##"
+       name = "Retries"
+       contains = "retry"
+       collapse = "similar"
+    "##,
+                   r##"
+       name = "Requests"
+       starts_with = "Started"
+       ends_with = "Completed"
+       collapse = "identical"
+    "##];
+    let values = ["retry 1", "retry 2", "Started GET", "retry 3", "Completed", "Completed",
+                  "Started GET", "noise", "Completed", "retry 4", "retry 5", "Started GET",
+                  "Started GET", "retry 6", "Completed", "noise"];
+
+    for contents in filters.iter() {
+        let buffer = Buffer::new(toml_string_to_filter(contents));
+        let mut lines = LineCollection::new(6);
+
+        for value in values.iter() {
+            lines.extend(strings(&[value]));
+            buffer.update_index(&lines);
+
+            let buffer_lines = buffer.with_lines(&lines);
+            let expected = buffer.collapse().unwrap().repetitions(&buffer_lines);
+            assert_eq!(runs(&expected), runs(&buffer_lines.repetitions()));
+        }
+
+        buffer.toggle_collapse(&lines);
+        assert_eq!(buffer.match_count.get(), buffer.with_lines(&lines).repetitions().len());
+        buffer.toggle_collapse(&lines);
+        let buffer_lines = buffer.with_lines(&lines);
+        let expected = buffer.collapse().unwrap().repetitions(&buffer_lines);
+        assert_eq!(runs(&expected), runs(&buffer_lines.repetitions()));
+    }
+}
+
+#[test]
+fn keeps_the_rows_of_shown_lines_as_lines_arrive() {
+    let filters = [r##"
+       name = "Retries"
+       contains = "retry"
+       collapse = "similar"
+    "##,
+                   r##"
+       name = "Requests"
+       starts_with = "Started"
+       ends_with = "Completed"
+    "##];
+    let values = ["retry 1", "retry 2", "retry 3", "Started GET", "Completed",
+                  "retry 4 after waiting for the queue", "Started GET /a/rather/long/path",
+                  "noise", "Completed", "retry 5", "Started GET", "retry 6",
+                  "Completed in a while", "noise"];
+
+    for contents in filters.iter() {
+        let buffer = Buffer::new(toml_string_to_filter(contents));
+        let mut lines = LineCollection::new(6);
+
+        for value in values.iter() {
+            lines.extend(strings(&[value]));
+            buffer.update_index(&lines);
+            assert_rows(&buffer, &lines, 12);
+        }
+
+        buffer.toggle_collapse(&lines);
+        assert_rows(&buffer, &lines, 12);
+        assert_rows(&buffer, &lines, 20);
+        buffer.toggle_collapse(&lines);
+        assert_rows(&buffer, &lines, 20);
+    }
+}
+
+#[test]
+fn drops_evicted_lines_from_the_index() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Ok"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+    "##));
+    let mut lines = LineCollection::new(5);
+    lines.extend(strings(&REQUEST_LINES[..5]));
+    buffer.update_index(&lines);
+
+    lines.extend(strings(&REQUEST_LINES[5..]));
+    buffer.update_index(&lines);
+
+    assert_eq!(vec!["Started POST \"/login\"", "Rendered form", "noise", "Completed 200 OK"],
+               indexed_contents(&buffer, &lines));
+}
+
+#[test]
+fn leaves_out_lines_received_from_the_given_id() {
+    let buffer = Buffer::new(toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "error"
+    "##));
+    let mut lines = LineCollection::new(10);
+    lines.extend(strings(&["error one", "info"]));
+    buffer.update_index(&lines);
+    let next_id = lines.next_id();
+
+    lines.extend(strings(&["error two"]));
+    buffer.update_index(&lines);
+
+    let buffer_lines = buffer.with_lines_before(&lines, Some(next_id));
+    assert_eq!(vec!["error one"],
+               (&buffer_lines)
+                   .into_iter()
+                   .map(|line| line.content_without_ansi.clone())
+                   .collect::<Vec<_>>());
+    assert_eq!(strings(&["error one", "error two"]), indexed_contents(&buffer, &lines));
+}
+
+/// Compares the index with the lines parsed at once, and with the one of a
+/// new buffer updated with every line at once, along with the rows kept.
+fn assert_indexed_at_once(buffer: &Buffer, contents: &str, lines: &LineCollection) {
+    let parsed_buffer = Buffer::new(toml_string_to_filter(contents));
+    parsed_buffer.update_index(lines);
+
+    assert_eq!(parsed_contents(buffer, lines), indexed_contents(buffer, lines));
+    assert_eq!(indexed_contents(&parsed_buffer, lines), indexed_contents(buffer, lines));
+    assert_eq!(parsed_buffer.unterminated_count(), buffer.unterminated_count());
+    for id in 0..lines.next_id() {
+        assert_eq!(parsed_buffer.ends_unterminated_block(id), buffer.ends_unterminated_block(id));
+    }
+    assert_rows(buffer, lines, 12);
+}
+
+/// Compares the rows kept for the shown lines with the ones measured anew.
+fn assert_rows(buffer: &Buffer, lines: &LineCollection, width: usize) {
+    let buffer_lines = buffer.with_lines(lines);
+    assert!(buffer_lines.lay_out(width));
+
+    let heights = buffer_lines.repetitions()
+        .iter()
+        .map(|repetition| {
+            let height = repetition.guess_height(width) as i32;
+            if buffer.ends_unterminated_block(repetition.last.id) {
+                height + 1
+            } else {
+                height
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(heights.len(), buffer_lines.len());
+    assert_eq!(heights.iter().fold(0, |sum, height| sum + height), buffer_lines.height());
+
+    let mut row = 0;
+    for (position, height) in heights.iter().enumerate() {
+        assert_eq!(row, buffer_lines.row_of(position));
+        assert_eq!(position, buffer_lines.position_at(row + height - 1));
+        row += *height;
+    }
+}
+
+fn runs(repetitions: &[Repetition]) -> Vec<(usize, usize, usize)> {
+    repetitions.iter()
+        .map(|repetition| (repetition.first.id, repetition.last.id, repetition.count))
+        .collect()
+}
+
+fn parsed_contents(buffer: &Buffer, lines: &LineCollection) -> Vec<String> {
+    let mut contents = lines.entries
+        .iter()
+        .parse(&buffer.filter)
+        .map(|line| line.content_without_ansi.clone())
+        .collect::<Vec<_>>();
+    contents.reverse();
+    contents
+}
+
+fn indexed_contents(buffer: &Buffer, lines: &LineCollection) -> Vec<String> {
+    let buffer_lines = buffer.with_lines(lines);
+    (&buffer_lines).into_iter().map(|line| line.content_without_ansi.clone()).collect()
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn toml_string_to_filter(contents: &str) -> Filter {
+    let parsed_contents = toml::Parser::new(contents).parse().unwrap();
+    toml::decode(toml::Value::Table(parsed_contents)).unwrap()
+}
@@ -0,0 +1,27 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::utils::clipboard::osc52_sequence;
+
+#[test]
+fn encodes_text_for_the_terminal_clipboard() {
+    assert_eq!("\x1b]52;c;R0VUIC8gMjAw\x07", osc52_sequence("GET / 200"));
+    assert_eq!("\x1b]52;c;w5c1\x07", osc52_sequence("×5"));
+}
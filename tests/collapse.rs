@@ -0,0 +1,64 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::collapse::{Collapse, mask};
+use flow::core::line::LineCollection;
+
+#[test]
+fn masks_timestamps_ids_and_numbers() {
+    assert_eq!("# [#] retrying job # after #ms",
+               mask("2016-10-02 14:03:55.120 [4f1c2a9e] retrying job 1207 after 250ms"));
+    assert_eq!("request # failed", mask("request 0b1d3f6e-93a4-4e63-8b7d-1c0e3c39d5a2 failed"));
+}
+
+#[test]
+fn collapses_identical_consecutive_lines() {
+    let lines = collection(&["retrying", "retrying", "retrying", "done", "retrying"]);
+    let repetitions = Collapse::Identical.repetitions(&lines.entries);
+
+    assert_eq!(vec![("retrying", 3), ("done", 1), ("retrying", 1)],
+               repetitions.iter()
+                   .map(|repetition| (&repetition.first.content_without_ansi[..], repetition.count))
+                   .collect::<Vec<_>>());
+    assert_eq!(2, repetitions[0].last.id);
+}
+
+#[test]
+fn collapses_similar_lines_only_when_asked() {
+    let lines = collection(&["10:00:01 retry 1", "10:00:02 retry 2", "10:00:03 retry 3"]);
+
+    assert_eq!(3, Collapse::Identical.repetitions(&lines.entries).len());
+    assert_eq!(1, Collapse::Similar.repetitions(&lines.entries).len());
+}
+
+#[test]
+fn shows_the_count_after_the_first_line() {
+    let lines = collection(&["retrying", "retrying"]);
+    let line = Collapse::Identical.repetitions(&lines.entries)[0].to_line();
+
+    assert!(line.content_without_ansi.starts_with("retrying ×2 ("));
+    assert_eq!(0, line.id);
+}
+
+fn collection(lines: &[&str]) -> LineCollection {
+    let mut collection = LineCollection::new(lines.len());
+    collection.extend(lines.iter().map(|line| line.to_string()));
+    collection
+}
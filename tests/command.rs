@@ -0,0 +1,62 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::process;
+
+use flow::utils::command::pipe;
+
+#[test]
+fn pipes_text_to_the_command() {
+    let path = env::temp_dir().join(format!("flow-pipe-{}", process::id()));
+    let _ = fs::remove_file(&path);
+
+    pipe(&format!("cat > {}.tmp && mv {}.tmp {}",
+                  path.display(),
+                  path.display(),
+                  path.display()),
+         "GET / 200")
+        .unwrap()
+        .recv()
+        .unwrap()
+        .unwrap();
+
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    let _ = fs::remove_file(&path);
+
+    assert_eq!("GET / 200\n", contents);
+}
+
+#[test]
+fn returns_before_the_command_reads_its_input() {
+    let line = "x".repeat(1 << 20);
+
+    assert!(pipe("sleep 1", &line).is_ok());
+}
+
+#[test]
+fn reports_the_exit_status_of_a_failed_command() {
+    let error = pipe("echo oops >&2; false", "x").unwrap().recv().unwrap().unwrap_err();
+
+    assert_eq!("exit status: 1 - oops", error.to_string());
+}
@@ -0,0 +1,412 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate ncurses;
+
+use ncurses::{COLOR_MAGENTA, COLOR_CYAN, COLOR_BLACK, COLOR_GREEN};
+use flow::utils::config_file::{ConfigFile, CURRENT_VERSION};
+use flow::ui::input::{Input, Key, Modifier};
+use flow::ui::key_map::{Action, Scope};
+use flow::ui::long_lines::LongLines;
+use flow::ui::theme::Role;
+
+#[test]
+fn parses_config_without_version() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+    "##);
+
+    assert_eq!(1, config.ok().unwrap().0.filters.len());
+}
+
+#[test]
+fn parses_config_with_supported_version() {
+    let config = ConfigFile::parse(r##"
+       version = 1
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    assert!(config.is_ok());
+}
+
+#[test]
+fn parses_config_with_current_version() {
+    let config = ConfigFile::parse(&format!(r##"
+       version = {}
+
+       [[filters]]
+       name = "All"
+    "##, CURRENT_VERSION));
+
+    assert!(config.ok().unwrap().1.is_empty());
+}
+
+#[test]
+fn rejects_config_with_newer_version() {
+    let config = ConfigFile::parse(r##"
+       version = 99
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("version 99 is newer"));
+}
+
+#[test]
+fn rejects_config_with_invalid_version() {
+    let config = ConfigFile::parse(r##"
+       version = "one"
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    assert!(config.is_err());
+}
+
+#[test]
+fn reports_unknown_filter_keys_with_suggestions() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[filters]]
+       name = "Errors"
+       start_with = "Started"
+       contain = "xyz"
+    "##);
+
+    let warnings = config.ok().unwrap().1.join("\n");
+    assert!(warnings.contains("`start_with` in filter `Errors`, did you mean `starts_with`?"));
+    assert!(warnings.contains("`contain` in filter `Errors`, did you mean `contains`?"));
+}
+
+#[test]
+fn keeps_warnings_apart_from_the_config_keys() {
+    let config = ConfigFile::parse(r##"
+       warnings = 1
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    let (_, warnings) = config.ok().unwrap();
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].contains("`warnings`"));
+}
+
+#[test]
+fn reports_unknown_top_level_keys() {
+    let config = ConfigFile::parse(r##"
+       filter_list = []
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    let (config, warnings) = config.ok().unwrap();
+    assert_eq!(1, config.filters.len());
+    assert_eq!(1, warnings.len());
+    assert!(warnings[0].contains("`filter_list`"));
+    assert!(!warnings[0].contains("did you mean"));
+}
+
+#[test]
+fn rejects_invalid_context_values() {
+    let quoted = ConfigFile::parse(r##"
+       [[filters]]
+       name = "Errors"
+       context = "3"
+    "##);
+    let negative = ConfigFile::parse(r##"
+       [[filters]]
+       name = "Errors"
+       after_context = -1
+    "##);
+
+    let message = quoted.err().unwrap();
+    assert!(message.contains("`context` in filter `Errors` needs to be a positive integer or 0"));
+    let message = negative.err().unwrap();
+    assert!(message.contains("`after_context` in filter `Errors`"));
+}
+
+#[test]
+fn rejects_invalid_filter_patterns() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "Requests"
+       starts_with = "Started"
+       ends_with = "Completed"
+       group_by = "\\[(\\w+"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("`group_by` in filter `Requests` isn't a valid regular expression"));
+}
+
+#[test]
+fn parses_highlight_rules() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[highlights]]
+       pattern = "user_id=\\d+"
+       color = "magenta"
+
+       [[highlights]]
+       pattern = "(?i)error"
+    "##);
+
+    let highlights = config.ok().unwrap().0.highlights;
+    assert_eq!(2, highlights.len());
+    assert_eq!(COLOR_MAGENTA, highlights[0].color);
+    assert!(highlights[0].matcher.is_match("user_id=42"));
+    assert!(highlights[1].matcher.is_match("ERROR"));
+}
+
+#[test]
+fn assigns_distinct_colors_to_highlight_rules_without_one() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[highlights]]
+       pattern = "warn"
+
+       [[highlights]]
+       pattern = "error"
+    "##);
+
+    let highlights = config.ok().unwrap().0.highlights;
+    assert_eq!(COLOR_MAGENTA, highlights[0].color);
+    assert_eq!(COLOR_CYAN, highlights[1].color);
+}
+
+#[test]
+fn skips_colors_taken_by_other_highlight_rules() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[highlights]]
+       pattern = "warn"
+
+       [[highlights]]
+       pattern = "error"
+       color = "magenta"
+    "##);
+
+    let highlights = config.ok().unwrap().0.highlights;
+    assert_eq!(COLOR_CYAN, highlights[0].color);
+    assert_eq!(COLOR_MAGENTA, highlights[1].color);
+}
+
+#[test]
+fn rejects_highlight_rule_with_unknown_color() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[highlights]]
+       pattern = "user_id"
+       color = "purple"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("unknown highlight color `purple`"));
+}
+
+#[test]
+fn parses_correlation_rules() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[correlations]]
+       name = "Request"
+       pattern = "\\[(?P<id>[0-9a-f]+)\\]"
+    "##);
+
+    let correlations = config.ok().unwrap().0.correlations;
+    assert_eq!(1, correlations.len());
+    assert_eq!("Request", correlations[0].name);
+}
+
+#[test]
+fn reports_unknown_correlation_keys() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [[correlations]]
+       name = "Request"
+       patern = "\\[(?P<id>[0-9a-f]+)\\]"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("`patern` in correlation #1, did you mean `pattern`?"));
+}
+
+#[test]
+fn parses_key_bindings() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [keys]
+       quit = "Q"
+       search = ["s", "Ctrl-S"]
+    "##);
+
+    let key_map = config.ok().unwrap().0.keys.unwrap();
+    let search = Input::Kb(Key::Char('S'), Some(Modifier::Ctrl));
+    assert_eq!(Some(Action::Quit),
+               key_map.action_for(&Input::Kb(Key::Char('Q'), None), Scope::Menu, false));
+    assert_eq!(Some(Action::Search), key_map.action_for(&search, Scope::Menu, false));
+    assert_eq!(None,
+               key_map.action_for(&Input::Kb(Key::Char('q'), None), Scope::Menu, false));
+}
+
+#[test]
+fn reports_unknown_key_binding_actions() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [keys]
+       qiut = "Q"
+       search = "s"
+    "##);
+
+    let (config, warnings) = config.ok().unwrap();
+    let warnings = warnings.join("\n");
+    assert!(warnings.contains("`qiut` in keys, did you mean `quit`?"));
+    assert_eq!(Some(Action::Search),
+               config.keys
+                   .unwrap()
+                   .action_for(&Input::Kb(Key::Char('s'), None), Scope::Menu, false));
+}
+
+#[test]
+fn rejects_invalid_key_bindings() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [keys]
+       quit = "Ctrl-PageUp"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("invalid key `Ctrl-PageUp` for `quit`"));
+}
+
+#[test]
+fn parses_long_lines_mode() {
+    let config = ConfigFile::parse(r##"
+       long_lines = "truncate"
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    assert_eq!(Some(LongLines::Truncate), config.unwrap().0.long_lines);
+}
+
+#[test]
+fn leaves_the_mouse_to_the_terminal_unless_enabled() {
+    let enabled = ConfigFile::parse(r##"
+       mouse = true
+
+       [[filters]]
+       name = "All"
+    "##);
+    let unset = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+    "##);
+
+    assert_eq!(Some(true), enabled.unwrap().0.mouse);
+    assert_eq!(None, unset.unwrap().0.mouse);
+}
+
+#[test]
+fn rejects_unknown_long_lines_mode() {
+    let config = ConfigFile::parse(r##"
+       long_lines = "hide"
+
+       [[filters]]
+       name = "All"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("unknown long lines mode `hide`"));
+}
+
+#[test]
+fn parses_theme_with_changed_colors() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [theme]
+       name = "light"
+       current_match = "black on green"
+    "##);
+
+    let theme = config.ok().unwrap().0.theme.unwrap();
+    assert_eq!("light", theme.name);
+    assert_eq!((COLOR_BLACK, COLOR_GREEN), theme.colors(Role::CurrentMatch));
+}
+
+#[test]
+fn rejects_unknown_theme() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [theme]
+       name = "neon"
+    "##);
+
+    let message = config.err().unwrap();
+    assert!(message.contains("unknown theme `neon`, available ones are: dark, light"));
+}
+
+#[test]
+fn reports_unknown_theme_keys() {
+    let config = ConfigFile::parse(r##"
+       [[filters]]
+       name = "All"
+
+       [theme]
+       matches = "black on green"
+    "##);
+
+    let (config, warnings) = config.ok().unwrap();
+    let warnings = warnings.join("\n");
+    assert!(warnings.contains("`matches` in theme, did you mean `match`?"));
+    assert!(config.theme.is_some());
+}
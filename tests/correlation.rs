@@ -0,0 +1,70 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate regex;
+extern crate flow;
+
+use regex::Regex;
+use flow::core::correlation::{Correlation, CorrelationRule};
+use flow::core::filter::Filter;
+use flow::core::line::{LineCollection, Parser};
+
+#[test]
+fn extracts_the_named_id_group() {
+    let rule = CorrelationRule::new("Request", Regex::new(r"(\w+) \[(?P<id>\d+)\]").unwrap());
+
+    assert_eq!(Some("42"), rule.extract("INFO [42] Started GET"));
+    assert_eq!(None, rule.extract("booting worker"));
+}
+
+#[test]
+fn falls_back_to_the_first_group() {
+    let rule = CorrelationRule::new("Thread", Regex::new(r"thread=(\S+)").unwrap());
+
+    assert_eq!(Some("worker-1"), rule.extract("thread=worker-1 job done"));
+}
+
+#[test]
+fn uses_the_first_rule_able_to_extract_an_id() {
+    let rules = vec![CorrelationRule::new("Trace", Regex::new(r"trace=(\w+)").unwrap()),
+                     CorrelationRule::new("Request", Regex::new(r"\[(\d+)\]").unwrap())];
+
+    let correlation = Correlation::find(&rules, "[42] Started GET").unwrap();
+    assert_eq!("Request 42", correlation.name());
+    assert!(Correlation::find(&rules, "booting worker").is_none());
+}
+
+#[test]
+fn correlated_filter_keeps_lines_sharing_the_id_in_order() {
+    let rules = vec![CorrelationRule::new("Request", Regex::new(r"\[(\d+)\]").unwrap())];
+    let filter = Filter::correlated(Correlation::find(&rules, "[4] Started").unwrap());
+
+    let mut lines = LineCollection::new(10);
+    lines.extend(vec!["[4] Started", "[45] Started", "[4] Rendered", "[5] Started", "[4] Completed"]
+        .into_iter()
+        .map(|line| line.to_string()));
+
+    let mut actual = lines.entries
+        .iter()
+        .parse(&filter)
+        .map(|line| line.content_without_ansi.clone())
+        .collect::<Vec<_>>();
+    actual.reverse();
+
+    assert_eq!(vec!["[4] Started", "[4] Rendered", "[4] Completed"], actual);
+}
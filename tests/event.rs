@@ -0,0 +1,249 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::cell::RefCell;
+
+use flow::ui::event::{EventBuilder, Event, ExportAction, Offset, PendingKeys};
+use flow::ui::input::{Input, Key, Modifier, Mouse};
+use flow::ui::key_map::{KeyMap, KeySpec};
+use flow::ui::navigation::State as NavigationState;
+use flow::ui::highlighter::Highlight;
+
+#[test]
+fn repeats_scrolling_by_the_typed_count() {
+    match last_event("20j") {
+        Event::ScrollContents(Offset::Line(-20)) => {}
+        _ => panic!("expected to scroll down 20 lines"),
+    }
+
+    match last_event("3k") {
+        Event::ScrollContents(Offset::Line(3)) => {}
+        _ => panic!("expected to scroll up 3 lines"),
+    }
+}
+
+#[test]
+fn repeats_search_by_the_typed_count() {
+    match last_event("4N") {
+        Event::RepeatSearch(Highlight::Previous, 4) => {}
+        _ => panic!("expected to find the 4th previous match"),
+    }
+}
+
+#[test]
+fn scrolls_half_a_page() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+    let input = Input::Kb(Key::Char('D'), Some(Modifier::Ctrl));
+
+    match EventBuilder::new(input, 4, &key_map, &pending_keys).construct(&NavigationState::Menu) {
+        Event::ScrollContents(Offset::HalfViewport(-1)) => {}
+        _ => panic!("expected to scroll down half a page"),
+    }
+}
+
+#[test]
+fn reads_multiple_key_commands() {
+    match last_event("gg") {
+        Event::ScrollContents(Offset::Top) => {}
+        _ => panic!("expected to scroll to the top"),
+    }
+
+    match last_event("ma") {
+        Event::SetMark('a') => {}
+        _ => panic!("expected to set mark `a`"),
+    }
+
+    match last_event("'a") {
+        Event::JumpToMark('a') => {}
+        _ => panic!("expected to jump to mark `a`"),
+    }
+}
+
+#[test]
+fn reads_rebound_multiple_key_commands() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("go_to_top", vec![KeySpec::parse("z").unwrap()]);
+    key_map.bind("quit", vec![KeySpec::parse("5").unwrap()]);
+    key_map.bind("set_mark", vec![KeySpec::parse("b").unwrap()]);
+    key_map.bind("close_tab", vec![KeySpec::parse("m").unwrap()]);
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    construct(&key_map, &pending_keys, Input::Kb(Key::Char('z'), None));
+    match construct(&key_map, &pending_keys, Input::Kb(Key::Char('z'), None)) {
+        Event::ScrollContents(Offset::Top) => {}
+        _ => panic!("expected to scroll to the top"),
+    }
+
+    match construct(&key_map, &pending_keys, Input::Kb(Key::Char('5'), None)) {
+        Event::Quit => {}
+        _ => panic!("expected to quit"),
+    }
+
+    match construct(&key_map, &pending_keys, Input::Kb(Key::Char('m'), None)) {
+        Event::CloseMenuItem => {}
+        _ => panic!("expected to close the tab"),
+    }
+}
+
+#[test]
+fn keeps_pending_keys_while_waiting_for_input() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    construct(&key_map, &pending_keys, Input::Kb(Key::Char('5'), None));
+    construct(&key_map, &pending_keys, Input::None);
+
+    match construct(&key_map, &pending_keys, Input::Kb(Key::Char('j'), None)) {
+        Event::ScrollContents(Offset::Line(-5)) => {}
+        _ => panic!("expected to scroll down 5 lines"),
+    }
+}
+
+#[test]
+fn drops_pending_keys_after_an_unrelated_key() {
+    match last_event("5xj") {
+        Event::ScrollContents(Offset::Line(-1)) => {}
+        _ => panic!("expected to scroll down a single line"),
+    }
+
+    match last_event("gxg") {
+        Event::Other => {}
+        _ => panic!("expected nothing to happen"),
+    }
+}
+
+#[test]
+fn scrolls_with_the_mouse_wheel() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    match construct(&key_map, &pending_keys, Input::Mouse(Mouse::WheelDown)) {
+        Event::ScrollContents(Offset::Line(-3)) => {}
+        _ => panic!("expected to scroll down 3 lines"),
+    }
+
+    let input = Input::Mouse(Mouse::WheelUp);
+    match EventBuilder::new(input, -1, &key_map, &pending_keys)
+        .construct(&NavigationState::Templates) {
+        Event::SelectTemplate(-1) => {}
+        _ => panic!("expected to select the previous template"),
+    }
+}
+
+#[test]
+fn passes_clicks_along() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    match construct(&key_map, &pending_keys, Input::Mouse(Mouse::Click(4, 12))) {
+        Event::Click(4, 12) => {}
+        _ => panic!("expected a click on row 4"),
+    }
+}
+
+#[test]
+fn moves_the_cursor_instead_of_scrolling_while_selecting() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+    let construct_selecting = |input| {
+        EventBuilder::new(input, -1, &key_map, &pending_keys).construct(&NavigationState::Selection)
+    };
+
+    match construct_selecting(Input::Kb(Key::Char('k'), None)) {
+        Event::MoveCursor(-1) => {}
+        _ => panic!("expected to move the cursor up"),
+    }
+
+    match construct_selecting(Input::Kb(Key::PageUp, None)) {
+        Event::ScrollContents(Offset::Viewport(1)) => {}
+        _ => panic!("expected to scroll up a page"),
+    }
+
+    match construct_selecting(Input::Kb(Key::Enter, None)) {
+        Event::Navigation(NavigationState::Detail) => {}
+        _ => panic!("expected to show the line details"),
+    }
+    match construct_selecting(Input::Kb(Key::Char('t'), None)) {
+        Event::CorrelateLine => {}
+        _ => panic!("expected to open a correlation for the selected line"),
+    }
+}
+
+#[test]
+fn starts_selecting_lines() {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    match construct(&key_map, &pending_keys, Input::Kb(Key::Enter, None)) {
+        Event::Navigation(NavigationState::Selection) => {}
+        _ => panic!("expected to start selecting lines"),
+    }
+}
+
+#[test]
+fn reads_rebound_keys_of_the_panes() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("close_help", vec![KeySpec::parse("x").unwrap()]);
+    key_map.bind("save_export", vec![KeySpec::parse("Ctrl-S").unwrap()]);
+    let pending_keys = RefCell::new(PendingKeys::default());
+    let in_state = |input: Input, state: NavigationState| {
+        EventBuilder::new(input, -1, &key_map, &pending_keys).construct(&state)
+    };
+
+    match in_state(Input::Kb(Key::Char('x'), None), NavigationState::Help) {
+        Event::CloseHelp => {}
+        _ => panic!("expected to close the help"),
+    }
+    match in_state(Input::Kb(Key::Escape, None), NavigationState::Help) {
+        Event::Other => {}
+        _ => panic!("expected Escape to no longer close the help"),
+    }
+    match in_state(Input::Kb(Key::Char('S'), Some(Modifier::Ctrl)), NavigationState::Export) {
+        Event::Export(ExportAction::Save) => {}
+        _ => panic!("expected to save the export"),
+    }
+    match in_state(Input::Kb(Key::Enter, None), NavigationState::Export) {
+        Event::Other => {}
+        _ => panic!("expected Enter to no longer save the export"),
+    }
+    match in_state(Input::Kb(Key::Char('q'), None), NavigationState::Export) {
+        Event::Export(ExportAction::Type('q')) => {}
+        _ => panic!("expected to type in the path"),
+    }
+}
+
+fn last_event(keys: &str) -> Event {
+    let key_map = KeyMap::default();
+    let pending_keys = RefCell::new(PendingKeys::default());
+
+    keys.chars()
+        .map(|value| construct(&key_map, &pending_keys, Input::Kb(Key::Char(value), None)))
+        .last()
+        .unwrap()
+}
+
+fn construct(key_map: &KeyMap, pending_keys: &RefCell<PendingKeys>, input: Input) -> Event {
+    let key = match input {
+        Input::Kb(Key::Char(value), _) => value as i32,
+        _ => -1,
+    };
+    EventBuilder::new(input, key, key_map, pending_keys).construct(&NavigationState::Menu)
+}
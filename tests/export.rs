@@ -0,0 +1,108 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+use flow::core::line::Line;
+use flow::core::export::{self, Format};
+use flow::core::filter::Context;
+use flow::core::matcher::{Matcher, MatchMode};
+use flow::core::selection::Kind;
+
+#[test]
+fn formats_lines_as_text() {
+    let line = Line::new("\x1b[31mfailed\x1b[0m user=42".to_string());
+
+    assert_eq!("failed user=42", Format::Text.format_line(&line));
+    assert_eq!("\x1b[31mfailed\x1b[0m user=42", Format::Ansi.format_line(&line));
+}
+
+#[test]
+fn formats_lines_as_json_with_their_fields() {
+    let mut line = Line::new("failed user=42 path=\"/a b\"".to_string());
+    line.received_at = 1475416935;
+
+    let expected = r#"{"fields":{"path":"/a b","user":"42"},"#.to_string() +
+                   r#""line":"failed user=42 path=\"/a b\"","received_at":1475416935}"#;
+
+    assert_eq!(expected, Format::Json.format_line(&line));
+}
+
+#[test]
+fn cycles_through_formats() {
+    assert_eq!(Format::Ansi, Format::Text.next());
+    assert_eq!(Format::Json, Format::Ansi.next());
+    assert_eq!(Format::Text, Format::Json.next());
+}
+
+#[test]
+fn expands_paths_from_the_home_folder() {
+    let mut expected = env::home_dir().unwrap();
+    expected.push("errors.log");
+
+    assert_eq!(expected, export::expand_path("~/errors.log"));
+    assert_eq!("errors.log", export::expand_path("errors.log").to_str().unwrap());
+}
+
+#[test]
+fn saves_lines_to_a_file() {
+    let mut path = env::temp_dir();
+    path.push("flow-export-test.log");
+    let lines = vec![Line::new("first".to_string()), Line::new("second".to_string())];
+
+    let entries = export::entries(lines.iter().collect(),
+                                  |line| line.content_without_ansi == "first");
+
+    let count = export::save(path.to_str().unwrap(), entries, Format::Text).unwrap();
+
+    let mut contents = String::new();
+    File::open(&path).unwrap().read_to_string(&mut contents).unwrap();
+    assert_eq!(2, count);
+    assert_eq!("first\n-- unterminated --\nsecond\n", contents);
+}
+
+#[test]
+fn keeps_the_lines_left_by_a_search_in_filter_mode() {
+    let lines = ["a", "b", "error one", "c", "d", "e", "error two"]
+        .iter()
+        .map(|text| Line::new(text.to_string()))
+        .collect::<Vec<_>>();
+    let entries = || {
+        export::entries(lines.iter().collect(),
+                        |line| line.content_without_ansi == "c")
+    };
+    let matcher = Matcher::new("error", MatchMode::default());
+    let contents = |entries: Vec<(Kind, &Line)>| {
+        entries.iter()
+            .map(|&(kind, line)| match kind {
+                Kind::Separator => "--".to_string(),
+                Kind::Marker => "unterminated".to_string(),
+                _ => line.content_without_ansi.clone(),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(vec!["b", "error one", "c", "--", "e", "error two"],
+               contents(export::filtered(entries(), &matcher, Context::new(1, 1), false)));
+    assert_eq!(vec!["a", "b", "c", "unterminated", "d", "e"],
+               contents(export::filtered(entries(), &matcher, Context::none(), true)));
+}
@@ -0,0 +1,48 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::fields::parse;
+
+#[test]
+fn parses_json_objects() {
+    let fields = parse(r#"10:02:11 INFO {"user": "ana", "status": 200, "tags": ["a"]}"#);
+
+    assert_eq!(vec![("status".to_string(), "200".to_string()),
+                    ("tags".to_string(), "[\"a\"]".to_string()),
+                    ("user".to_string(), "ana".to_string())],
+               fields);
+}
+
+#[test]
+fn parses_key_value_pairs() {
+    let fields = parse(r#"Completed user_id=42 path="/a b" (db.time=1.5ms, cached=true)"#);
+
+    assert_eq!(vec![("user_id".to_string(), "42".to_string()),
+                    ("path".to_string(), "/a b".to_string()),
+                    ("db.time".to_string(), "1.5ms".to_string()),
+                    ("cached".to_string(), "true".to_string())],
+               fields);
+}
+
+#[test]
+fn ignores_lines_without_fields() {
+    assert!(parse("Started GET / for 127.0.0.1").is_empty());
+    assert!(parse("a == b and {not json}").is_empty());
+}
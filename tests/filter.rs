@@ -23,8 +23,8 @@ extern crate lazy_static;
 extern crate flow;
 
 use regex::Regex;
-use flow::core::filter::Filter;
-use flow::core::line::{Line, Parser};
+use flow::core::filter::{Filter, Context};
+use flow::core::line::{Line, LineCollection, Parser};
 
 lazy_static! {
     static ref LINES: Vec<Line> = vec![
@@ -64,6 +64,17 @@ fn decodes_named_filter() {
     assert!(filter.end.is_none());
 }
 
+#[test]
+fn keeps_the_unread_color_by_name() {
+    let filter = toml_string_to_filter(r##"
+       name = "All"
+       unread_color = "blue"
+    "##);
+
+    assert_eq!(Some("blue".to_string()), filter.unread_color);
+    assert_eq!(None, toml_string_to_filter("name = \"All\"").unread_color);
+}
+
 #[test]
 fn decodes_filter_with_content_constraint() {
     let filter = toml_string_to_filter(r##"
@@ -112,6 +123,26 @@ fn decodes_filter_with_boundary_match_constraints() {
     assert!(!filter.end.unwrap().has_named_match);
 }
 
+#[test]
+fn decodes_filter_with_context() {
+    let filter = toml_string_to_filter(r##"
+       name = "Errors"
+       context = 3
+       after_context = 5
+    "##);
+
+    assert_eq!(Context::new(3, 5), filter.context);
+}
+
+#[test]
+fn decodes_filter_without_context() {
+    let filter = toml_string_to_filter(r##"
+       name = "All"
+    "##);
+
+    assert!(filter.context.is_empty());
+}
+
 #[test]
 fn having_no_constraints_matches_everything() {
     let filter = toml_string_to_filter(r##"
@@ -121,7 +152,7 @@ fn having_no_constraints_matches_everything() {
     let lines = vec![Line::new("".to_string()),
                      Line::new("lorem ipsum".to_string()),
                      Line::new("こんにちは！".to_string())];
-    assert_eq!(3, lines.iter().parse(filter).count());
+    assert_eq!(3, lines.iter().parse(&filter).count());
 }
 
 #[test]
@@ -135,7 +166,7 @@ fn having_content_constraint_matches_against_provided_value() {
                      Line::new("Legislature".to_string()),
                      Line::new("Folklore".to_string())];
     let actual = lines.iter()
-        .parse(filter)
+        .parse(&filter)
         .map(|line| line.content_without_ansi.clone())
         .collect::<Vec<_>>();
     let expected = vec!["Folklore", "Lorem ipsum"];
@@ -328,9 +359,90 @@ fn filters_entries_by_main_content_and_boundary_without_matcher() {
     assert_line_content(filter, expected);
 }
 
+#[test]
+fn reassembles_interleaved_blocks_by_group_key() {
+    let filter = toml_string_to_filter(r##"
+       name = "OK requests"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)"
+       group_by = "\\[(?P<id>[0-9a-f]+)\\]"
+    "##);
+
+    let lines = vec!["[a1] Started GET /",
+                     "[b2] Started GET /missing",
+                     "[a1] Rendered index",
+                     "[b2] Completed 404",
+                     "[c3] Started POST /login",
+                     "[a1] Completed 200",
+                     "booting worker",
+                     "[c3] Rendered form"];
+    let expected = vec!["[a1] Completed 200",
+                        "[a1] Rendered index",
+                        "[a1] Started GET /"];
+    assert_parsed_content(&lines, filter, expected);
+}
+
+#[test]
+fn uses_first_group_as_key_when_unnamed() {
+    let filter = toml_string_to_filter(r##"
+       name = "Requests"
+       starts_with = "Started"
+       group_by = "^(\\w+):"
+    "##);
+
+    let lines = vec!["one: Started", "two: Started", "one: body", "two: body"];
+    let expected = vec!["two: body", "two: Started", "one: body", "one: Started"];
+    assert_parsed_content(&lines, filter, expected);
+}
+
+#[test]
+fn emits_blocks_left_open_past_their_max_length() {
+    let filter = toml_string_to_filter(r##"
+       name = "OK requests"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)?"
+       max_block_length = 3
+    "##);
+
+    let lines = vec!["Started GET /c",
+                     "Completed 500",
+                     "Started GET /a",
+                     "Rendered a",
+                     "Started GET /b",
+                     "Completed 200",
+                     "noise",
+                     "Started GET /d"];
+    let expected = vec!["Completed 200", "Started GET /b", "Rendered a", "Started GET /a"];
+    assert_parsed_content(&lines, filter, expected);
+}
+
+#[test]
+fn ignores_open_blocks_without_a_limit() {
+    let filter = toml_string_to_filter(r##"
+       name = "OK requests"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>200)?"
+    "##);
+
+    let lines = vec!["Started GET /a", "Rendered a", "Started GET /b", "Completed 200", "noise"];
+    let expected = vec!["noise", "Completed 200", "Started GET /b"];
+    assert_parsed_content(&lines, filter, expected);
+}
+
+fn assert_parsed_content(lines: &[&str], filter: Filter, expected: Vec<&str>) {
+    let mut collection = LineCollection::new(lines.len());
+    collection.extend(lines.iter().map(|line| line.to_string()));
+    let actual = collection.entries
+        .iter()
+        .parse(&filter)
+        .map(|line| line.content_without_ansi.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(expected, actual);
+}
+
 fn assert_line_content(filter: Filter, expected: Vec<&str>) {
     let actual = LINES.iter()
-        .parse(filter)
+        .parse(&filter)
         .map(|line| line.content_without_ansi.clone())
         .collect::<Vec<_>>();
     assert_eq!(expected, actual);
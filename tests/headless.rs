@@ -0,0 +1,91 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate toml;
+extern crate flow;
+
+use flow::core::filter::Filter;
+use flow::core::headless::Printer;
+
+#[test]
+fn prints_matching_lines() {
+    let mut printer = Printer::new(toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "ERROR"
+    "##),
+                                   100);
+
+    assert_eq!(strings(&["ERROR one"]),
+               printer.matching_lines(strings(&["ok", "ERROR one", "ok"])));
+    assert_eq!(strings(&["ERROR two"]),
+               printer.matching_lines(strings(&["ERROR two"])));
+    assert!(printer.remaining_lines().is_empty());
+}
+
+#[test]
+fn prints_blocks_once_they_end() {
+    let mut printer = Printer::new(toml_string_to_filter(r##"
+       name = "Failed requests"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>500)?"
+    "##),
+                                   100);
+
+    assert!(printer.matching_lines(strings(&["Started GET", "Rendered"])).is_empty());
+    assert_eq!(strings(&["Started GET", "Rendered", "Completed 500"]),
+               printer.matching_lines(strings(&["Completed 500", "noise", "Started POST"])));
+    assert!(printer.remaining_lines().is_empty());
+}
+
+#[test]
+fn holds_back_blocks_without_an_end_until_the_next_one_starts() {
+    let mut printer = Printer::new(toml_string_to_filter(r##"
+       name = "Post requests"
+       starts_with = "Started (?P<matching>POST)?"
+    "##),
+                                   100);
+
+    assert!(printer.matching_lines(strings(&["Started POST", "Rendered"])).is_empty());
+    assert_eq!(strings(&["Started POST", "Rendered", "Completed"]),
+               printer.matching_lines(strings(&["Completed", "Started GET"])));
+    assert!(printer.matching_lines(strings(&["Started POST"])).is_empty());
+    assert_eq!(strings(&["Started POST"]), printer.remaining_lines());
+}
+
+#[test]
+fn keeps_blocks_spanning_several_chunks() {
+    let mut printer = Printer::new(toml_string_to_filter(r##"
+       name = "Failed requests"
+       starts_with = "Started"
+       ends_with = "Completed (?P<matching>500)?"
+    "##),
+                                   6);
+
+    let lines = strings(&["noise", "noise", "Started GET", "Rendered", "Completed 500", "noise"]);
+    assert_eq!(strings(&["Started GET", "Rendered", "Completed 500"]),
+               printer.matching_lines(lines));
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+fn toml_string_to_filter(contents: &str) -> Filter {
+    let parsed_contents = toml::Parser::new(contents).parse().unwrap();
+    toml::decode(toml::Value::Table(parsed_contents)).unwrap()
+}
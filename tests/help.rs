@@ -0,0 +1,108 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate toml;
+
+use flow::core::filter::Filter;
+use flow::ui::help::{self, Section};
+use flow::ui::key_map::KeyMap;
+use flow::ui::navigation::State as NavigationState;
+
+#[test]
+fn lists_keys_of_the_state_help_was_opened_from() {
+    let key_map = KeyMap::default();
+
+    let menu = help::key_section(&key_map, &NavigationState::Menu, true);
+    assert!(has_entry(&menu, "/, Ctrl-F", "Search"));
+    assert!(has_entry(&menu, "?", "Show help"));
+    assert!(has_entry(&menu, "gg", "Go to top"));
+    assert!(has_entry(&menu, "m<letter>", "Set mark"));
+    assert!(has_entry(&menu, "'<letter>", "Jump to mark"));
+    assert!(has_entry(&menu, "Double-click a line", "Copy the line"));
+    assert!(menu.entries.iter().any(|&(ref keys, _)| keys == "<number>"));
+
+    let selection = help::key_section(&key_map, &NavigationState::Selection, true);
+    assert!(has_entry(&selection, "Up, k", "Move cursor up"));
+    assert!(!has_entry(&selection, "Up, k", "Scroll up"));
+
+    let templates = help::key_section(&key_map, &NavigationState::Templates, true);
+    assert!(has_entry(&templates, "Enter", "Open template"));
+    assert!(has_entry(&templates, "Wheel", "Select a template"));
+    assert!(!has_entry(&templates, "/, Ctrl-F", "Search"));
+}
+
+#[test]
+fn leaves_out_the_mouse_unless_its_events_are_read() {
+    let menu = help::key_section(&KeyMap::default(), &NavigationState::Menu, false);
+
+    assert!(!menu.entries.iter().any(|&(ref keys, _)| keys == "Wheel"));
+    assert!(has_entry(&menu, "/, Ctrl-F", "Search"));
+}
+
+#[test]
+fn tells_the_keys_closing_the_help() {
+    let mut key_map = KeyMap::default();
+    assert_eq!("Help  (Escape, q closes)", help::title(&key_map));
+
+    key_map.bind("close_help", vec![]);
+    assert_eq!("Help", help::title(&key_map));
+}
+
+#[test]
+fn lists_filters_with_their_patterns() {
+    let all = toml_string_to_filter(r#"name = "All""#);
+    let requests = toml_string_to_filter(r#"
+        name = "Requests"
+        contains = "GET|POST"
+        starts_with = "^Started"
+        ends_with = "^Completed"
+    "#);
+    let section = help::filter_section(&[&all, &requests]);
+
+    assert_eq!("Filters", section.title);
+    assert!(has_entry(&section, "All", "every line"));
+    assert!(has_entry(&section,
+                      "Requests",
+                      "contains `GET|POST`, starts with `^Started`, ends with `^Completed`"));
+}
+
+#[test]
+fn aligns_entries_within_each_section() {
+    let keys = vec![("q".to_string(), "Quit".to_string()),
+                    ("Ctrl-F".to_string(), "Search".to_string())];
+    let modes = vec![("Long lines".to_string(), "wrap".to_string())];
+    let sections = vec![Section::new("Keys", keys), Section::new("Modes", modes)];
+
+    let expected = vec!["Keys",
+                        "  q       Quit",
+                        "  Ctrl-F  Search",
+                        "",
+                        "Modes",
+                        "  Long lines  wrap"];
+    assert_eq!(expected, help::lines(&sections));
+}
+
+fn has_entry(section: &Section, key: &str, description: &str) -> bool {
+    section.entries.iter().any(|&(ref k, ref d)| k == key && d == description)
+}
+
+fn toml_string_to_filter(contents: &str) -> Filter {
+    let parsed_contents = toml::Parser::new(contents).parse().unwrap();
+    toml::decode(toml::Value::Table(parsed_contents)).unwrap()
+}
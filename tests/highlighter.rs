@@ -0,0 +1,54 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+extern crate flow;
+
+use flow::core::line::Line;
+use flow::core::matcher::{Matcher, MatchMode};
+use flow::core::collapse::Repetition;
+use flow::ui::highlighter::MatchCache;
+
+#[test]
+fn searches_lines_again_once_repeated() {
+    let mut line = Line::new("retrying".to_string());
+    line.id = 3;
+    let mut repetition = Repetition::new(&line);
+    let mut cache = MatchCache::new();
+    cache.prepare(&Matcher::new("×", MatchMode::default()), 80, 0, 1);
+
+    assert!(cache.offsets(&repetition).is_empty());
+
+    repetition.count = 2;
+    assert_eq!(vec![0], *cache.offsets(&repetition));
+    assert_eq!(Some(&vec![0]), cache.get(3));
+}
+
+#[test]
+fn forgets_matches_of_other_queries() {
+    let mut line = Line::new("retrying".to_string());
+    line.id = 3;
+    let repetition = Repetition::new(&line);
+    let mut cache = MatchCache::new();
+    cache.prepare(&Matcher::new("try", MatchMode::default()), 80, 0, 1);
+    cache.offsets(&repetition);
+
+    cache.prepare(&Matcher::new("try", MatchMode::default()), 80, 0, 1);
+    assert_eq!(Some(&vec![0]), cache.get(3));
+
+    cache.prepare(&Matcher::new("ing", MatchMode::default()), 80, 0, 1);
+    assert_eq!(None, cache.get(3));
+}
@@ -0,0 +1,58 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::histogram::{Histogram, Bucket, timestamp};
+use flow::core::line::LineCollection;
+
+#[test]
+fn reads_dates_and_times() {
+    assert_eq!(Some(1475417035), timestamp("2016-10-02T14:03:55.120Z INFO Started"));
+    assert_eq!(Some(50635), timestamp("[14:03:55] INFO Started"));
+    assert_eq!(None, timestamp("INFO Started"));
+}
+
+#[test]
+fn buckets_lines_by_their_timestamps() {
+    let lines = collection(&["10:00:00 Started",
+                             "10:00:01 Completed",
+                             "  at worker.rs:12",
+                             "10:00:03 Started"]);
+    let histogram = Histogram::new(&lines.entries, 2);
+
+    assert_eq!(vec![Bucket { count: 3, first_id: Some(0) }, Bucket { count: 1, first_id: Some(3) }],
+               histogram.buckets);
+    assert_eq!("10:00:00 – 10:00:01", histogram.label(0));
+}
+
+#[test]
+fn leaves_empty_ranges_without_lines() {
+    let lines = collection(&["10:00:00 Started", "10:00:09 Completed"]);
+    let histogram = Histogram::new(&lines.entries, 5);
+
+    assert_eq!(vec![1, 0, 0, 0, 1],
+               histogram.buckets.iter().map(|bucket| bucket.count).collect::<Vec<_>>());
+    assert_eq!(None, histogram.buckets[2].first_id);
+}
+
+fn collection(lines: &[&str]) -> LineCollection {
+    let mut collection = LineCollection::new(lines.len());
+    collection.extend(lines.iter().map(|line| line.to_string()));
+    collection
+}
@@ -0,0 +1,90 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate toml;
+
+use flow::ui::input::{Input, Key, Modifier};
+use flow::ui::key_map::{KeyMap, KeySpec, Action, Scope};
+
+#[test]
+fn parses_key_specs() {
+    assert!(matches("pageup", Input::Kb(Key::PageUp, None)));
+    assert!(matches("Space", Input::Kb(Key::Char(' '), None)));
+    assert!(matches("ctrl-f", Input::Kb(Key::Char('F'), Some(Modifier::Ctrl))));
+    assert!(matches("Alt-n", Input::Kb(Key::Char('n'), Some(Modifier::Alt(110)))));
+    assert!(matches("-", Input::Kb(Key::Char('-'), None)));
+    assert!(!matches("Alt-n", Input::Kb(Key::Char('n'), None)));
+
+    assert!(KeySpec::parse("Ctrl-Left").is_none());
+    assert!(KeySpec::parse("qq").is_none());
+    assert!(KeySpec::parse("").is_none());
+}
+
+#[test]
+fn finds_default_actions_by_scope() {
+    let key_map = KeyMap::default();
+    let slash = Input::Kb(Key::Char('/'), None);
+
+    assert_eq!(Some(Action::Search), key_map.action_for(&slash, Scope::Menu, false));
+    assert_eq!(None, key_map.action_for(&slash, Scope::Search, false));
+    assert_eq!(Some(Action::NextMatch),
+               key_map.action_for(&Input::Kb(Key::Char('n'), Some(Modifier::Alt(110))),
+                                  Scope::Search,
+                                  false));
+}
+
+#[test]
+fn skips_text_keys_while_typing() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("scroll_down", vec![KeySpec::parse("j").unwrap()]);
+    let input = Input::Kb(Key::Char('j'), None);
+
+    assert_eq!(Some(Action::ScrollDown), key_map.action_for(&input, Scope::Global, false));
+    assert_eq!(None, key_map.action_for(&input, Scope::Global, true));
+}
+
+#[test]
+fn describes_bindings_by_scope() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("close_tab", vec![KeySpec::parse("x").unwrap()]);
+    let bindings = key_map.describe(Scope::Menu);
+
+    assert!(bindings.contains(&("/, Ctrl-F".to_string(), "Search".to_string())));
+    assert!(bindings.contains(&("x".to_string(), "Close tab".to_string())));
+    assert!(!bindings.iter().any(|&(_, ref description)| description == "Next match"));
+}
+
+#[test]
+fn prints_bindings_that_can_be_read_back() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("quit", vec![KeySpec::parse("\"").unwrap(), KeySpec::parse("Escape").unwrap()]);
+
+    let contents = key_map.to_toml();
+    assert!(contents.contains("search = [\"/\", \"Ctrl-F\"]"));
+    assert!(contents.contains("quit = [\"\\\"\", \"Escape\"]"));
+
+    let parsed_contents = toml::Parser::new(&contents).parse().unwrap();
+    let keys = parsed_contents.get("keys").unwrap().clone();
+    let parsed_key_map: KeyMap = toml::decode(keys).unwrap();
+    assert_eq!(contents, parsed_key_map.to_toml());
+}
+
+fn matches(spec: &str, input: Input) -> bool {
+    KeySpec::parse(spec).unwrap().matches(&input)
+}
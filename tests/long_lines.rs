@@ -0,0 +1,45 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::ui::long_lines::LongLines;
+
+#[test]
+fn finds_modes_by_name() {
+    assert_eq!(Some(LongLines::Wrap), LongLines::from_name("wrap"));
+    assert_eq!(Some(LongLines::Scroll), LongLines::from_name("scroll"));
+    assert_eq!(Some(LongLines::Truncate), LongLines::from_name("truncate"));
+    assert_eq!(None, LongLines::from_name("hide"));
+}
+
+#[test]
+fn cycles_through_modes() {
+    assert_eq!(LongLines::Scroll, LongLines::Wrap.next());
+    assert_eq!(LongLines::Truncate, LongLines::Scroll.next());
+    assert_eq!(LongLines::Wrap, LongLines::Truncate.next());
+}
+
+#[test]
+fn marks_cut_edges_only_when_truncating() {
+    assert_eq!((false, false), LongLines::Scroll.cut_edges(200, 10, 80));
+    assert_eq!((false, true), LongLines::Truncate.cut_edges(200, 0, 80));
+    assert_eq!((true, true), LongLines::Truncate.cut_edges(200, 10, 80));
+    assert_eq!((true, false), LongLines::Truncate.cut_edges(200, 120, 80));
+    assert_eq!((false, false), LongLines::Truncate.cut_edges(50, 0, 80));
+}
@@ -0,0 +1,79 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::Line;
+use flow::core::matcher::{Matcher, MatchMode};
+
+#[test]
+fn matches_plain_text_literally() {
+    let matcher = Matcher::new("a.c", MatchMode::default());
+
+    assert!(matcher.is_match("xa.cx"));
+    assert!(!matcher.is_match("abc"));
+}
+
+#[test]
+fn matches_regex_of_variable_length() {
+    let mut mode = MatchMode::default();
+    mode.regex = true;
+    let line = Line::new("id=7 and id=1234".to_string());
+
+    let matches = line.matches_for(&Matcher::new(r"id=\d+", mode));
+    assert_eq!(vec![(0, "id=7"), (9, "id=1234")], matches);
+}
+
+#[test]
+fn ignores_empty_regex_matches() {
+    let mut mode = MatchMode::default();
+    mode.regex = true;
+    let matcher = Matcher::new("x*", mode);
+
+    assert!(!matcher.is_match("abc"));
+    assert_eq!(vec![(1, "xx")], matcher.find_all("axxb"));
+}
+
+#[test]
+fn never_matches_invalid_regex() {
+    let mut mode = MatchMode::default();
+    mode.regex = true;
+    let matcher = Matcher::new("(unclosed", mode);
+
+    assert!(!matcher.is_match("(unclosed"));
+}
+
+#[test]
+fn matches_ignoring_case() {
+    let mut mode = MatchMode::default();
+    mode.case_insensitive = true;
+    let matcher = Matcher::new("needle", mode);
+
+    assert_eq!(vec![(4, "NEEDLE"), (15, "Needle")],
+               matcher.find_all("sit NEEDLE and Needle"));
+}
+
+#[test]
+fn matches_whole_words_only() {
+    let mut mode = MatchMode::default();
+    mode.whole_word = true;
+    let matcher = Matcher::new("mel", mode);
+
+    assert!(matcher.is_match("sit amen mel"));
+    assert!(!matcher.is_match("Completed melody"));
+}
@@ -0,0 +1,51 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::rate::RateMeter;
+
+#[test]
+fn averages_the_rate_over_the_last_seconds() {
+    let mut meter = RateMeter::default();
+    meter.record(40, 100);
+    meter.record(10, 103);
+    meter.record(5, 103);
+
+    assert_eq!(11.0, meter.per_second(103));
+    assert_eq!(3.0, meter.per_second(107));
+    assert_eq!(0.0, meter.per_second(200));
+}
+
+#[test]
+fn draws_the_last_minute_oldest_first() {
+    let mut meter = RateMeter::default();
+    meter.record(10, 1000);
+    meter.record(80, 1059);
+
+    assert_eq!("▁  █", meter.sparkline(1059, 4));
+}
+
+#[test]
+fn forgets_seconds_older_than_a_minute() {
+    let mut meter = RateMeter::default();
+    meter.record(10, 1000);
+    meter.record(1, 1060);
+
+    assert_eq!("   █", meter.sparkline(1060, 4));
+}
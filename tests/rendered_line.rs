@@ -0,0 +1,129 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::line::Line;
+use flow::core::matcher::{Matcher, MatchMode};
+use flow::core::filter::Context;
+use flow::ui::rendered_line::{RenderedLineCollection, Kind};
+use flow::ui::printer::Viewport;
+
+#[test]
+fn finds_the_first_line_reaching_into_the_viewport() {
+    let rendered_lines = collection(&[(0, 2), (1, 3), (2, 1)]);
+
+    assert_eq!((0, 0), rendered_lines.first_visible(0));
+    assert_eq!((1, 2), rendered_lines.first_visible(2));
+    assert_eq!((1, 2), rendered_lines.first_visible(4));
+    assert_eq!((2, 5), rendered_lines.first_visible(5));
+    assert_eq!((3, 6), rendered_lines.first_visible(6));
+}
+
+#[test]
+fn places_lines_laid_out_around_the_viewport() {
+    let mut rendered_lines = collection(&[(4, 2), (5, 1)]);
+    rendered_lines.rows_above = 10;
+    rendered_lines.rows_below = 5;
+
+    assert_eq!(18, rendered_lines.height());
+    assert_eq!(12, rendered_lines.row_of(1));
+    assert_eq!((1, 12), rendered_lines.first_visible(12));
+    assert!(rendered_lines.is_laid_out(10, 12));
+    assert!(!rendered_lines.is_laid_out(9, 11));
+    assert!(!rendered_lines.is_laid_out(11, 13));
+}
+
+#[test]
+fn finds_matches_in_view_past_the_lines_left_out_below() {
+    let mut rendered_lines = collection(&[(4, 1), (5, 1)]);
+    rendered_lines.entries[0].found_matches = Some(vec![0]);
+    rendered_lines.rows_above = 10;
+    rendered_lines.rows_below = 5;
+
+    let below = Viewport {
+        reverse_index: 0,
+        visible_height: 5,
+    };
+    let around = Viewport {
+        reverse_index: 5,
+        visible_height: 3,
+    };
+
+    assert!(rendered_lines.viewport_match(&below).is_none());
+    assert_eq!(1, rendered_lines.viewport_match(&around).unwrap().line);
+}
+
+#[test]
+fn measures_the_height_of_newly_received_lines() {
+    let rendered_lines = collection(&[(3, 2), (4, 1), (5, 3)]);
+
+    assert_eq!(4, rendered_lines.height_since(4));
+    assert_eq!(0, rendered_lines.height_since(6));
+}
+
+#[test]
+fn measures_lines_the_way_they_are_wrapped() {
+    let plain = Line::new("x".repeat(20));
+    let tabbed = Line::new("a\tb\tc\td".to_string());
+    let wide = Line::new("ab日本".to_string());
+
+    assert_eq!(2, plain.guess_height(10));
+    assert_eq!(3, plain.guess_height(9));
+    assert_eq!(25, tabbed.width);
+    assert_eq!(2, tabbed.guess_height(10));
+    assert_eq!((1, 8), tabbed.wrapped_position(6, 10));
+    assert_eq!(4, tabbed.guess_height(8));
+    assert_eq!(2, wide.guess_height(5));
+    assert_eq!((1, 0), wide.wrapped_position(5, 5));
+}
+
+#[test]
+fn locates_matching_lines_by_id() {
+    let mut rendered_lines = collection(&[(0, 1), (1, 1), (2, 1), (5, 1)]);
+    let mode = MatchMode { regex: true, ..MatchMode::default() };
+    let filtered = rendered_lines.matching(&Matcher::new("line [05]", mode), Context::new(0, 1));
+
+    assert_eq!(4, filtered.len());
+    assert_eq!(Some(0), filtered.position_of(0));
+    assert_eq!(Some(3), filtered.position_of(5));
+    assert_eq!(None, filtered.position_of(1));
+}
+
+#[test]
+fn keeps_markers_when_excluding_lines() {
+    let mut rendered_lines = collection(&[(0, 1), (1, 1)]);
+    rendered_lines.create_unterminated_marker(1);
+    let mode = MatchMode { regex: true, ..MatchMode::default() };
+    let filtered = rendered_lines.excluding(&Matcher::new("unterminated|line 0", mode));
+
+    assert_eq!(2, filtered.len());
+    assert!(filtered[1].kind == Kind::Marker);
+}
+
+fn collection(lines: &[(usize, i32)]) -> RenderedLineCollection {
+    let mut rendered_lines = RenderedLineCollection::default();
+
+    for &(id, height) in lines {
+        let mut line = Line::new(format!("line {}", id));
+        line.id = id;
+        rendered_lines.create(line, height, None);
+    }
+
+    rendered_lines
+}
@@ -0,0 +1,74 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+
+use flow::core::filter::Context;
+use flow::ui::search::{Layout, input_width};
+use flow::ui::key_map::{KeyMap, KeySpec};
+
+#[test]
+fn spells_out_options_on_wide_terminals() {
+    assert_eq!(Layout::Full, Layout::for_width(120, Context::none(), &KeyMap::default()));
+    assert!(input_width(120, Context::none(), &KeyMap::default()) >= 20);
+}
+
+#[test]
+fn shortens_options_to_single_letters_on_standard_terminals() {
+    assert_eq!(Layout::Compact, Layout::for_width(80, Context::none(), &KeyMap::default()));
+    assert_eq!(56, input_width(80, Context::none(), &KeyMap::default()));
+}
+
+#[test]
+fn drops_options_on_narrow_terminals() {
+    assert_eq!(Layout::Hidden, Layout::for_width(30, Context::none(), &KeyMap::default()));
+    assert_eq!(28, input_width(30, Context::none(), &KeyMap::default()));
+    assert_eq!(1, input_width(2, Context::none(), &KeyMap::default()));
+}
+
+#[test]
+fn makes_room_for_contexts_wider_than_the_steps() {
+    let context = Context::new(250, 1000);
+    let key_map = KeyMap::default();
+    let layout = Layout::for_width(120, context, &key_map);
+
+    assert_eq!(Layout::Full, layout);
+    assert_eq!(81, layout.width(context, &key_map));
+    assert_eq!(120, input_width(120, context, &key_map) + layout.width(context, &key_map) + 1);
+    assert_eq!(Layout::Compact, Layout::for_width(80, context, &key_map));
+}
+
+#[test]
+fn labels_options_with_their_keys() {
+    let mut key_map = KeyMap::default();
+    key_map.bind("toggle_regex_mode", vec![KeySpec::parse("Alt-z").unwrap()]);
+    key_map.bind("toggle_filter_mode", vec![KeySpec::parse("Alt-f").unwrap()]);
+
+    let labels = Layout::Full.labels(&key_map, "0");
+    assert_eq!("Filter Mode", labels[2].text);
+    assert_eq!(Some(0), labels[2].shortcut);
+    assert_eq!("Context 0", labels[4].text);
+    assert_eq!("Regex (Alt-z)", labels[5].text);
+    assert_eq!(None, labels[5].shortcut);
+
+    let compact_labels = Layout::Compact.labels(&key_map, "0");
+    assert_eq!("F", compact_labels[2].text);
+    assert_eq!("Z", compact_labels[5].text);
+    assert!(Layout::Full.width(Context::none(), &key_map) >
+            Layout::Full.width(Context::none(), &KeyMap::default()));
+}
@@ -0,0 +1,90 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate libc;
+extern crate ncurses;
+
+use std::ffi::CString;
+
+use ncurses::*;
+
+use flow::ui::status_bar::{Status, StatusBar, group_digits};
+use flow::ui::navigation::{Navigation, State};
+
+fn status(paused_count: Option<usize>) -> Status {
+    Status {
+        received_count: 1500,
+        held_count: 1500,
+        capacity: 3000,
+        rate: 2.0,
+        tab_rate: 0.5,
+        tab_sparkline: String::new(),
+        paused_count: paused_count,
+        notice: None,
+    }
+}
+
+#[test]
+fn groups_digits_by_thousands() {
+    assert_eq!("0", group_digits(0));
+    assert_eq!("999", group_digits(999));
+    assert_eq!("1,234", group_digits(1234));
+    assert_eq!("1,234,567", group_digits(1_234_567));
+}
+
+#[test]
+fn shows_lines_received_while_paused() {
+    assert!(status(Some(1234)).text().starts_with(" PAUSED, 1,234 new lines · 1500 lines"));
+    assert!(status(None).text().starts_with(" 1500 lines"));
+}
+
+#[test]
+fn shows_notice_first() {
+    let mut status = status(Some(3));
+    status.notice = Some("Saved 42 lines to errors.log".to_string());
+
+    assert!(status.text().starts_with(" Saved 42 lines to errors.log · PAUSED, 3 new lines"));
+}
+
+#[test]
+fn keeps_the_cursor_where_text_is_typed_in() {
+    let null = CString::new("/dev/null").unwrap();
+    let (write, read) = (CString::new("w").unwrap(), CString::new("r").unwrap());
+    let screen = unsafe {
+        newterm(Some("xterm"),
+                libc::fopen(null.as_ptr(), write.as_ptr()) as ll::FILE_p,
+                libc::fopen(null.as_ptr(), read.as_ptr()) as ll::FILE_p)
+    };
+    let mut navigation = Navigation::new(11, 0, &["all".to_string()]);
+    let status_bar = StatusBar::new(10, 0);
+    navigation.change_state(State::Export);
+    navigation.export_prompt.type_char('a');
+
+    status_bar.render(&status(None));
+    navigation.update();
+
+    let (mut row, mut column) = (0, 0);
+    getsyx(&mut row, &mut column);
+    assert_eq!((11, " Export to: a".len() as i32), (row, column));
+
+    status_bar.destroy();
+    navigation.destroy();
+    endwin();
+    delscreen(screen);
+}
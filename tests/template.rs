@@ -0,0 +1,108 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate toml;
+extern crate flow;
+
+use flow::core::template::{cluster, tokenize};
+use flow::core::filter::Filter;
+use flow::core::line::{LineCollection, Parser};
+
+#[test]
+fn replaces_variable_tokens_with_placeholders() {
+    assert_eq!(vec!["GET", "<str>", "from", "<ip>", "took", "<num>ms"],
+               tokenize(r#"GET "/users/5" from 10.0.0.12 took 12.5ms"#));
+    assert_eq!(vec!["job", "<uuid>", "failed", "at", "<hex>"],
+               tokenize("job 0b1d3f6e-93a4-4e63-8b7d-1c0e3c39d5a2 failed at 0x7fff5fbff8a8"));
+}
+
+#[test]
+fn groups_lines_into_templates_ordered_by_frequency() {
+    let lines = collection(&["Connected to primary",
+                             "Retrying job 12",
+                             "Connected to replica",
+                             "Connected to db-3",
+                             "Retrying job 13",
+                             "Shutting down"]);
+    let templates = cluster(&lines.entries);
+
+    assert_eq!(vec![("Connected to <*>".to_string(), vec![0, 2, 3]),
+                    ("Retrying job <num>".to_string(), vec![1, 4]),
+                    ("Shutting down".to_string(), vec![5])],
+               templates.iter()
+                   .map(|template| (template.to_string(), template.ids.clone()))
+                   .collect::<Vec<_>>());
+}
+
+#[test]
+fn keeps_unrelated_lines_of_the_same_length_apart() {
+    let lines = collection(&["Cache warmed up", "Worker 3 started"]);
+
+    assert_eq!(2, cluster(&lines.entries).len());
+}
+
+#[test]
+fn matches_later_lines_of_a_template() {
+    let lines = collection(&[r#"User "bob" logged in from 10.0.0.1"#,
+                             r#"User "alice" logged in from 10.0.0.7"#]);
+    let template = &cluster(&lines.entries)[0];
+    let regex = template.to_regex();
+
+    assert!(regex.is_match(r#"User "carol smith" logged in from 192.168.1.20"#));
+    assert!(!regex.is_match(r#"User "carol" logged out from 192.168.1.20"#));
+    assert!(Filter::templated(template, &every_line()).is_virtual());
+}
+
+#[test]
+fn keeps_the_filter_of_the_source_tab() {
+    let lines = collection(&["error disk 1 full", "info disk 2 full", "error disk 3 full"]);
+    let templates = cluster(&lines.entries);
+    let source = toml_string_to_filter(r##"
+       name = "Errors"
+       contains = "error"
+    "##);
+    let filter = Filter::templated(&templates[0], &source);
+
+    assert_eq!(1, templates.len());
+    assert_eq!(vec![2, 0],
+               lines.entries.iter().parse(&filter).map(|line| line.id).collect::<Vec<_>>());
+}
+
+#[test]
+fn draws_how_lines_spread_over_time() {
+    let lines = collection(&["tick 1", "noise", "noise", "tick 2"]);
+    let templates = cluster(&lines.entries);
+    let tick = templates.iter().find(|template| template.to_string() == "tick <num>").unwrap();
+
+    assert_eq!("█  █", tick.sparkline(0, 4, 4));
+}
+
+fn collection(lines: &[&str]) -> LineCollection {
+    let mut collection = LineCollection::new(lines.len());
+    collection.extend(lines.iter().map(|line| line.to_string()));
+    collection
+}
+
+fn every_line() -> Filter {
+    toml_string_to_filter(r##"name = "All""##)
+}
+
+fn toml_string_to_filter(contents: &str) -> Filter {
+    let parsed_contents = toml::Parser::new(contents).parse().unwrap();
+    toml::decode(toml::Value::Table(parsed_contents)).unwrap()
+}
@@ -0,0 +1,66 @@
+/**
+ * Flow - Realtime log analyzer
+ * Copyright (C) 2016 Daniel Mircea
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+extern crate flow;
+extern crate ncurses;
+
+use ncurses::{COLOR_BLACK, COLOR_BLUE, COLOR_RED, COLOR_WHITE, COLOR_YELLOW};
+use flow::ui::color::{self, COLOR_DEFAULT};
+use flow::ui::theme::{self, Theme, Role};
+
+#[test]
+fn parses_colors() {
+    assert_eq!(Some((COLOR_WHITE, COLOR_BLUE)), theme::parse_colors("white on blue"));
+    assert_eq!(Some((COLOR_YELLOW, COLOR_DEFAULT)), theme::parse_colors("yellow"));
+    assert_eq!(Some((COLOR_DEFAULT, COLOR_BLACK)), theme::parse_colors("default on black"));
+    assert_eq!(None, theme::parse_colors("white on purple"));
+    assert_eq!(None, theme::parse_colors("white on blue on black"));
+}
+
+#[test]
+fn defaults_to_the_dark_theme() {
+    let theme = Theme::default();
+
+    assert_eq!("dark", theme.name);
+    assert_eq!((COLOR_WHITE, COLOR_BLUE), theme.colors(Role::Bar));
+}
+
+#[test]
+fn ships_every_listed_theme() {
+    for name in theme::theme_names() {
+        assert!(Theme::from_name(name).is_some());
+    }
+    assert!(Theme::from_name("neon").is_none());
+}
+
+#[test]
+fn colors_markers_and_context_lines_from_the_theme() {
+    let theme = Theme::from_name("light").unwrap();
+
+    assert_eq!((COLOR_WHITE, COLOR_RED), theme.colors(Role::Marker));
+    assert_eq!((COLOR_BLUE, COLOR_DEFAULT), theme.colors(Role::Context));
+    assert!(theme::key_names().contains(&"unread"));
+    assert!(theme::key_names().contains(&"heading"));
+}
+
+#[test]
+fn turns_colors_off_when_no_color_is_set() {
+    assert!(color::is_allowed(None));
+    assert!(color::is_allowed(Some(String::new())));
+    assert!(!color::is_allowed(Some("1".to_string())));
+}